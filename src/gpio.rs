@@ -4,6 +4,12 @@
 //! accessing the registers through either `/dev/gpiomem` or `/dev/mem`. GPIO interrupts
 //! are configured using the `gpiochip` character device.
 //!
+//! If neither `/dev/gpiomem` nor `/dev/mem` can be accessed, for instance on systems where
+//! direct register access is blocked, RPPAL automatically falls back to the `gpiochip` character
+//! device for reading and writing pin levels as well. This backend only supports the [`Input`]
+//! and [`Output`] modes, can't change the drive strength, and is considerably slower. While an
+//! interrupt is configured for a pin, its level can only be retrieved through the interrupt events.
+//!
 //! ## Pins
 //!
 //! GPIO pins are retrieved from a [`Gpio`] instance by their BCM GPIO number by calling
@@ -18,6 +24,10 @@
 //! (or a derived [`InputPin`], [`OutputPin`] or [`IoPin`]) goes out of scope, it can be
 //! retrieved again through another [`Gpio::get`] call.
 //!
+//! Use [`Gpio::get_physical`] to retrieve a pin by its physical location on the 40-pin GPIO
+//! header instead. [`bcm_from_physical`] and [`physical_from_bcm`] convert between both
+//! numbering schemes.
+//!
 //! By default, pins are reset to their original state when they go out of scope.
 //! Use [`InputPin::set_reset_on_drop(false)`], [`OutputPin::set_reset_on_drop(false)`]
 //! or [`IoPin::set_reset_on_drop(false)`], respectively, to disable this behavior.
@@ -32,10 +42,28 @@
 //! An interrupt trigger for a single pin can be polled with [`InputPin::poll_interrupt`],
 //! which blocks the current thread until a trigger event occurs, or until the timeout period
 //! elapses. [`Gpio::poll_interrupts`] should be used when multiple pins have been configured
-//! for synchronous interrupt triggers, and need to be polled simultaneously.
+//! for synchronous interrupt triggers, and need to be polled simultaneously. Synchronous
+//! interrupt triggers don't spawn any threads, which makes them suitable for single-threaded
+//! control loops.
+//! [`InputPin::drain_events`] retrieves all pending trigger events in a single call without
+//! blocking, which reduces the per-event overhead when decoding high-frequency signals.
 //!
 //! Asynchronous interrupt triggers are configured using [`InputPin::set_async_interrupt`]. The
 //! specified callback function will be executed on a separate thread when a trigger event occurs.
+//! The scheduling policy, priority and CPU affinity of those threads can be configured through
+//! [`Gpio::set_interrupt_thread_priority`] and [`Gpio::set_interrupt_thread_affinity`].
+//!
+//! Each pin can only be configured for one interrupt type at a time. Configuring a synchronous
+//! interrupt trigger clears any asynchronous interrupt trigger on the same pin, and vice versa.
+//!
+//! When the `stream` feature is enabled, [`InputPin::interrupt_stream`] exposes asynchronous
+//! interrupt trigger events as a `Stream`, which can be used with async executors.
+//!
+//! Both interrupt types support an optional debounce period, which filters out any trigger
+//! events caused by contact bounce on mechanical switches and buttons.
+//!
+//! [`Encoder`] uses asynchronous interrupt triggers to decode the position of a quadrature
+//! encoder connected to two (or three, with an index channel) input pins.
 //!
 //! ## Software-based PWM
 //!
@@ -43,6 +71,9 @@
 //! emulated by toggling the pin's output state on a separate thread, combined with sleep and
 //! busy-waiting.
 //!
+//! [`SoftPwm`] wraps an [`OutputPin`] to provide an interface similar to the hardware [`Pwm`]
+//! peripheral, with a configurable frequency and duty cycle.
+//!
 //! Software-based PWM is inherently inaccurate on a multi-threaded OS due to scheduling/preemption.
 //! If an accurate or faster PWM signal is required, use the hardware [`Pwm`] peripheral instead.
 //!
@@ -51,11 +82,26 @@
 //! function call overhead, typical jitter is expected to be up to 10 µs on debug builds, and up to
 //! 2 µs on release builds.
 //!
+//! [`DmaPwm`] offloads the signal generation to a DMA channel, which results in far lower
+//! jitter, and supports any number of pins with a shared period. `DmaPwm` requires superuser
+//! privileges, and isn't supported on the Raspberry Pi 5.
+//!
+//! ## Clock outputs
+//!
+//! [`Clock`] configures one of the general-purpose clock generators (GPCLK0-2) to output
+//! a clock signal on a supported pin, which can be used as a master clock for external devices.
+//!
+//! ## 1-Wire
+//!
+//! [`OneWire`] implements a bit-banged 1-Wire bus master on a single open-drain pin,
+//! which can be used to communicate with devices such as the DS18B20 temperature sensor
+//! without enabling the kernel's `w1-gpio` device tree overlay.
+//!
 //! ## Examples
 //!
 //! Basic example:
 //!
-//! ```
+//! ```no_run
 //! use std::thread;
 //! use std::time::Duration;
 //!
@@ -99,18 +145,32 @@
 //! [raspberrypi/linux#2289]: https://github.com/raspberrypi/linux/issues/2289
 //! [`Gpio`]: struct.Gpio.html
 //! [`Gpio::get`]: struct.Gpio.html#method.get
+//! [`Gpio::get_physical`]: struct.Gpio.html#method.get_physical
+//! [`bcm_from_physical`]: fn.bcm_from_physical.html
+//! [`physical_from_bcm`]: fn.physical_from_bcm.html
 //! [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
 //! [`Pin`]: struct.Pin.html
 //! [`InputPin`]: struct.InputPin.html
 //! [`InputPin::set_reset_on_drop(false)`]: struct.InputPin.html#method.set_reset_on_drop
 //! [`InputPin::set_interrupt`]: struct.InputPin.html#method.set_interrupt
 //! [`InputPin::poll_interrupt`]: struct.InputPin.html#method.poll_interrupt
+//! [`InputPin::drain_events`]: struct.InputPin.html#method.drain_events
 //! [`InputPin::set_async_interrupt`]: struct.InputPin.html#method.set_async_interrupt
+//! [`InputPin::interrupt_stream`]: struct.InputPin.html#method.interrupt_stream
+//! [`Encoder`]: struct.Encoder.html
+//! [`Gpio::set_interrupt_thread_priority`]: struct.Gpio.html#method.set_interrupt_thread_priority
+//! [`Gpio::set_interrupt_thread_affinity`]: struct.Gpio.html#method.set_interrupt_thread_affinity
 //! [`OutputPin`]: struct.OutputPin.html
+//! [`SoftPwm`]: struct.SoftPwm.html
+//! [`DmaPwm`]: struct.DmaPwm.html
+//! [`Clock`]: struct.Clock.html
+//! [`OneWire`]: struct.OneWire.html
 //! [`OutputPin::set_reset_on_drop(false)`]: struct.OutputPin.html#method.set_reset_on_drop
 //! [`IoPin`]: struct.IoPin.html
 //! [`IoPin::set_reset_on_drop(false)`]: struct.IoPin.html#method.set_reset_on_drop
 //! [`Pwm`]: ../pwm/struct.Pwm.html
+//! [`Input`]: enum.Mode.html#variant.Input
+//! [`Output`]: enum.Mode.html#variant.Output
 
 use std::error;
 use std::fmt;
@@ -118,11 +178,16 @@ use std::io;
 use std::mem::MaybeUninit;
 use std::ops::Not;
 use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 use std::result;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex, Once, Weak};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Once, Weak};
 use std::time::Duration;
 
+mod cleanup;
+mod clock;
+mod dma_pwm;
+mod encoder;
 mod epoll;
 mod gpiomem;
 #[cfg(any(
@@ -133,15 +198,29 @@ mod gpiomem;
 mod hal;
 #[cfg(feature = "hal-unproven")]
 mod hal_unproven;
+mod header;
 mod interrupt;
 mod ioctl;
+mod one_wire;
 mod pin;
 mod soft_pwm;
+#[cfg(feature = "stream")]
+mod stream;
+mod sysfs;
 
 use crate::system;
 use crate::system::DeviceInfo;
+use crate::time::Instant;
 
+pub use self::clock::{Clock, ClockSource};
+pub use self::dma_pwm::DmaPwm;
+pub use self::encoder::Encoder;
+pub use self::header::{bcm_from_physical, physical_from_bcm};
+pub use self::one_wire::OneWire;
 pub use self::pin::{InputPin, IoPin, OutputPin, Pin};
+pub use self::soft_pwm::SoftPwm;
+#[cfg(feature = "stream")]
+pub use self::stream::InterruptStream;
 
 /// Errors that can occur when accessing the GPIO peripheral.
 #[derive(Debug)]
@@ -168,6 +247,21 @@ pub enum Error {
     /// [`OutputPin`]: struct.OutputPin.html
     /// [`IoPin`]: struct.IoPin.html
     PinUsed(u8),
+    /// Pin is claimed by another process or a kernel driver.
+    ///
+    /// The `gpiochip` character device reports the pin as requested by another consumer,
+    /// which prevents RPPAL from configuring it. Contains the pin number, and the consumer
+    /// label reported by the kernel, which is usually the name of the process, tool or
+    /// kernel driver that requested the pin, or `sysfs` for pins exported through
+    /// `/sys/class/gpio`. The label may be empty if the consumer didn't provide one.
+    ///
+    /// This error is returned when the pin is retrieved, when an interrupt trigger can't be
+    /// configured, or when the pin is configured while the `gpiochip` character device is
+    /// used as a fallback for the GPIO registers. Pins exported through `/sys/class/gpio`
+    /// can be reclaimed with [`Gpio::get_take_over`].
+    ///
+    /// [`Gpio::get_take_over`]: struct.Gpio.html#method.get_take_over
+    PinClaimed(u8, String),
     /// Pin is not available.
     ///
     /// The GPIO peripheral doesn't expose a GPIO pin with the specified number. Pins are
@@ -177,6 +271,10 @@ pub enum Error {
     /// Permission denied when opening `/dev/gpiomem`, `/dev/mem` or `/dev/gpiochipN` for
     /// read/write access.
     ///
+    /// Contains the path of the device that couldn't be opened. Access to `/dev/gpiomem` and
+    /// `/dev/gpiochipN` is usually granted through membership of the `gpio` group, while
+    /// `/dev/mem` requires superuser privileges.
+    ///
     /// More information on possible causes for this error can be found [here].
     ///
     /// [here]: index.html#permission-denied
@@ -185,6 +283,28 @@ pub enum Error {
     Io(io::Error),
     /// Thread panicked.
     ThreadPanic,
+    /// Invalid or unsupported value.
+    InvalidValue,
+    /// Conflicting logic levels.
+    ///
+    /// The same pin was included multiple times with different logic levels.
+    ConflictingLevels(u8),
+    /// Operation timed out.
+    ///
+    /// The expected trigger event didn't occur before the specified timeout
+    /// period elapsed.
+    TimedOut,
+    /// Measurement incomplete.
+    ///
+    /// The timeout period elapsed before the requested number of trigger events
+    /// was captured. Contains the number of trigger events that were captured.
+    IncompleteMeasurement(usize),
+    /// Pin is configured for hardware PWM.
+    ///
+    /// Software-based PWM can't be enabled on a pin that was configured for its hardware
+    /// PWM function, because the pin is likely controlled by a PWM channel enabled through
+    /// `/boot/config.txt`. Disable the PWM channel, or use a different pin.
+    PwmConflict(u8),
 }
 
 impl fmt::Display for Error {
@@ -192,10 +312,41 @@ impl fmt::Display for Error {
         match *self {
             Error::UnknownModel => write!(f, "Unknown Raspberry Pi model"),
             Error::PinUsed(pin) => write!(f, "Pin {} is already in use", pin),
+            Error::PinClaimed(pin, ref consumer) if consumer.is_empty() => write!(
+                f,
+                "Pin {} is claimed by another process or kernel driver",
+                pin
+            ),
+            Error::PinClaimed(pin, ref consumer) => {
+                write!(f, "Pin {} is claimed by {}", pin, consumer)
+            }
             Error::PinNotAvailable(pin) => write!(f, "Pin {} is not available", pin),
-            Error::PermissionDenied(ref path) => write!(f, "Permission denied: {}", path),
+            Error::PermissionDenied(ref path) if path.starts_with("/dev/mem") => write!(
+                f,
+                "Permission denied: {} (superuser privileges are required)",
+                path
+            ),
+            Error::PermissionDenied(ref path) => write!(
+                f,
+                "Permission denied: {} (make sure the current user is a member of the gpio group, \
+                 or run the application with superuser privileges)",
+                path
+            ),
             Error::Io(ref err) => write!(f, "I/O error: {}", err),
             Error::ThreadPanic => write!(f, "Thread panicked"),
+            Error::InvalidValue => write!(f, "Invalid or unsupported value"),
+            Error::ConflictingLevels(pin) => {
+                write!(f, "Conflicting logic levels specified for pin {}", pin)
+            }
+            Error::TimedOut => write!(f, "Operation timed out"),
+            Error::PwmConflict(pin) => {
+                write!(f, "Pin {} is configured for hardware PWM", pin)
+            }
+            Error::IncompleteMeasurement(captured) => write!(
+                f,
+                "Measurement incomplete (captured {} trigger events)",
+                captured
+            ),
         }
     }
 }
@@ -253,6 +404,10 @@ impl fmt::Display for Mode {
 }
 
 /// Pin logic levels.
+///
+/// `Level` can be converted from and to a `bool`, where `true` corresponds to
+/// `Level::High`, and `false` to `Level::Low`. Converting from a `u8` results in
+/// `Level::Low` for `0`, and `Level::High` for any other value.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[repr(u8)]
 pub enum Level {
@@ -260,6 +415,15 @@ pub enum Level {
     High = 1,
 }
 
+impl Level {
+    /// Returns the opposite logic level.
+    ///
+    /// `toggle` is equivalent to the `!` operator.
+    pub fn toggle(self) -> Level {
+        !self
+    }
+}
+
 impl From<bool> for Level {
     fn from(e: bool) -> Level {
         if e {
@@ -270,6 +434,12 @@ impl From<bool> for Level {
     }
 }
 
+impl From<Level> for bool {
+    fn from(level: Level) -> bool {
+        level == Level::High
+    }
+}
+
 impl fmt::Display for Level {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -338,13 +508,101 @@ impl fmt::Display for Trigger {
     }
 }
 
+/// Interrupt trigger event returned by [`InputPin::drain_events`].
+///
+/// [`InputPin::drain_events`]: struct.InputPin.html#method.drain_events
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct EdgeEvent {
+    /// The pin's logic level right after the edge occurred.
+    pub level: Level,
+    /// The timestamp captured by the kernel when the edge occurred.
+    pub instant: Instant,
+}
+
+/// Interfaces used to access the GPIO peripheral.
+///
+/// The active backend is selected when the first [`Gpio`] instance is constructed,
+/// and can be retrieved through [`Gpio::backend`]. Each variant contains the path
+/// of the device that was opened.
+///
+/// [`Gpio`]: struct.Gpio.html
+/// [`Gpio::backend`]: struct.Gpio.html#method.backend
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Backend {
+    /// The BCM283x or BCM2711 GPIO registers, memory-mapped through `/dev/gpiomem`
+    /// or `/dev/mem`.
+    Bcm(PathBuf),
+    /// The RP1 GPIO registers on the Raspberry Pi 5, memory-mapped through
+    /// `/dev/gpiomem0`.
+    Rp1(PathBuf),
+    /// The gpiochip character device, which is used when the GPIO registers can't
+    /// be memory-mapped. Some features aren't available through the character device.
+    /// Alternate function modes can't be selected, and pins that are switched to an
+    /// alternate function keep their current mode. Failures to read or configure a
+    /// pin are logged as warnings when the `log` feature is enabled.
+    Cdev(PathBuf),
+    /// In-memory registers, which are used when the `mock` feature is enabled and
+    /// the `RPPAL_MOCK` environment variable is set.
+    Mock,
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Backend::Bcm(ref path) => write!(f, "Bcm ({})", path.display()),
+            Backend::Rp1(ref path) => write!(f, "Rp1 ({})", path.display()),
+            Backend::Cdev(ref path) => write!(f, "Cdev ({})", path.display()),
+            Backend::Mock => write!(f, "Mock"),
+        }
+    }
+}
+
+/// Scheduling policies for the threads that handle asynchronous interrupts.
+///
+/// More information on each policy can be found in the `sched(7)` man page.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SchedPolicy {
+    /// The default time-sharing policy (`SCHED_OTHER`).
+    Other,
+    /// First-in, first-out real-time policy (`SCHED_FIFO`).
+    Fifo,
+    /// Round-robin real-time policy (`SCHED_RR`).
+    RoundRobin,
+}
+
+impl SchedPolicy {
+    pub(crate) fn as_raw(self) -> i32 {
+        match self {
+            SchedPolicy::Other => libc::SCHED_OTHER,
+            SchedPolicy::Fifo => libc::SCHED_FIFO,
+            SchedPolicy::RoundRobin => libc::SCHED_RR,
+        }
+    }
+}
+
+impl fmt::Display for SchedPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SchedPolicy::Other => write!(f, "Other"),
+            SchedPolicy::Fifo => write!(f, "Fifo"),
+            SchedPolicy::RoundRobin => write!(f, "RoundRobin"),
+        }
+    }
+}
+
+// GPIO registers, gpiochip device and number of GPIO lines used to initialize GpioState
+type OpenedBackend = (Box<dyn gpiomem::GpioRegisters>, std::fs::File, u8);
+
 // Store Gpio's state separately, so we can conveniently share it through
 // a cloned Arc.
 pub(crate) struct GpioState {
     gpio_mem: Box<dyn gpiomem::GpioRegisters>,
     cdev: std::fs::File,
     sync_interrupts: Mutex<interrupt::EventLoop>,
+    interrupt_thread: Mutex<interrupt::ThreadConfig>,
     pins_taken: [AtomicBool; u8::MAX as usize],
+    // Action the cleanup handler takes for each pin that's in use
+    pins_cleanup: [AtomicU8; u8::MAX as usize],
     gpio_lines: u8,
 }
 
@@ -354,12 +612,39 @@ impl fmt::Debug for GpioState {
             .field("gpio_mem", &self.gpio_mem)
             .field("cdev", &self.cdev)
             .field("sync_interrupts", &self.sync_interrupts)
+            .field("interrupt_thread", &self.interrupt_thread)
             .field("pins_taken", &format_args!("{{ .. }}"))
+            .field("pins_cleanup", &format_args!("{{ .. }}"))
             .field("gpio_lines", &self.gpio_lines)
             .finish()
     }
 }
 
+// Returns the shared state between Gpio and Pin instances. GpioState is dropped after
+// all Gpio and Pin instances go out of scope, guaranteeing we won't have any pins
+// simultaneously using different EventLoop or GpioMem instances.
+fn gpio_state() -> MutexGuard<'static, Weak<GpioState>> {
+    // Replace this when std::sync::SyncLazy is stabilized. https://github.com/rust-lang/rust/issues/74465
+    static mut GPIO_STATE: MaybeUninit<Mutex<Weak<GpioState>>> = MaybeUninit::uninit();
+    static ONCE: Once = Once::new();
+
+    // call_once is thread-safe, guaranteed to be called only once, and memory writes performed
+    // by the closure can be observed by other threads after execution completes.
+    unsafe {
+        ONCE.call_once(|| {
+            GPIO_STATE.write(Mutex::new(Weak::new()));
+        });
+
+        // GPIO_STATE will always be initialized at this point.
+        // The Weak reference can't be left in an inconsistent state, so a poisoned lock
+        // is recovered. This also keeps the cleanup handler from panicking.
+        GPIO_STATE
+            .assume_init_ref()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
 /// Provides access to the Raspberry Pi's GPIO peripheral.
 #[derive(Clone, Debug)]
 pub struct Gpio {
@@ -369,24 +654,40 @@ pub struct Gpio {
 impl Gpio {
     /// Constructs a new `Gpio`.
     pub fn new() -> Result<Gpio> {
-        // Replace this when std::sync::SyncLazy is stabilized. https://github.com/rust-lang/rust/issues/74465
-
-        // Shared state between Gpio and Pin instances. GpioState is dropped after
-        // all Gpio and Pin instances go out of scope, guaranteeing we won't have
-        // any pins simultaneously using different EventLoop or GpioMem instances.
-        static mut GPIO_STATE: MaybeUninit<Mutex<Weak<GpioState>>> = MaybeUninit::uninit();
-        static ONCE: Once = Once::new();
-
-        // call_once is thread-safe, guaranteed to be called only once, and memory writes performed
-        // by the closure can be observed by other threads after execution completes.
-        let mut weak_state = unsafe {
-            ONCE.call_once(|| {
-                GPIO_STATE.write(Mutex::new(Weak::new()));
-            });
+        Gpio::open(None, None)
+    }
 
-            // GPIO_STATE will always be initialized at this point.
-            GPIO_STATE.assume_init_ref().lock().unwrap()
-        };
+    /// Constructs a new `Gpio` using the specified device paths.
+    ///
+    /// By default, [`new`] memory-maps the GPIO registers through `/dev/gpiomem` (or
+    /// `/dev/gpiomem0` on the Raspberry Pi 5) or `/dev/mem`, and searches `/dev/gpiochip*`
+    /// for the gpiochip device that controls the GPIO header pins. `with_device` can be used
+    /// to override either of those, and falls back to the default behavior for any path
+    /// that's set to `None`.
+    ///
+    /// `gpiomem` needs to point to a device that maps the GPIO registers at offset 0,
+    /// similar to `/dev/gpiomem`. If the registers can't be memory-mapped, `with_device`
+    /// falls back to the gpiochip character device, the same way [`new`] does.
+    ///
+    /// `gpiochip` is used as is, without checking whether its label matches any of the
+    /// known gpiochip drivers.
+    ///
+    /// The Raspberry Pi model still needs to be identified, because the register layout
+    /// depends on the SoC. `with_device` returns `Err(`[`Error::UnknownModel`]`)` if that
+    /// isn't possible.
+    ///
+    /// All `Gpio` instances share the same underlying state. If another `Gpio` instance or
+    /// any of its pins are still in scope, `with_device` ignores the specified paths, and
+    /// returns a `Gpio` that shares the existing state.
+    ///
+    /// [`new`]: #method.new
+    /// [`Error::UnknownModel`]: enum.Error.html#variant.UnknownModel
+    pub fn with_device(gpiomem: Option<&Path>, gpiochip: Option<&Path>) -> Result<Gpio> {
+        Gpio::open(gpiomem, gpiochip)
+    }
+
+    fn open(gpiomem: Option<&Path>, gpiochip: Option<&Path>) -> Result<Gpio> {
+        let mut weak_state = gpio_state();
 
         // Clone a strong reference if a GpioState instance already exists, otherwise
         // initialize it here so we can return any relevant errors.
@@ -395,26 +696,34 @@ impl Gpio {
                 inner: state.clone(),
             })
         } else {
-            let device_info = DeviceInfo::new().map_err(|_| Error::UnknownModel)?;
+            let (gpio_mem, cdev, gpio_lines) = match Gpio::open_mock()? {
+                Some(mock) => mock,
+                None => {
+                    let device_info = DeviceInfo::new().map_err(|_| Error::UnknownModel)?;
+                    let cdev = match gpiochip {
+                        Some(path) => ioctl::open_gpiochip(path)?,
+                        None => ioctl::find_gpiochip()?,
+                    };
+                    let gpio_mem = Gpio::open_gpio_mem(&device_info, gpiomem, &cdev)?;
 
-            let gpio_mem: Box<dyn gpiomem::GpioRegisters> = match device_info.gpio_interface() {
-                system::GpioInterface::Bcm => Box::new(gpiomem::bcm::GpioMem::open()?),
-                system::GpioInterface::Rp1 => Box::new(gpiomem::rp1::GpioMem::open()?),
+                    (gpio_mem, cdev, device_info.gpio_lines())
+                }
             };
 
-            let cdev = ioctl::find_gpiochip()?;
             let sync_interrupts = Mutex::new(interrupt::EventLoop::new(
                 cdev.as_raw_fd(),
                 u8::MAX as usize,
             )?);
             let pins_taken = init_array!(AtomicBool::new(false), u8::MAX as usize);
-            let gpio_lines = device_info.gpio_lines();
+            let pins_cleanup = init_array!(AtomicU8::new(cleanup::ACTION_RESET), u8::MAX as usize);
 
             let gpio_state = Arc::new(GpioState {
                 gpio_mem,
                 cdev,
                 sync_interrupts,
+                interrupt_thread: Mutex::new(interrupt::ThreadConfig::default()),
                 pins_taken,
+                pins_cleanup,
                 gpio_lines,
             });
 
@@ -426,6 +735,64 @@ impl Gpio {
         }
     }
 
+    // Opens the in-memory mock backend if it's explicitly requested through the
+    // RPPAL_MOCK environment variable. Interrupts aren't supported, because there's
+    // no gpiochip device. /dev/null only provides a file descriptor for the event loop.
+    #[cfg(feature = "mock")]
+    fn open_mock() -> Result<Option<OpenedBackend>> {
+        if !crate::mock::is_requested() {
+            return Ok(None);
+        }
+
+        Ok(Some((
+            Box::new(gpiomem::mock::GpioMem::new()),
+            std::fs::File::open("/dev/null")?,
+            gpiomem::mock::GPIO_LINES,
+        )))
+    }
+
+    #[cfg(not(feature = "mock"))]
+    fn open_mock() -> Result<Option<OpenedBackend>> {
+        Ok(None)
+    }
+
+    // Memory-maps the GPIO registers. If access to the registers is blocked, fall back
+    // to the gpiochip character device instead, which supports a limited set of features.
+    fn open_gpio_mem(
+        device_info: &DeviceInfo,
+        gpiomem: Option<&Path>,
+        cdev: &std::fs::File,
+    ) -> Result<Box<dyn gpiomem::GpioRegisters>> {
+        let gpio_mem: Result<Box<dyn gpiomem::GpioRegisters>> = match device_info.gpio_interface() {
+            system::GpioInterface::Bcm => {
+                gpiomem::bcm::GpioMem::open(gpiomem).map(|gpio_mem| Box::new(gpio_mem) as _)
+            }
+            system::GpioInterface::Rp1 => {
+                gpiomem::rp1::GpioMem::open(gpiomem).map(|gpio_mem| Box::new(gpio_mem) as _)
+            }
+        };
+
+        match gpio_mem {
+            Err(err @ Error::PermissionDenied(_)) | Err(err @ Error::Io(_)) => {
+                match gpiomem::cdev::GpioMem::open(cdev, device_info.gpio_lines()) {
+                    Ok(gpio_mem) => Ok(Box::new(gpio_mem)),
+                    Err(_) => Err(err),
+                }
+            }
+            gpio_mem => gpio_mem,
+        }
+    }
+
+    /// Returns the interface that's used to access the GPIO peripheral.
+    ///
+    /// `backend` can be used to confirm whether the GPIO registers are accessed
+    /// directly through memory-mapped I/O, or whether `Gpio` fell back to the slower
+    /// gpiochip character device, which is the case when the memory-mapped registers
+    /// aren't accessible.
+    pub fn backend(&self) -> Backend {
+        self.inner.gpio_mem.backend()
+    }
+
     /// Returns a [`Pin`] for the specified BCM GPIO number.
     ///
     /// Retrieving a GPIO pin grants access to the pin through an owned [`Pin`] instance.
@@ -433,12 +800,33 @@ impl Gpio {
     /// After a [`Pin`] (or a derived [`InputPin`], [`OutputPin`] or [`IoPin`]) goes out
     /// of scope, it can be retrieved again through another `get` call.
     ///
+    /// `get` returns `Err(`[`Error::PinClaimed`]`)` if the `gpiochip` character device
+    /// reports the pin as requested by another process or a kernel driver, such as
+    /// `i2c_bcm2835`. This check is performed for every backend, even though the GPIO
+    /// registers can still be accessed directly when they're memory-mapped. Pins requested
+    /// by other processes that use RPPAL can't be detected, because they share the same
+    /// consumer label.
+    ///
     /// [`Pin`]: struct.Pin.html
     /// [`InputPin`]: struct.InputPin.html
     /// [`OutputPin`]: struct.OutputPin.html
     /// [`IoPin`]: struct.IoPin.html
     /// [`Error::PinUsed`]: enum.Error.html#variant.PinUsed
+    /// [`Error::PinClaimed`]: enum.Error.html#variant.PinClaimed
     pub fn get(&self, pin: u8) -> Result<Pin> {
+        let pin = self.reserve(pin)?;
+
+        match self.line_consumer(pin.pin()) {
+            // Pin is claimed by another process or kernel driver. Dropping pin
+            // releases the reservation.
+            Some(consumer) => Err(Error::PinClaimed(pin.pin(), consumer)),
+            None => Ok(pin),
+        }
+    }
+
+    // Reserves the pin for the current process, without checking whether it's
+    // claimed by another process or kernel driver
+    fn reserve(&self, pin: u8) -> Result<Pin> {
         if pin >= self.inner.gpio_lines {
             return Err(Error::PinNotAvailable(pin));
         }
@@ -456,6 +844,364 @@ impl Gpio {
         }
     }
 
+    // Returns the consumer that claimed the pin, as reported by the gpiochip character
+    // device. The mock backend doesn't have a gpiochip device, so any errors are ignored.
+    fn line_consumer(&self, pin: u8) -> Option<String> {
+        ioctl::line_consumer(self.inner.cdev.as_raw_fd(), pin)
+            .ok()
+            .flatten()
+    }
+
+    /// Returns a [`Pin`] for each of the specified BCM GPIO numbers.
+    ///
+    /// `get_all` reserves the pins in the order they're specified. If any of the pins
+    /// can't be retrieved, all pins that were already reserved by this call are released
+    /// before the error is returned, so either all pins are retrieved, or none are.
+    ///
+    /// Returns `Err(`[`Error::PinUsed`]`)` if any of the pins is already in use, including
+    /// when the same BCM GPIO number is specified more than once, or
+    /// `Err(`[`Error::PinNotAvailable`]`)` if any of the pins isn't available.
+    ///
+    /// [`Pin`]: struct.Pin.html
+    /// [`Error::PinUsed`]: enum.Error.html#variant.PinUsed
+    /// [`Error::PinNotAvailable`]: enum.Error.html#variant.PinNotAvailable
+    pub fn get_all(&self, pins: &[u8]) -> Result<Vec<Pin>> {
+        // Any pins retrieved before an error occurs are released when the
+        // partially collected Vec is dropped.
+        pins.iter().map(|&pin| self.get(pin)).collect()
+    }
+
+    /// Returns a [`Pin`] for the specified BCM GPIO number, and reclaims the pin if it's
+    /// still reserved through the sysfs GPIO interface.
+    ///
+    /// Pins retrieved through [`get`] are only reserved for the current process, and are
+    /// automatically released when the process exits, even if it's abnormally terminated.
+    /// However, pins exported through `/sys/class/gpio` by other tools or scripts stay
+    /// reserved until they're explicitly unexported, which prevents interrupts from being
+    /// configured. `get_take_over` unexports the pin if necessary, and resets it to a known
+    /// state by changing its mode to [`Mode::Input`] and disabling the built-in pull-up/pull-down
+    /// resistors.
+    ///
+    /// Make sure no other process is actively using the pin before taking it over.
+    ///
+    /// If the pin is already in use by the current process, `get_take_over` returns
+    /// `Err(`[`Error::PinUsed`]`)`. If the pin is still claimed by another process or a
+    /// kernel driver after it's unexported, `get_take_over` returns
+    /// `Err(`[`Error::PinClaimed`]`)`.
+    ///
+    /// [`Pin`]: struct.Pin.html
+    /// [`get`]: #method.get
+    /// [`Mode::Input`]: enum.Mode.html#variant.Input
+    /// [`Error::PinUsed`]: enum.Error.html#variant.PinUsed
+    /// [`Error::PinClaimed`]: enum.Error.html#variant.PinClaimed
+    pub fn get_take_over(&self, pin: u8) -> Result<Pin> {
+        // The sysfs interface shows up as a consumer, so the pin is unexported
+        // before checking for any remaining claims
+        let pin = self.reserve(pin)?;
+
+        #[cfg(feature = "mock")]
+        let is_mock = self.is_mock();
+        #[cfg(not(feature = "mock"))]
+        let is_mock = false;
+
+        if !is_mock {
+            sysfs::unexport(pin.pin())?;
+        }
+
+        if let Some(consumer) = self.line_consumer(pin.pin()) {
+            return Err(Error::PinClaimed(pin.pin(), consumer));
+        }
+
+        self.inner.gpio_mem.set_mode(pin.pin(), Mode::Input);
+        self.inner.gpio_mem.set_bias(pin.pin(), Bias::Off);
+
+        Ok(pin)
+    }
+
+    /// Sets the scheduling policy and priority for the threads that handle asynchronous
+    /// interrupts.
+    ///
+    /// Asynchronous interrupt triggers configured through [`InputPin::set_async_interrupt`],
+    /// [`InputPin::interrupt_stream`] and similar methods each use a separate thread to wait
+    /// for trigger events and execute the callback. By default, those threads use the same
+    /// scheduling policy as the thread that configured the interrupt, which means they
+    /// compete with every other thread for CPU time. Switching to a real-time policy such as
+    /// [`SchedPolicy::Fifo`] reduces the latency between a trigger event and the execution
+    /// of the callback. The timestamps captured by the kernel aren't affected by the
+    /// scheduling policy.
+    ///
+    /// `priority` should be within the range supported by `policy`, which is 1 to 99 for
+    /// the real-time policies, and 0 for [`SchedPolicy::Other`]. Out of range values return
+    /// `Err(`[`Error::InvalidValue`]`)`.
+    ///
+    /// The new settings only apply to interrupt triggers configured after calling this
+    /// method. Real-time policies usually require superuser privileges or the
+    /// `CAP_SYS_NICE` capability. If the settings can't be applied, configuring an
+    /// asynchronous interrupt trigger returns an `Err(`[`Error::Io`]`)`.
+    ///
+    /// [`InputPin::set_async_interrupt`]: struct.InputPin.html#method.set_async_interrupt
+    /// [`InputPin::interrupt_stream`]: struct.InputPin.html#method.interrupt_stream
+    /// [`SchedPolicy::Fifo`]: enum.SchedPolicy.html#variant.Fifo
+    /// [`SchedPolicy::Other`]: enum.SchedPolicy.html#variant.Other
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn set_interrupt_thread_priority(&self, policy: SchedPolicy, priority: u8) -> Result<()> {
+        let (min, max) = unsafe {
+            (
+                libc::sched_get_priority_min(policy.as_raw()),
+                libc::sched_get_priority_max(policy.as_raw()),
+            )
+        };
+
+        if i32::from(priority) < min || i32::from(priority) > max {
+            return Err(Error::InvalidValue);
+        }
+
+        self.inner.interrupt_thread.lock().unwrap().priority = Some((policy, priority));
+
+        Ok(())
+    }
+
+    /// Restricts the threads that handle asynchronous interrupts to the specified CPU cores.
+    ///
+    /// Combined with a real-time scheduling policy set through
+    /// [`set_interrupt_thread_priority`], pinning the interrupt threads to a core that's
+    /// isolated from the scheduler (for instance through the `isolcpus` kernel parameter)
+    /// further reduces latency.
+    ///
+    /// Setting `cpus` to an empty slice removes any previously configured restrictions.
+    /// Returns `Err(`[`Error::InvalidValue`]`)` if any of the CPU numbers is out of range.
+    ///
+    /// The new settings only apply to interrupt triggers configured after calling this
+    /// method.
+    ///
+    /// [`set_interrupt_thread_priority`]: #method.set_interrupt_thread_priority
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_interrupt_thread_affinity(&self, cpus: &[usize]) -> Result<()> {
+        if cpus.iter().any(|&cpu| cpu >= interrupt::MAX_CPUS) {
+            return Err(Error::InvalidValue);
+        }
+
+        self.inner.interrupt_thread.lock().unwrap().affinity = if cpus.is_empty() {
+            None
+        } else {
+            Some(cpus.to_vec())
+        };
+
+        Ok(())
+    }
+
+    /// Installs a signal handler that resets all output pins to inputs when the process
+    /// receives a `SIGINT` or `SIGTERM` signal.
+    ///
+    /// By default, these signals terminate the process without running any destructors,
+    /// which leaves output pins in their current state, even if [`OutputPin::set_reset_on_drop`]
+    /// is enabled. This can be dangerous when the pins control motors, heaters or other
+    /// actuators.
+    ///
+    /// After installing the handler, any pin that's still in use by the current process and
+    /// configured as an output when one of the signals is received is changed to
+    /// [`Mode::Input`], so its output is no longer driven. Pins that have `reset_on_drop`
+    /// disabled are left unchanged, and output pins with a drop state configured through
+    /// [`OutputPin::set_drop_state`] are driven to that logic level instead, the same way
+    /// they would be when they go out of scope. A software-based PWM signal on such a pin
+    /// isn't stopped, but is only able to change the level until the process is terminated. The process is then terminated
+    /// the same way it would've been terminated without the handler. To keep the handler
+    /// async-signal-safe, the pins are reset on a separate thread, which is spawned when
+    /// `install_cleanup_handler` is first called.
+    ///
+    /// The handler replaces any existing handlers for `SIGINT` and `SIGTERM`, and remains
+    /// installed until the process exits. Calling `install_cleanup_handler` more than once
+    /// has no effect. Signals that can't be caught, such as `SIGKILL`, still terminate the
+    /// process immediately.
+    ///
+    /// [`OutputPin::set_reset_on_drop`]: struct.OutputPin.html#method.set_reset_on_drop
+    /// [`OutputPin::set_drop_state`]: struct.OutputPin.html#method.set_drop_state
+    /// [`Mode::Input`]: enum.Mode.html#variant.Input
+    pub fn install_cleanup_handler(&self) -> Result<()> {
+        cleanup::install()
+    }
+
+    /// Returns `true` if `Gpio` is using the in-memory mock backend.
+    ///
+    /// When the `mock` feature is enabled, and the `RPPAL_MOCK` environment variable is set
+    /// to a non-empty value, [`new`] and [`with_device`] use an in-memory mock backend
+    /// instead of the GPIO registers, even if a supported Raspberry Pi model is detected.
+    /// This allows code that uses the GPIO peripheral to be tested on other systems. Without
+    /// the environment variable, [`new`] returns [`Error::UnknownModel`] on unsupported
+    /// systems as usual.
+    ///
+    /// The mock backend emulates 54 GPIO pins. It stores each pin's mode, output state and
+    /// pull-up/pull-down resistor configuration. Input pins read the level applied through
+    /// [`set_mock_input_level`], or follow their pull-up/pull-down configuration if no
+    /// level has been applied. Output level changes are recorded, and can be retrieved
+    /// through [`take_mock_transitions`]. Interrupts and GPCLK outputs aren't supported.
+    ///
+    /// `is_mock` is only available when the `mock` feature is enabled.
+    ///
+    /// [`new`]: #method.new
+    /// [`with_device`]: #method.with_device
+    /// [`Error::UnknownModel`]: enum.Error.html#variant.UnknownModel
+    /// [`set_mock_input_level`]: #method.set_mock_input_level
+    /// [`take_mock_transitions`]: #method.take_mock_transitions
+    #[cfg(feature = "mock")]
+    pub fn is_mock(&self) -> bool {
+        self.inner.gpio_mem.as_mock().is_some()
+    }
+
+    /// Applies an external logic level to the specified pin on the mock backend.
+    ///
+    /// The level is returned when the pin is read while it's not configured as an output.
+    /// Setting `level` to `None` removes the external level, after which the pin follows
+    /// its pull-up/pull-down configuration.
+    ///
+    /// Returns [`Error::InvalidValue`] if `Gpio` isn't using the mock backend, or
+    /// [`Error::PinNotAvailable`] if the pin number is out of range.
+    ///
+    /// `set_mock_input_level` is only available when the `mock` feature is enabled.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    /// [`Error::PinNotAvailable`]: enum.Error.html#variant.PinNotAvailable
+    #[cfg(feature = "mock")]
+    pub fn set_mock_input_level(&self, pin: u8, level: Option<Level>) -> Result<()> {
+        let mock = self.inner.gpio_mem.as_mock().ok_or(Error::InvalidValue)?;
+
+        if pin >= self.inner.gpio_lines {
+            return Err(Error::PinNotAvailable(pin));
+        }
+
+        mock.set_input_level(pin, level);
+
+        Ok(())
+    }
+
+    /// Returns and clears the output transitions recorded by the mock backend.
+    ///
+    /// Every time a pin that's configured as an output changes its level, or a pin is
+    /// switched to [`Mode::Output`], the mock backend records the pin number and its new
+    /// output level. Transitions are returned in the order they occurred.
+    ///
+    /// Returns [`Error::InvalidValue`] if `Gpio` isn't using the mock backend.
+    ///
+    /// `take_mock_transitions` is only available when the `mock` feature is enabled.
+    ///
+    /// [`Mode::Output`]: enum.Mode.html#variant.Output
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    #[cfg(feature = "mock")]
+    pub fn take_mock_transitions(&self) -> Result<Vec<(u8, Level)>> {
+        let mock = self.inner.gpio_mem.as_mock().ok_or(Error::InvalidValue)?;
+
+        Ok(mock.take_transitions())
+    }
+
+    /// Returns the current mode of the specified BCM GPIO pin.
+    ///
+    /// Unlike [`Pin::mode`], `mode` doesn't require ownership of the pin, and can be used
+    /// to inspect pins that are currently in use elsewhere, or that are controlled by
+    /// another peripheral or process.
+    ///
+    /// To configure a pin for a specific mode, including any of the alternate functions
+    /// that aren't wrapped by RPPAL, retrieve the pin with [`get`], and convert it to an
+    /// [`IoPin`] using [`Pin::into_io`]. If the pin should keep its mode after the [`IoPin`]
+    /// goes out of scope, call [`IoPin::set_reset_on_drop(false)`].
+    ///
+    /// Returns [`Error::PinNotAvailable`] if the GPIO peripheral doesn't expose a pin with
+    /// the specified number.
+    ///
+    /// [`Pin::mode`]: struct.Pin.html#method.mode
+    /// [`get`]: #method.get
+    /// [`IoPin`]: struct.IoPin.html
+    /// [`Pin::into_io`]: struct.Pin.html#method.into_io
+    /// [`IoPin::set_reset_on_drop(false)`]: struct.IoPin.html#method.set_reset_on_drop
+    /// [`Error::PinNotAvailable`]: enum.Error.html#variant.PinNotAvailable
+    pub fn mode(&self, pin: u8) -> Result<Mode> {
+        if pin >= self.inner.gpio_lines {
+            return Err(Error::PinNotAvailable(pin));
+        }
+
+        Ok(self.inner.gpio_mem.mode(pin))
+    }
+
+    /// Returns a [`Pin`] for the specified physical pin on the 40-pin GPIO header.
+    ///
+    /// `get_physical` converts `header_pin` to a BCM GPIO number using [`bcm_from_physical`],
+    /// and then calls [`get`]. Returns [`Error::InvalidValue`] if `header_pin` is a power or
+    /// ground pin, or if it's outside of the range `1` to `40`.
+    ///
+    /// [`Pin`]: struct.Pin.html
+    /// [`bcm_from_physical`]: fn.bcm_from_physical.html
+    /// [`get`]: #method.get
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn get_physical(&self, header_pin: u8) -> Result<Pin> {
+        self.get(bcm_from_physical(header_pin).ok_or(Error::InvalidValue)?)
+    }
+
+    /// Sets the logic level of multiple output pins simultaneously.
+    ///
+    /// Instead of changing each pin individually, `write_pins` combines all pins that should be
+    /// set high into a single write to the relevant GPSET register, and all pins that should be set
+    /// low into a single write to the relevant GPCLR register. Pins that aren't included in `pins`
+    /// aren't affected.
+    ///
+    /// On the BCM283x and BCM2711, GPIO 0-31 and GPIO 32-57 are controlled through separate registers,
+    /// so pins in different banks are updated using separate writes. Pins that are set high are updated
+    /// before pins that are set low, which means a small amount of skew between those groups is unavoidable.
+    ///
+    /// Open-drain outputs are emulated by switching the pin's mode, which can't be combined
+    /// with a single register write. Driving an open-drain pin high could short it against
+    /// other devices on the same line, so `write_pins` doesn't accept open-drain pins.
+    ///
+    /// Returns [`Error::ConflictingLevels`] if the same pin is included more than once with different logic
+    /// levels, or [`Error::InvalidValue`] if any of the pins has open-drain output enabled through
+    /// [`OutputPin::set_open_drain`]. No pins are changed if an error is returned.
+    ///
+    /// [`Error::ConflictingLevels`]: enum.Error.html#variant.ConflictingLevels
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    /// [`OutputPin::set_open_drain`]: struct.OutputPin.html#method.set_open_drain
+    pub fn write_pins(&self, pins: &[(&OutputPin, Level)]) -> Result<()> {
+        let mut high = 0u64;
+        let mut low = 0u64;
+
+        for (pin, level) in pins {
+            if pin.is_open_drain() {
+                return Err(Error::InvalidValue);
+            }
+
+            let mask = 1u64 << pin.pin();
+
+            match level {
+                Level::High => high |= mask,
+                Level::Low => low |= mask,
+            }
+
+            if high & low & mask > 0 {
+                return Err(Error::ConflictingLevels(pin.pin()));
+            }
+        }
+
+        self.inner.gpio_mem.set_levels(high, low);
+
+        Ok(())
+    }
+
+    /// Reads the logic level of multiple input pins simultaneously.
+    ///
+    /// Instead of reading each pin individually, `read_pins` takes a single snapshot of the relevant
+    /// GPLEV registers, and decodes the logic level of each of the specified pins from that snapshot.
+    /// The returned levels are in the same order as `pins`.
+    ///
+    /// On the BCM283x and BCM2711, the levels of GPIO 0-31 and GPIO 32-57 are stored in separate
+    /// registers (GPLEV0 and GPLEV1), which are read one after the other. Only pins within the same
+    /// register are guaranteed to be sampled at the same time. On the BCM2712 (Raspberry Pi 5), all
+    /// user-accessible pins (GPIO 0-27) are read from a single register.
+    pub fn read_pins(&self, pins: &[&InputPin]) -> Vec<Level> {
+        let levels = self.inner.gpio_mem.levels();
+
+        pins.iter()
+            .map(|pin| Level::from((levels >> pin.pin()) & 0b1 > 0))
+            .collect()
+    }
+
     /// Blocks until an interrupt is triggered on any of the specified pins, or until a timeout occurs.
     ///
     /// Only pins that have been previously configured for synchronous interrupts using [`InputPin::set_interrupt`]
@@ -473,7 +1219,9 @@ impl Gpio {
     /// `timeout` can be set to `None` to wait indefinitely.
     ///
     /// When an interrupt event is triggered, `poll_interrupts` returns
-    /// `Ok((&`[`InputPin`]`, `[`Level`]`))` containing the corresponding pin and logic level. If multiple events trigger
+    /// `Ok((&`[`InputPin`]`, `[`Level`]`, `[`Instant`]`))` containing the corresponding pin, logic level and
+    /// the timestamp captured by the kernel when the event occurred. The logic level is derived from the
+    /// edge direction reported by the kernel, rather than read separately after the event. If multiple events trigger
     /// at the same time, only the first one is returned. The remaining events are cached and will be returned
     /// the next time [`InputPin::poll_interrupt`] or `poll_interrupts` is called.
     ///
@@ -482,12 +1230,13 @@ impl Gpio {
     /// [`InputPin::set_async_interrupt`]: struct.InputPin.html#method.set_async_interrupt
     /// [`InputPin`]: struct.InputPin.html
     /// [`Level`]: enum.Level.html
+    /// [`Instant`]: ../time/struct.Instant.html
     pub fn poll_interrupts<'a>(
         &self,
         pins: &[&'a InputPin],
         reset: bool,
         timeout: Option<Duration>,
-    ) -> Result<Option<(&'a InputPin, Level)>> {
+    ) -> Result<Option<(&'a InputPin, Level, Instant)>> {
         (*self.inner.sync_interrupts.lock().unwrap()).poll(pins, reset, timeout)
     }
 }
@@ -0,0 +1,145 @@
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::thread;
+
+use libc::{self, c_void};
+
+use crate::gpio::{gpio_state, Level, Mode, Result};
+
+const SIGNALS: [i32; 2] = [libc::SIGINT, libc::SIGTERM];
+
+// Actions the cleanup thread takes for each pin that's in use, stored per pin in
+// GpioState, so they can be read without access to the pin's owner. They mirror
+// what happens when the pin goes out of scope.
+//
+// Changes the pin to an input if it's configured as an output
+pub const ACTION_RESET: u8 = 0;
+// Leaves the pin unchanged, used when reset_on_drop is disabled
+pub const ACTION_KEEP: u8 = 1;
+// Drives the pin to the drop state configured through OutputPin::set_drop_state
+pub const ACTION_DRIVE_LOW: u8 = 2;
+pub const ACTION_DRIVE_HIGH: u8 = 3;
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+// Write end of the pipe used to notify the cleanup thread
+static PIPE_FD: AtomicI32 = AtomicI32::new(-1);
+
+// Only async-signal-safe functions can be called from a signal handler, so the
+// signal number is passed on to the cleanup thread, which resets the pins.
+extern "C" fn handle_signal(signal: i32) {
+    let errno = unsafe { *libc::__errno_location() };
+    let signal = signal as u8;
+
+    unsafe {
+        libc::write(
+            PIPE_FD.load(Ordering::SeqCst),
+            &signal as *const u8 as *const c_void,
+            1,
+        );
+
+        *libc::__errno_location() = errno;
+    }
+}
+
+fn set_handler(signal: i32, handler: libc::sighandler_t) -> Result<()> {
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = handler;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+
+        if libc::sigaction(signal, &action, ptr::null_mut()) == -1 {
+            return Err(io::Error::last_os_error().into());
+        }
+    }
+
+    Ok(())
+}
+
+// Applies the cleanup action of each pin that's currently in use by this process
+fn reset_pins() {
+    let state = match gpio_state().upgrade() {
+        Some(state) => state,
+        None => return,
+    };
+
+    for pin in 0..state.gpio_lines {
+        if !state.pins_taken[pin as usize].load(Ordering::SeqCst) {
+            continue;
+        }
+
+        let level = match state.pins_cleanup[pin as usize].load(Ordering::SeqCst) {
+            ACTION_KEEP => continue,
+            ACTION_DRIVE_LOW => Level::Low,
+            ACTION_DRIVE_HIGH => Level::High,
+            _ => {
+                if state.gpio_mem.mode(pin) == Mode::Output {
+                    state.gpio_mem.set_mode(pin, Mode::Input);
+                }
+
+                continue;
+            }
+        };
+
+        // Set the output level before switching the mode, so the pin doesn't
+        // briefly output the wrong level
+        match level {
+            Level::Low => state.gpio_mem.set_low(pin),
+            Level::High => state.gpio_mem.set_high(pin),
+        }
+        state.gpio_mem.set_mode(pin, Mode::Output);
+    }
+}
+
+fn wait_for_signal(read_fd: i32) -> i32 {
+    let mut signal: u8 = 0;
+
+    loop {
+        let result = unsafe { libc::read(read_fd, &mut signal as *mut u8 as *mut c_void, 1) };
+
+        if result == 1 {
+            return i32::from(signal);
+        } else if result == -1 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+            continue;
+        }
+
+        // The pipe is never closed, so this shouldn't happen
+        return libc::SIGTERM;
+    }
+}
+
+pub fn install() -> Result<()> {
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let mut fds = [0i32; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+        INSTALLED.store(false, Ordering::SeqCst);
+        return Err(io::Error::last_os_error().into());
+    }
+
+    let [read_fd, write_fd] = fds;
+    PIPE_FD.store(write_fd, Ordering::SeqCst);
+
+    thread::spawn(move || {
+        let signal = wait_for_signal(read_fd);
+
+        reset_pins();
+
+        // Terminate the process the same way it would've been terminated without
+        // our handler
+        let _ = set_handler(signal, libc::SIG_DFL);
+        unsafe {
+            libc::raise(signal);
+        }
+    });
+
+    for &signal in SIGNALS.iter() {
+        set_handler(signal, handle_signal as *const () as libc::sighandler_t)?;
+    }
+
+    Ok(())
+}
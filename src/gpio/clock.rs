@@ -0,0 +1,343 @@
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::thread;
+use std::time::Duration;
+
+use libc::{self, c_void, off_t, size_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
+
+use crate::gpio::{Error, IoPin, Mode, Pin, Result};
+use crate::system::{DeviceInfo, SoC};
+
+const PATH_DEV_MEM: &str = "/dev/mem";
+
+// The clock manager registers are located outside of the GPIO address space,
+// and can only be accessed through /dev/mem.
+const CM_OFFSET: u32 = 0x10_1000;
+const CM_MEM_SIZE: usize = 0x88;
+// GPCLK0 control register. GPCLK1 and GPCLK2 follow at 8-byte intervals.
+const CM_GP0CTL: usize = 0x70 / std::mem::size_of::<u32>();
+const CM_GP0DIV: usize = 0x74 / std::mem::size_of::<u32>();
+
+const CM_PASSWORD: u32 = 0x5a00_0000;
+const CM_CTL_SRC_MASK: u32 = 0x0f;
+const CM_CTL_ENAB: u32 = 0x10;
+const CM_CTL_BUSY: u32 = 0x80;
+const CM_CTL_MASH_LSB: u32 = 9;
+const CM_DIV_DIVI_LSB: u32 = 12;
+
+const CM_SRC_OSCILLATOR: u32 = 1;
+const CM_SRC_PLLD: u32 = 6;
+
+// Integer divider range for 1-stage MASH, which is needed for fractional dividers
+const DIVI_MIN: u32 = 2;
+const DIVI_MAX: u32 = 4095;
+const DIVF_SCALE: f64 = 4096.0;
+
+const BUSY_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Clock sources for the general-purpose clock generators.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ClockSource {
+    /// Crystal oscillator. 19.2 MHz on the BCM283x, 54 MHz on the BCM2711.
+    Oscillator,
+    /// PLLD. 500 MHz on the BCM283x, 750 MHz on the BCM2711.
+    PllD,
+}
+
+impl ClockSource {
+    fn frequency(self, soc: SoC) -> f64 {
+        match (self, soc) {
+            (ClockSource::Oscillator, SoC::Bcm2711) => 54_000_000.0,
+            (ClockSource::Oscillator, _) => 19_200_000.0,
+            (ClockSource::PllD, SoC::Bcm2711) => 750_000_000.0,
+            (ClockSource::PllD, _) => 500_000_000.0,
+        }
+    }
+
+    fn src(self) -> u32 {
+        match self {
+            ClockSource::Oscillator => CM_SRC_OSCILLATOR,
+            ClockSource::PllD => CM_SRC_PLLD,
+        }
+    }
+}
+
+impl fmt::Display for ClockSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ClockSource::Oscillator => write!(f, "Oscillator"),
+            ClockSource::PllD => write!(f, "PllD"),
+        }
+    }
+}
+
+/// General-purpose clock (GPCLK) output.
+///
+/// `Clock` configures one of the three general-purpose clock generators, and routes its
+/// output to a GPIO pin. GPCLK0 is available on BCM GPIO 4 (physical pin 7) and 20 (physical
+/// pin 38), GPCLK1 on BCM GPIO 5 (physical pin 29) and 21 (physical pin 40), and GPCLK2 on
+/// BCM GPIO 6 (physical pin 31). The Compute Module also exposes GPCLK0 on GPIO 32 and 34,
+/// GPCLK1 on GPIO 42 and 44, and GPCLK2 on GPIO 43.
+///
+/// The output frequency is derived from the selected [`ClockSource`] using a divider with a
+/// 12-bit integer and a 12-bit fractional part. When a fractional divider is needed, the clock
+/// generator alternates between the two nearest integer dividers, which results in the requested
+/// average frequency at the cost of some jitter. Because the divider has a limited resolution,
+/// the requested frequency can't always be reached exactly. Use [`frequency`] to retrieve the
+/// frequency that was actually configured.
+///
+/// The clock manager registers can only be accessed through `/dev/mem`, which requires
+/// superuser privileges. `Clock` isn't supported on the Raspberry Pi 5.
+///
+/// The clock generator is stopped, and the pin's mode is reset when `Clock` goes out of scope.
+///
+/// [`ClockSource`]: enum.ClockSource.html
+/// [`frequency`]: #method.frequency
+pub struct Clock {
+    pin: IoPin,
+    channel: usize,
+    mem_ptr: *mut u32,
+    soc: SoC,
+    source: ClockSource,
+    frequency: f64,
+    enabled: bool,
+}
+
+impl Clock {
+    /// Constructs a new `Clock` on the specified pin, and starts the clock generator.
+    ///
+    /// `frequency` indicates the target frequency in hertz (Hz).
+    ///
+    /// Returns [`Error::InvalidValue`] if the pin doesn't support a GPCLK output, or the target
+    /// frequency is out of range for the selected clock source.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn new(pin: Pin, source: ClockSource, frequency: f64) -> Result<Clock> {
+        let (channel, mode) = match pin.pin() {
+            4 | 32 | 34 => (0, Mode::Alt0),
+            20 => (0, Mode::Alt5),
+            5 | 42 | 44 => (1, Mode::Alt0),
+            21 => (1, Mode::Alt5),
+            6 | 43 => (2, Mode::Alt0),
+            _ => return Err(Error::InvalidValue),
+        };
+
+        let device_info = DeviceInfo::new().map_err(|_| Error::UnknownModel)?;
+        if device_info.soc() == SoC::Bcm2712 {
+            return Err(Error::InvalidValue);
+        }
+
+        let mem_ptr = Self::map_devmem(device_info.peripheral_base())?;
+
+        let mut clock = Clock {
+            pin: pin.into_io(mode),
+            channel,
+            mem_ptr,
+            soc: device_info.soc(),
+            source,
+            frequency: 0.0,
+            enabled: false,
+        };
+
+        clock.set_frequency(frequency)?;
+        clock.enable()?;
+
+        Ok(clock)
+    }
+
+    fn map_devmem(peripheral_base: u32) -> Result<*mut u32> {
+        let mem_file = match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_SYNC)
+            .open(PATH_DEV_MEM)
+        {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                return Err(Error::PermissionDenied(String::from(PATH_DEV_MEM)))
+            }
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        // Memory-map /dev/mem at the clock manager offset for our SoC
+        let mem_ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                CM_MEM_SIZE,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                mem_file.as_raw_fd(),
+                (peripheral_base + CM_OFFSET) as off_t,
+            )
+        };
+
+        if mem_ptr == MAP_FAILED {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        Ok(mem_ptr as *mut u32)
+    }
+
+    #[inline(always)]
+    fn read(&self, offset: usize) -> u32 {
+        unsafe { ptr::read_volatile(self.mem_ptr.add(offset)) }
+    }
+
+    #[inline(always)]
+    fn write(&self, offset: usize, value: u32) {
+        unsafe {
+            ptr::write_volatile(self.mem_ptr.add(offset), value);
+        }
+    }
+
+    fn ctl(&self) -> usize {
+        CM_GP0CTL + self.channel * 2
+    }
+
+    fn div(&self) -> usize {
+        CM_GP0DIV + self.channel * 2
+    }
+
+    // Stops the clock generator and waits until it's no longer busy
+    fn stop(&self) -> Result<()> {
+        let ctl = self.read(self.ctl()) & 0x00ff_ffff;
+        self.write(self.ctl(), CM_PASSWORD | (ctl & !CM_CTL_ENAB));
+
+        let start = std::time::Instant::now();
+        while self.read(self.ctl()) & CM_CTL_BUSY > 0 {
+            if start.elapsed() > BUSY_TIMEOUT {
+                return Err(Error::TimedOut);
+            }
+
+            thread::sleep(Duration::from_micros(10));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the clock source.
+    pub fn source(&self) -> ClockSource {
+        self.source
+    }
+
+    /// Returns the configured frequency in hertz (Hz).
+    ///
+    /// The returned value is calculated based on the divider that's currently configured,
+    /// and may differ from the frequency requested through [`new`] or [`set_frequency`].
+    ///
+    /// [`new`]: #method.new
+    /// [`set_frequency`]: #method.set_frequency
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Sets the target frequency in hertz (Hz), and returns the frequency that was actually
+    /// configured.
+    ///
+    /// If the clock generator is enabled, it's briefly stopped while the divider is updated.
+    ///
+    /// Returns [`Error::InvalidValue`] if the target frequency is out of range for the
+    /// selected clock source.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_frequency(&mut self, frequency: f64) -> Result<f64> {
+        if frequency <= 0.0 {
+            return Err(Error::InvalidValue);
+        }
+
+        let source_frequency = self.source.frequency(self.soc);
+        let divisor = source_frequency / frequency;
+
+        let mut divi = divisor.trunc() as u32;
+        let mut divf = ((divisor - divisor.trunc()) * DIVF_SCALE).round() as u32;
+        if divf >= DIVF_SCALE as u32 {
+            divi += 1;
+            divf = 0;
+        }
+
+        if !(DIVI_MIN..=DIVI_MAX).contains(&divi) {
+            return Err(Error::InvalidValue);
+        }
+
+        // 1-stage MASH is only needed for fractional dividers
+        let mash = if divf > 0 { 1 } else { 0 };
+
+        self.stop()?;
+
+        self.write(self.div(), CM_PASSWORD | (divi << CM_DIV_DIVI_LSB) | divf);
+        self.write(
+            self.ctl(),
+            CM_PASSWORD | (mash << CM_CTL_MASH_LSB) | (self.source.src() & CM_CTL_SRC_MASK),
+        );
+
+        if self.enabled {
+            self.write(
+                self.ctl(),
+                CM_PASSWORD | (self.read(self.ctl()) & 0x00ff_ffff) | CM_CTL_ENAB,
+            );
+        }
+
+        self.frequency = source_frequency / (f64::from(divi) + f64::from(divf) / DIVF_SCALE);
+
+        Ok(self.frequency)
+    }
+
+    /// Returns `true` if the clock generator is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables the clock generator.
+    pub fn enable(&mut self) -> Result<()> {
+        self.write(
+            self.ctl(),
+            CM_PASSWORD | (self.read(self.ctl()) & 0x00ff_ffff) | CM_CTL_ENAB,
+        );
+
+        self.enabled = true;
+
+        Ok(())
+    }
+
+    /// Disables the clock generator.
+    pub fn disable(&mut self) -> Result<()> {
+        self.stop()?;
+
+        self.enabled = false;
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clock")
+            .field("pin", &self.pin)
+            .field("channel", &self.channel)
+            .field("mem_ptr", &self.mem_ptr)
+            .field("soc", &self.soc)
+            .field("source", &self.source)
+            .field("frequency", &self.frequency)
+            .field("enabled", &self.enabled)
+            .finish()
+    }
+}
+
+impl Drop for Clock {
+    fn drop(&mut self) {
+        let _ = self.stop();
+
+        unsafe {
+            libc::munmap(self.mem_ptr as *mut c_void, CM_MEM_SIZE as size_t);
+        }
+    }
+}
+
+// Required because of the raw pointer to our memory-mapped file
+unsafe impl Send for Clock {}
+
+unsafe impl Sync for Clock {}
@@ -0,0 +1,729 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::mem;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::thread;
+use std::time::Duration;
+
+use libc::{self, c_void, off_t, size_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
+
+use crate::gpio::{Error, OutputPin, Result};
+use crate::system::{DeviceInfo, SoC};
+
+const PATH_DEV_MEM: &str = "/dev/mem";
+const PATH_DEV_VCIO: &str = "/dev/vcio";
+
+const PAGE_SIZE: usize = 4096;
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+// The DMA controller accesses the peripherals through their bus addresses
+const BUS_PERIPHERAL_BASE: u32 = 0x7e00_0000;
+// Physical addresses for RAM allocated through the mailbox interface are found by
+// removing the cache alias bits from the bus address.
+const BUS_ALIAS_MASK: u32 = 0xc000_0000;
+
+const GPIO_OFFSET: u32 = 0x20_0000;
+const GPSET0: u32 = 0x1c;
+const GPCLR0: u32 = 0x28;
+
+// The PCM peripheral consumes one FIFO entry per frame, which paces the DMA transfers
+const PCM_OFFSET: u32 = 0x20_3000;
+const PCM_MEM_SIZE: usize = 0x24;
+const PCM_CS_A: usize = 0;
+const PCM_FIFO_A: u32 = 0x04;
+const PCM_MODE_A: usize = 0x08 / mem::size_of::<u32>();
+const PCM_TXC_A: usize = 0x10 / mem::size_of::<u32>();
+const PCM_DREQ_A: usize = 0x14 / mem::size_of::<u32>();
+
+const PCM_CS_EN: u32 = 1 << 0;
+const PCM_CS_TXON: u32 = 1 << 2;
+const PCM_CS_TXCLR: u32 = 1 << 3;
+const PCM_CS_DMAEN: u32 = 1 << 9;
+const PCM_TXC_CH1WEX: u32 = 1 << 31;
+const PCM_TXC_CH1EN: u32 = 1 << 30;
+const PCM_MODE_FLEN_LSB: u32 = 10;
+const PCM_DREQ_TX_LSB: u32 = 8;
+const PCM_DREQ_TX_PANIC_LSB: u32 = 24;
+// Number of PCM clock cycles per frame
+const PCM_FRAME_LENGTH: u64 = 10;
+
+const CM_OFFSET: u32 = 0x10_1000;
+const CM_MEM_SIZE: usize = 0xa0;
+const CM_PCMCTL: usize = 0x98 / mem::size_of::<u32>();
+const CM_PCMDIV: usize = 0x9c / mem::size_of::<u32>();
+
+const CM_PASSWORD: u32 = 0x5a00_0000;
+const CM_CTL_ENAB: u32 = 0x10;
+const CM_CTL_KILL: u32 = 0x20;
+const CM_CTL_BUSY: u32 = 0x80;
+const CM_DIV_DIVI_LSB: u32 = 12;
+const CM_SRC_PLLD: u32 = 6;
+
+// The fractional divider would introduce jitter, so only the integer part is used
+const DIVI_MIN: u64 = 2;
+const DIVI_MAX: u64 = 4095;
+
+const DMA_OFFSET: u32 = 0x7000;
+const DMA_MEM_SIZE: usize = 0x1000;
+const DMA_CHANNEL_SIZE: usize = 0x100 / mem::size_of::<u32>();
+const DMA_CS: usize = 0;
+const DMA_CONBLK_AD: usize = 0x04 / mem::size_of::<u32>();
+const DMA_DEBUG: usize = 0x20 / mem::size_of::<u32>();
+// DMA channels that aren't used by the kernel on a default Raspberry Pi OS installation.
+// Channels 11-14 on the BCM2711 are DMA4 engines, which use a different control block
+// layout.
+const DMA_CHANNEL_BCM283X: usize = 14;
+const DMA_CHANNEL_BCM2711: usize = 7;
+
+const DMA_CS_ACTIVE: u32 = 1 << 0;
+const DMA_CS_PRIORITY_LSB: u32 = 16;
+const DMA_CS_PANIC_PRIORITY_LSB: u32 = 20;
+const DMA_CS_WAIT_FOR_OUTSTANDING_WRITES: u32 = 1 << 28;
+const DMA_CS_RESET: u32 = 1 << 31;
+const DMA_DEBUG_CLEAR_ERRORS: u32 = 0x07;
+
+const DMA_TI_WAIT_RESP: u32 = 1 << 3;
+const DMA_TI_DEST_DREQ: u32 = 1 << 6;
+const DMA_TI_PERMAP_LSB: u32 = 16;
+const DMA_TI_NO_WIDE_BURSTS: u32 = 1 << 26;
+const DREQ_PCM_TX: u32 = 2;
+
+// _IOWR(100, 0, char *)
+const IOCTL_MBOX_PROPERTY: u32 =
+    (3 << 30) | ((mem::size_of::<*mut u8>() as u32) << 16) | (100 << 8);
+const MBOX_REQUEST: u32 = 0;
+const MBOX_RESPONSE_SUCCESS: u32 = 0x8000_0000;
+const MBOX_TAG_ALLOCATE_MEMORY: u32 = 0x0003_000c;
+const MBOX_TAG_LOCK_MEMORY: u32 = 0x0003_000d;
+const MBOX_TAG_UNLOCK_MEMORY: u32 = 0x0003_000e;
+const MBOX_TAG_RELEASE_MEMORY: u32 = 0x0003_000f;
+// Allocates uncached memory, so the DMA controller and the CPU see the same data
+const MEM_FLAG_DIRECT: u32 = 1 << 2;
+const MEM_FLAG_L1_NONALLOCATING: u32 = (1 << 2) | (1 << 3);
+
+const DEFAULT_RESOLUTION: Duration = Duration::from_micros(1);
+// Limits the amount of memory that's allocated for control blocks
+const MAX_STEPS: usize = 50_000;
+const BUSY_TIMEOUT: Duration = Duration::from_millis(10);
+
+#[repr(C, align(32))]
+#[derive(Debug, Copy, Clone, Default)]
+struct ControlBlock {
+    transfer_info: u32,
+    source_address: u32,
+    destination_address: u32,
+    transfer_length: u32,
+    stride: u32,
+    next_control_block: u32,
+    reserved: [u32; 2],
+}
+
+fn open_file(path: &str, options: &mut OpenOptions) -> Result<File> {
+    match options.open(path) {
+        Ok(file) => Ok(file),
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            Err(Error::PermissionDenied(String::from(path)))
+        }
+        Err(e) => Err(Error::Io(e)),
+    }
+}
+
+// Memory-mapped region of /dev/mem
+#[derive(Debug)]
+struct Mapping {
+    ptr: *mut u32,
+    size: usize,
+}
+
+impl Mapping {
+    fn new(mem_file: &File, offset: u32, size: usize) -> Result<Mapping> {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                mem_file.as_raw_fd(),
+                offset as off_t,
+            )
+        };
+
+        if ptr == MAP_FAILED {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        Ok(Mapping {
+            ptr: ptr as *mut u32,
+            size,
+        })
+    }
+
+    #[inline(always)]
+    fn read(&self, offset: usize) -> u32 {
+        unsafe { ptr::read_volatile(self.ptr.add(offset)) }
+    }
+
+    #[inline(always)]
+    fn write(&self, offset: usize, value: u32) {
+        unsafe {
+            ptr::write_volatile(self.ptr.add(offset), value);
+        }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut c_void, self.size as size_t);
+        }
+    }
+}
+
+// Uncached memory allocated by the VideoCore firmware through the mailbox property
+// interface, which stays at a fixed bus address for as long as it's locked.
+#[derive(Debug)]
+struct GpuMemory {
+    vcio: File,
+    handle: u32,
+    bus_address: u32,
+    mapping: Option<Mapping>,
+}
+
+impl GpuMemory {
+    fn new(mem_file: &File, size: usize, flags: u32) -> Result<GpuMemory> {
+        let vcio = open_file(PATH_DEV_VCIO, OpenOptions::new().read(true).write(true))?;
+
+        let handle = mailbox_property(
+            &vcio,
+            MBOX_TAG_ALLOCATE_MEMORY,
+            &[size as u32, PAGE_SIZE as u32, flags],
+        )?;
+        if handle == 0 {
+            return Err(Error::Io(io::Error::from(io::ErrorKind::OutOfMemory)));
+        }
+
+        let mut memory = GpuMemory {
+            vcio,
+            handle,
+            bus_address: 0,
+            mapping: None,
+        };
+
+        memory.bus_address = mailbox_property(&memory.vcio, MBOX_TAG_LOCK_MEMORY, &[handle])?;
+        if memory.bus_address == 0 {
+            return Err(Error::Io(io::Error::from(io::ErrorKind::OutOfMemory)));
+        }
+
+        memory.mapping = Some(Mapping::new(
+            mem_file,
+            memory.bus_address & !BUS_ALIAS_MASK,
+            size,
+        )?);
+
+        Ok(memory)
+    }
+
+    fn mapping(&self) -> &Mapping {
+        // The mapping is always set once GpuMemory is constructed
+        self.mapping.as_ref().unwrap()
+    }
+}
+
+impl Drop for GpuMemory {
+    fn drop(&mut self) {
+        self.mapping = None;
+
+        if self.bus_address != 0 {
+            let _ = mailbox_property(&self.vcio, MBOX_TAG_UNLOCK_MEMORY, &[self.handle]);
+        }
+
+        let _ = mailbox_property(&self.vcio, MBOX_TAG_RELEASE_MEMORY, &[self.handle]);
+    }
+}
+
+// Sends a single tag to the mailbox property interface, and returns the first word
+// of the response
+fn mailbox_property(vcio: &File, tag: u32, values: &[u32]) -> Result<u32> {
+    let mut buffer = [0u32; 16];
+    let size = (6 + values.len()) * mem::size_of::<u32>();
+
+    buffer[0] = size as u32;
+    buffer[1] = MBOX_REQUEST;
+    buffer[2] = tag;
+    buffer[3] = mem::size_of_val(values) as u32;
+    buffer[4] = 0;
+    buffer[5..5 + values.len()].copy_from_slice(values);
+    // The end tag is already set to 0
+
+    parse_retval!(unsafe {
+        libc::ioctl(
+            vcio.as_raw_fd(),
+            IOCTL_MBOX_PROPERTY as _,
+            buffer.as_mut_ptr(),
+        )
+    })?;
+
+    if buffer[1] != MBOX_RESPONSE_SUCCESS {
+        return Err(Error::Io(io::Error::from(io::ErrorKind::Other)));
+    }
+
+    Ok(buffer[5])
+}
+
+/// DMA-based PWM on any GPIO pin.
+///
+/// `DmaPwm` generates PWM signals on one or more GPIO pins without any CPU involvement,
+/// similar to the approach used by ServoBlaster and pigpio. The period is divided into
+/// steps of a fixed length, the resolution. A DMA channel loops through a sequence of
+/// control blocks, which sets all active pins high at the start of the period, and sets
+/// each pin low again through the GPIO clear register once its pulse width has elapsed.
+/// The DMA transfers are paced by the PCM peripheral, which consumes one FIFO entry per
+/// step.
+///
+/// Because the signal timing doesn't depend on the scheduler, the typical jitter is far
+/// lower than with [`SoftPwm`], which makes `DmaPwm` suitable for driving many servos or
+/// dimming LEDs on pins that aren't connected to the hardware [`Pwm`] peripheral. Heavy
+/// memory bus traffic, for instance from the GPU, can still occasionally delay individual
+/// edges by a few microseconds.
+///
+/// All pins share the same period. Only GPIO 0-31 are supported, which includes all pins
+/// on the GPIO header.
+///
+/// Pulse width changes take effect immediately. If a pulse width is decreased while the
+/// pin is still high for the current period, the pin may stay high until the new pulse
+/// width has elapsed in the next period, which results in a single elongated pulse.
+///
+/// `DmaPwm` uses DMA channel 14 on the BCM283x and channel 7 on the BCM2711, and memory
+/// allocated through the VideoCore mailbox interface (`/dev/vcio`). The DMA controller and
+/// PCM peripheral are accessed through `/dev/mem`, which requires superuser privileges.
+/// The PCM peripheral can't be used for audio while `DmaPwm` is active. `DmaPwm` isn't
+/// supported on the Raspberry Pi 5.
+///
+/// The DMA channel and PCM peripheral are stopped, and all pins are set low when
+/// `DmaPwm` goes out of scope.
+///
+/// [`SoftPwm`]: struct.SoftPwm.html
+/// [`Pwm`]: ../pwm/struct.Pwm.html
+pub struct DmaPwm {
+    pins: Vec<(OutputPin, usize)>,
+    period: Duration,
+    resolution: Duration,
+    steps: usize,
+    dma_channel: usize,
+    dma: Mapping,
+    pcm: Mapping,
+    cm: Mapping,
+    // Control blocks, followed by the set mask and the clear mask for each step
+    memory: GpuMemory,
+}
+
+impl DmaPwm {
+    /// Constructs a new `DmaPwm` with the specified period, and a resolution of 1 µs.
+    ///
+    /// The DMA channel starts running immediately. Use [`add_pin`] to generate a PWM
+    /// signal on a pin.
+    ///
+    /// Returns [`Error::InvalidValue`] if the period is shorter than 2 µs or longer than
+    /// 50 ms.
+    ///
+    /// [`add_pin`]: #method.add_pin
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn new(period: Duration) -> Result<DmaPwm> {
+        DmaPwm::with_resolution(period, DEFAULT_RESOLUTION)
+    }
+
+    /// Constructs a new `DmaPwm` with the specified period and resolution.
+    ///
+    /// The resolution is rounded to the nearest value supported by the PCM clock, which
+    /// is a multiple of 20 ns on the BCM283x, and 13.3 ns on the BCM2711. The period is
+    /// rounded to the nearest multiple of the resolution. Use [`period`] and
+    /// [`resolution`] to retrieve the values that were actually configured.
+    ///
+    /// Returns [`Error::InvalidValue`] if the resolution is out of range, or the period
+    /// is shorter than 2 steps or longer than 50,000 steps.
+    ///
+    /// [`period`]: #method.period
+    /// [`resolution`]: #method.resolution
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn with_resolution(period: Duration, resolution: Duration) -> Result<DmaPwm> {
+        let device_info = DeviceInfo::new().map_err(|_| Error::UnknownModel)?;
+        let (pll_frequency, dma_channel, mem_flags) = match device_info.soc() {
+            SoC::Bcm2712 => return Err(Error::InvalidValue),
+            SoC::Bcm2711 => (750_000_000, DMA_CHANNEL_BCM2711, MEM_FLAG_DIRECT),
+            SoC::Bcm2835 => (500_000_000, DMA_CHANNEL_BCM283X, MEM_FLAG_L1_NONALLOCATING),
+            _ => (500_000_000, DMA_CHANNEL_BCM283X, MEM_FLAG_DIRECT),
+        };
+
+        // Each step takes one PCM frame
+        let divi = (pll_frequency * resolution.as_nanos() as u64
+            + PCM_FRAME_LENGTH * NANOS_PER_SEC / 2)
+            / (PCM_FRAME_LENGTH * NANOS_PER_SEC);
+        if !(DIVI_MIN..=DIVI_MAX).contains(&divi) {
+            return Err(Error::InvalidValue);
+        }
+
+        let resolution_ns = divi * PCM_FRAME_LENGTH * NANOS_PER_SEC / pll_frequency;
+        let steps = ((period.as_nanos() as u64 + resolution_ns / 2) / resolution_ns) as usize;
+        if !(2..=MAX_STEPS).contains(&steps) {
+            return Err(Error::InvalidValue);
+        }
+
+        let mem_file = open_file(
+            PATH_DEV_MEM,
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .custom_flags(O_SYNC),
+        )?;
+
+        let peripheral_base = device_info.peripheral_base();
+        let dma = Mapping::new(&mem_file, peripheral_base + DMA_OFFSET, DMA_MEM_SIZE)?;
+        let pcm = Mapping::new(&mem_file, peripheral_base + PCM_OFFSET, PCM_MEM_SIZE)?;
+        let cm = Mapping::new(&mem_file, peripheral_base + CM_OFFSET, CM_MEM_SIZE)?;
+
+        let size =
+            (1 + steps * 2) * mem::size_of::<ControlBlock>() + (1 + steps) * mem::size_of::<u32>();
+        let size = (size + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+        let memory = GpuMemory::new(&mem_file, size, mem_flags)?;
+
+        let dma_pwm = DmaPwm {
+            pins: Vec::new(),
+            period: Duration::from_nanos(steps as u64 * resolution_ns),
+            resolution: Duration::from_nanos(resolution_ns),
+            steps,
+            dma_channel,
+            dma,
+            pcm,
+            cm,
+            memory,
+        };
+
+        dma_pwm.init_control_blocks();
+        dma_pwm.start_pcm(divi as u32)?;
+        dma_pwm.start_dma();
+
+        Ok(dma_pwm)
+    }
+
+    /// Returns the period.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the resolution.
+    pub fn resolution(&self) -> Duration {
+        self.resolution
+    }
+
+    /// Adds a pin to the set of pins controlled by `DmaPwm`.
+    ///
+    /// The pin is set low, and its pulse width starts at 0. Use [`set_pulse_width`] to
+    /// change the pulse width.
+    ///
+    /// Returns [`Error::InvalidValue`] if the pin number is higher than 31, or the pin was
+    /// already added.
+    ///
+    /// [`set_pulse_width`]: #method.set_pulse_width
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn add_pin(&mut self, mut pin: OutputPin) -> Result<()> {
+        if pin.pin() > 31 || self.index(pin.pin()).is_some() {
+            return Err(Error::InvalidValue);
+        }
+
+        pin.set_low();
+        self.set_clear_bit(0, pin.pin(), true);
+        self.pins.push((pin, 0));
+
+        Ok(())
+    }
+
+    /// Removes a pin from the set of pins controlled by `DmaPwm`, and returns the
+    /// [`OutputPin`].
+    ///
+    /// The pin is set low. Returns `None` if the pin wasn't added.
+    ///
+    /// [`OutputPin`]: struct.OutputPin.html
+    pub fn remove_pin(&mut self, pin: u8) -> Option<OutputPin> {
+        let index = self.index(pin)?;
+
+        let (_, steps) = self.pins[index];
+        self.set_set_bit(pin, false);
+        if let Some(clear_step) = self.clear_step(steps) {
+            self.set_clear_bit(clear_step, pin, false);
+        }
+
+        let (mut pin, _) = self.pins.remove(index);
+        pin.set_low();
+
+        Some(pin)
+    }
+
+    /// Returns the pulse width of the specified pin, or `None` if the pin wasn't added.
+    pub fn pulse_width(&self, pin: u8) -> Option<Duration> {
+        self.index(pin)
+            .map(|index| self.resolution * self.pins[index].1 as u32)
+    }
+
+    /// Sets the pulse width of the specified pin.
+    ///
+    /// The pulse width is rounded to the nearest multiple of the resolution. A pulse width
+    /// of 0 keeps the pin low, and a pulse width equal to the period keeps the pin high.
+    ///
+    /// Returns [`Error::InvalidValue`] if the pin wasn't added, or the pulse width is
+    /// longer than the period.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_pulse_width(&mut self, pin: u8, pulse_width: Duration) -> Result<()> {
+        let index = self.index(pin).ok_or(Error::InvalidValue)?;
+        if pulse_width > self.period {
+            return Err(Error::InvalidValue);
+        }
+
+        let resolution_ns = self.resolution.as_nanos() as u64;
+        let steps = ((pulse_width.as_nanos() as u64 + resolution_ns / 2) / resolution_ns) as usize;
+        let steps = steps.min(self.steps);
+
+        let prev_steps = self.pins[index].1;
+        if steps == prev_steps {
+            return Ok(());
+        }
+
+        // Add the new clear step before removing the old one, so the pin is never
+        // left high for an entire period
+        if let Some(clear_step) = self.clear_step(steps) {
+            self.set_clear_bit(clear_step, pin, true);
+        }
+
+        self.set_set_bit(pin, steps > 0);
+
+        if let Some(clear_step) = self.clear_step(prev_steps) {
+            self.set_clear_bit(clear_step, pin, false);
+        }
+
+        self.pins[index].1 = steps;
+
+        Ok(())
+    }
+
+    fn index(&self, pin: u8) -> Option<usize> {
+        self.pins
+            .iter()
+            .position(|(output_pin, _)| output_pin.pin() == pin)
+    }
+
+    // Returns the step during which a pin with the specified pulse width is set low.
+    // Pins with a 100% duty cycle are never set low.
+    fn clear_step(&self, steps: usize) -> Option<usize> {
+        if steps < self.steps {
+            Some(steps)
+        } else {
+            None
+        }
+    }
+
+    fn control_block_offset(index: usize) -> usize {
+        index * mem::size_of::<ControlBlock>() / mem::size_of::<u32>()
+    }
+
+    // Word offset of the set mask. The clear masks for each step follow directly after.
+    fn set_mask_offset(&self) -> usize {
+        DmaPwm::control_block_offset(1 + self.steps * 2)
+    }
+
+    fn set_set_bit(&self, pin: u8, value: bool) {
+        self.set_mask_bit(self.set_mask_offset(), pin, value);
+    }
+
+    fn set_clear_bit(&self, step: usize, pin: u8, value: bool) {
+        self.set_mask_bit(self.set_mask_offset() + 1 + step, pin, value);
+    }
+
+    fn set_mask_bit(&self, offset: usize, pin: u8, value: bool) {
+        let mapping = self.memory.mapping();
+        let mask = mapping.read(offset);
+
+        if value {
+            mapping.write(offset, mask | (1 << pin));
+        } else {
+            mapping.write(offset, mask & !(1 << pin));
+        }
+    }
+
+    fn bus_address(&self, offset: usize) -> u32 {
+        self.memory.bus_address + (offset * mem::size_of::<u32>()) as u32
+    }
+
+    fn write_control_block(&self, index: usize, control_block: ControlBlock) {
+        let offset = DmaPwm::control_block_offset(index);
+        let words: [u32; 8] = unsafe { mem::transmute(control_block) };
+
+        for (i, &word) in words.iter().enumerate() {
+            self.memory.mapping().write(offset + i, word);
+        }
+    }
+
+    // The first control block sets all active pins high. Each step then consists of a
+    // control block that sets pins low, followed by a control block that waits until the
+    // PCM FIFO has room for another entry. The last step loops back to the start.
+    fn init_control_blocks(&self) {
+        let set_mask = self.set_mask_offset();
+        for i in 0..=self.steps {
+            self.memory.mapping().write(set_mask + i, 0);
+        }
+
+        let gpio_bus_address = BUS_PERIPHERAL_BASE + GPIO_OFFSET;
+
+        self.write_control_block(
+            0,
+            ControlBlock {
+                transfer_info: DMA_TI_NO_WIDE_BURSTS | DMA_TI_WAIT_RESP,
+                source_address: self.bus_address(set_mask),
+                destination_address: gpio_bus_address + GPSET0,
+                transfer_length: mem::size_of::<u32>() as u32,
+                next_control_block: self.bus_address(DmaPwm::control_block_offset(1)),
+                ..ControlBlock::default()
+            },
+        );
+
+        for step in 0..self.steps {
+            let clear = 1 + step * 2;
+            let delay = clear + 1;
+            let next = if step + 1 < self.steps { delay + 1 } else { 0 };
+
+            self.write_control_block(
+                clear,
+                ControlBlock {
+                    transfer_info: DMA_TI_NO_WIDE_BURSTS | DMA_TI_WAIT_RESP,
+                    source_address: self.bus_address(set_mask + 1 + step),
+                    destination_address: gpio_bus_address + GPCLR0,
+                    transfer_length: mem::size_of::<u32>() as u32,
+                    next_control_block: self.bus_address(DmaPwm::control_block_offset(delay)),
+                    ..ControlBlock::default()
+                },
+            );
+
+            // The data written to the PCM FIFO isn't used, because PCM_DOUT isn't routed
+            // to any pin
+            self.write_control_block(
+                delay,
+                ControlBlock {
+                    transfer_info: DMA_TI_NO_WIDE_BURSTS
+                        | DMA_TI_WAIT_RESP
+                        | DMA_TI_DEST_DREQ
+                        | (DREQ_PCM_TX << DMA_TI_PERMAP_LSB),
+                    source_address: self.bus_address(set_mask),
+                    destination_address: BUS_PERIPHERAL_BASE + PCM_OFFSET + PCM_FIFO_A,
+                    transfer_length: mem::size_of::<u32>() as u32,
+                    next_control_block: self.bus_address(DmaPwm::control_block_offset(next)),
+                    ..ControlBlock::default()
+                },
+            );
+        }
+    }
+
+    fn stop_clock(&self) -> Result<()> {
+        self.cm.write(CM_PCMCTL, CM_PASSWORD | CM_CTL_KILL);
+
+        let start = std::time::Instant::now();
+        while self.cm.read(CM_PCMCTL) & CM_CTL_BUSY > 0 {
+            if start.elapsed() > BUSY_TIMEOUT {
+                return Err(Error::TimedOut);
+            }
+
+            thread::sleep(Duration::from_micros(10));
+        }
+
+        Ok(())
+    }
+
+    fn start_pcm(&self, divi: u32) -> Result<()> {
+        self.pcm.write(PCM_CS_A, 0);
+        self.stop_clock()?;
+
+        self.cm
+            .write(CM_PCMDIV, CM_PASSWORD | (divi << CM_DIV_DIVI_LSB));
+        self.cm.write(CM_PCMCTL, CM_PASSWORD | CM_SRC_PLLD);
+        self.cm
+            .write(CM_PCMCTL, CM_PASSWORD | CM_SRC_PLLD | CM_CTL_ENAB);
+
+        self.pcm.write(PCM_CS_A, PCM_CS_EN);
+        self.pcm.write(PCM_TXC_A, PCM_TXC_CH1WEX | PCM_TXC_CH1EN);
+        self.pcm.write(
+            PCM_MODE_A,
+            ((PCM_FRAME_LENGTH - 1) as u32) << PCM_MODE_FLEN_LSB,
+        );
+        self.pcm
+            .write(PCM_CS_A, self.pcm.read(PCM_CS_A) | PCM_CS_TXCLR);
+        thread::sleep(Duration::from_micros(10));
+        self.pcm.write(
+            PCM_DREQ_A,
+            (16 << PCM_DREQ_TX_PANIC_LSB) | (30 << PCM_DREQ_TX_LSB),
+        );
+        self.pcm
+            .write(PCM_CS_A, self.pcm.read(PCM_CS_A) | PCM_CS_DMAEN);
+        thread::sleep(Duration::from_micros(10));
+        self.pcm
+            .write(PCM_CS_A, self.pcm.read(PCM_CS_A) | PCM_CS_TXON);
+
+        Ok(())
+    }
+
+    fn start_dma(&self) {
+        let channel = self.dma_channel * DMA_CHANNEL_SIZE;
+
+        self.dma.write(channel + DMA_CS, DMA_CS_RESET);
+        thread::sleep(Duration::from_micros(10));
+        self.dma.write(channel + DMA_DEBUG, DMA_DEBUG_CLEAR_ERRORS);
+        self.dma.write(
+            channel + DMA_CONBLK_AD,
+            self.bus_address(DmaPwm::control_block_offset(0)),
+        );
+        self.dma.write(
+            channel + DMA_CS,
+            DMA_CS_WAIT_FOR_OUTSTANDING_WRITES
+                | (8 << DMA_CS_PANIC_PRIORITY_LSB)
+                | (8 << DMA_CS_PRIORITY_LSB)
+                | DMA_CS_ACTIVE,
+        );
+    }
+
+    fn stop_dma(&self) {
+        self.dma
+            .write(self.dma_channel * DMA_CHANNEL_SIZE + DMA_CS, DMA_CS_RESET);
+        thread::sleep(Duration::from_micros(10));
+    }
+}
+
+impl fmt::Debug for DmaPwm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DmaPwm")
+            .field("pins", &self.pins)
+            .field("period", &self.period)
+            .field("resolution", &self.resolution)
+            .field("steps", &self.steps)
+            .field("dma_channel", &self.dma_channel)
+            .field("bus_address", &self.memory.bus_address)
+            .finish()
+    }
+}
+
+impl Drop for DmaPwm {
+    fn drop(&mut self) {
+        // The DMA channel needs to be stopped before the control blocks are released
+        self.stop_dma();
+        self.pcm.write(PCM_CS_A, 0);
+        let _ = self.stop_clock();
+
+        for (pin, _) in self.pins.iter_mut() {
+            pin.set_low();
+        }
+    }
+}
+
+// Required because of the raw pointers to our memory-mapped files
+unsafe impl Send for DmaPwm {}
+
+unsafe impl Sync for DmaPwm {}
@@ -0,0 +1,170 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::gpio::{InputPin, Level, Result, Trigger};
+
+// Position change for each transition between two-bit (A << 1 | B) states, indexed
+// by (previous state << 2) | new state. Transitions that skip a state are invalid,
+// and don't change the position.
+const TRANSITIONS: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+
+#[derive(Debug)]
+struct State {
+    level_a: Level,
+    level_b: Level,
+    position: i64,
+    // Position at the time of the last call to take_delta
+    taken: i64,
+}
+
+impl State {
+    fn bits(&self) -> usize {
+        ((self.level_a as usize) << 1) | self.level_b as usize
+    }
+
+    fn update(&mut self, level_a: Level, level_b: Level) {
+        let prev = self.bits();
+
+        self.level_a = level_a;
+        self.level_b = level_b;
+
+        self.position += i64::from(TRANSITIONS[(prev << 2) | self.bits()]);
+    }
+}
+
+/// Quadrature encoder decoder.
+///
+/// `Encoder` tracks the position of a rotary or linear quadrature encoder, using
+/// asynchronous interrupt triggers on both edges of the A and B channels. Every valid
+/// transition between two consecutive states of the 2-bit Gray code increments or
+/// decrements the position by one, which results in four counts per full cycle on both
+/// channels. The position is incremented when channel A leads channel B.
+///
+/// Invalid transitions, where both channels appear to change at the same time, are
+/// ignored. Contact bounce on mechanical encoders results in transitions back and forth
+/// between two adjacent states, which cancel each other out.
+///
+/// An optional index (Z) channel resets the position to 0 on each rising edge.
+///
+/// Each channel is handled on its own interrupt thread. If the edges on channels A and B
+/// occur closer together than the time it takes to handle a trigger event, which is
+/// usually in the range of tens of microseconds, the events may be processed out of
+/// order, and counts can be lost.
+///
+/// The interrupt triggers are removed when `Encoder` goes out of scope.
+#[derive(Debug)]
+pub struct Encoder {
+    pin_a: InputPin,
+    pin_b: InputPin,
+    pin_z: Option<InputPin>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Encoder {
+    /// Constructs a new `Encoder` for channels A and B.
+    ///
+    /// Any previously configured (a)synchronous interrupt triggers for `pin_a` and
+    /// `pin_b` are cleared. The position starts at 0.
+    pub fn new(pin_a: InputPin, pin_b: InputPin) -> Result<Encoder> {
+        Encoder::init(pin_a, pin_b, None)
+    }
+
+    /// Constructs a new `Encoder` for channels A and B, and index channel Z.
+    ///
+    /// The position is reset to 0 on each rising edge of `pin_z`.
+    ///
+    /// Any previously configured (a)synchronous interrupt triggers for `pin_a`, `pin_b`
+    /// and `pin_z` are cleared. The position starts at 0.
+    pub fn with_index(pin_a: InputPin, pin_b: InputPin, pin_z: InputPin) -> Result<Encoder> {
+        Encoder::init(pin_a, pin_b, Some(pin_z))
+    }
+
+    fn init(
+        mut pin_a: InputPin,
+        mut pin_b: InputPin,
+        mut pin_z: Option<InputPin>,
+    ) -> Result<Encoder> {
+        let state = Arc::new(Mutex::new(State {
+            level_a: pin_a.read(),
+            level_b: pin_b.read(),
+            position: 0,
+            taken: 0,
+        }));
+
+        let state_a = state.clone();
+        pin_a.set_async_interrupt(Trigger::Both, None, move |level| {
+            let mut state = lock(&state_a);
+            let level_b = state.level_b;
+            state.update(level, level_b);
+        })?;
+
+        let state_b = state.clone();
+        pin_b.set_async_interrupt(Trigger::Both, None, move |level| {
+            let mut state = lock(&state_b);
+            let level_a = state.level_a;
+            state.update(level_a, level);
+        })?;
+
+        if let Some(ref mut pin_z) = pin_z {
+            let state_z = state.clone();
+            pin_z.set_async_interrupt(Trigger::RisingEdge, None, move |_| {
+                let mut state = lock(&state_z);
+                state.taken -= state.position;
+                state.position = 0;
+            })?;
+        }
+
+        Ok(Encoder {
+            pin_a,
+            pin_b,
+            pin_z,
+            state,
+        })
+    }
+
+    /// Returns the current position.
+    pub fn position(&self) -> i64 {
+        lock(&self.state).position
+    }
+
+    /// Sets the current position.
+    pub fn set_position(&self, position: i64) {
+        let mut state = lock(&self.state);
+
+        state.taken += position - state.position;
+        state.position = position;
+    }
+
+    /// Returns the change in position since the last call to `take_delta`, or since
+    /// `Encoder` was constructed.
+    ///
+    /// Changes caused by [`set_position`] or the index channel aren't included.
+    ///
+    /// [`set_position`]: #method.set_position
+    pub fn take_delta(&self) -> i64 {
+        let mut state = lock(&self.state);
+
+        let delta = state.position - state.taken;
+        state.taken = state.position;
+
+        delta
+    }
+
+    /// Consumes the `Encoder`, removes the interrupt triggers, and returns the
+    /// [`InputPin`]s for channels A, B and (optionally) Z.
+    ///
+    /// [`InputPin`]: struct.InputPin.html
+    pub fn into_inner(mut self) -> Result<(InputPin, InputPin, Option<InputPin>)> {
+        self.pin_a.clear_async_interrupt()?;
+        self.pin_b.clear_async_interrupt()?;
+
+        if let Some(ref mut pin_z) = self.pin_z {
+            pin_z.clear_async_interrupt()?;
+        }
+
+        Ok((self.pin_a, self.pin_b, self.pin_z))
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -1,13 +1,27 @@
-use crate::gpio::{Bias, Level, Mode};
+use crate::gpio::{Backend, Bias, Level, Mode, Result};
 
 pub mod bcm;
+pub mod cdev;
+#[cfg(feature = "mock")]
+pub mod mock;
 pub mod rp1;
 
 pub(crate) trait GpioRegisters: std::fmt::Debug + Sync + Send {
+    fn backend(&self) -> Backend;
     fn set_high(&self, pin: u8);
     fn set_low(&self, pin: u8);
+    // Each bit in `high` and `low` represents the pin at the corresponding bit position
+    fn set_levels(&self, high: u64, low: u64);
     fn level(&self, pin: u8) -> Level;
+    // Each bit represents the level of the pin at the corresponding bit position
+    fn levels(&self) -> u64;
     fn mode(&self, pin: u8) -> Mode;
     fn set_mode(&self, pin: u8, mode: Mode);
+    fn bias(&self, pin: u8) -> Option<Bias>;
     fn set_bias(&self, pin: u8, bias: Bias);
+    fn set_drive_strength(&self, pin: u8, milliamps: u8) -> Result<()>;
+    #[cfg(feature = "mock")]
+    fn as_mock(&self) -> Option<&mock::GpioMem> {
+        None
+    }
 }
@@ -3,15 +3,17 @@ use std::fs::OpenOptions;
 use std::io;
 use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 use std::ptr;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 
 use libc::{self, c_void, off_t, size_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
 
 use crate::gpio::gpiomem::GpioRegisters;
-use crate::gpio::{Bias, Error, Level, Mode, Result};
+use crate::gpio::{Backend, Bias, Error, Level, Mode, Result};
 use crate::system::{DeviceInfo, SoC};
 
 const PATH_DEV_GPIOMEM: &str = "/dev/gpiomem";
@@ -29,6 +31,15 @@ const GPPUDCLK0: usize = 0x98 / std::mem::size_of::<u32>();
 // Only available on BCM2711 (RPi4)
 const GPPUD_CNTRL_REG0: usize = 0xe4 / std::mem::size_of::<u32>();
 
+// The PADS registers are located outside of the GPIO address space, and can
+// only be accessed through /dev/mem.
+const PADS_OFFSET: u32 = 0x10_0000;
+const PADS_MEM_SIZE: usize = 0x38;
+// Pad control registers for GPIO 0-27, GPIO 28-45 and GPIO 46-53
+const PADS_GPIO_0_27: usize = 0x2c / std::mem::size_of::<u32>();
+const PADS_PASSWORD: u32 = 0x5a00_0000;
+const PADS_DRIVE_MASK: u32 = 0b111;
+
 const FSEL_INPUT: u8 = 0b000;
 const FSEL_OUTPUT: u8 = 0b001;
 const FSEL_ALT0: u8 = 0b100;
@@ -41,7 +52,11 @@ const FSEL_ALT5: u8 = 0b010;
 pub struct GpioMem {
     mem_ptr: *mut u32,
     locks: [AtomicBool; GPIO_MEM_REGISTERS],
+    // The PADS registers are mapped the first time they're needed. Null until then.
+    // The lock also prevents concurrent read-modify-write cycles.
+    pads_ptr: Mutex<*mut u32>,
     soc: SoC,
+    path: PathBuf,
 }
 
 impl fmt::Debug for GpioMem {
@@ -49,33 +64,27 @@ impl fmt::Debug for GpioMem {
         f.debug_struct("GpioMem")
             .field("mem_ptr", &self.mem_ptr)
             .field("locks", &format_args!("{{ .. }}"))
+            .field("pads_ptr", &self.pads_ptr)
             .field("soc", &self.soc)
+            .field("path", &self.path)
             .finish()
     }
 }
 
 impl GpioMem {
-    pub fn open() -> Result<GpioMem> {
-        // Try /dev/gpiomem first. If that fails, try /dev/mem instead. If neither works,
-        // report back the error that's the most relevant.
-        let mem_ptr = match Self::map_devgpiomem() {
-            Ok(ptr) => ptr,
-            Err(gpiomem_err) => match Self::map_devmem() {
-                Ok(ptr) => ptr,
+    pub fn open(path: Option<&Path>) -> Result<GpioMem> {
+        let (mem_ptr, path) = match path {
+            Some(path) => match Self::map_devgpiomem(path) {
                 Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::PermissionDenied => {
-                    // Did /dev/gpiomem also give us a Permission Denied error? If so, return
-                    // that path instead of /dev/mem. Solving /dev/gpiomem issues should be
-                    // preferred (add user to gpio group) over /dev/mem (use sudo),
-                    match gpiomem_err {
-                        Error::Io(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
-                            return Err(Error::PermissionDenied(String::from(PATH_DEV_GPIOMEM)));
-                        }
-                        _ => return Err(Error::PermissionDenied(String::from(PATH_DEV_MEM))),
-                    }
+                    return Err(Error::PermissionDenied(path.display().to_string()));
                 }
-                Err(Error::UnknownModel) => return Err(Error::UnknownModel),
-                _ => return Err(gpiomem_err),
+                mem_ptr => (mem_ptr?, path.to_path_buf()),
             },
+            None => {
+                let (mem_ptr, path) = Self::map_default()?;
+
+                (mem_ptr, PathBuf::from(path))
+            }
         };
 
         let locks = init_array!(AtomicBool::new(false), GPIO_MEM_REGISTERS);
@@ -86,12 +95,39 @@ impl GpioMem {
         Ok(GpioMem {
             mem_ptr,
             locks,
+            pads_ptr: Mutex::new(ptr::null_mut()),
             soc,
+            path,
         })
     }
 
-    fn map_devgpiomem() -> Result<*mut u32> {
-        // Open /dev/gpiomem with read/write/sync flags. This might fail if
+    // Returns the mapped memory, and the path of the device that was used
+    fn map_default() -> Result<(*mut u32, &'static str)> {
+        // Try /dev/gpiomem first. If that fails, try /dev/mem instead. If neither works,
+        // report back the error that's the most relevant.
+        match Self::map_devgpiomem(Path::new(PATH_DEV_GPIOMEM)) {
+            Ok(ptr) => Ok((ptr, PATH_DEV_GPIOMEM)),
+            Err(gpiomem_err) => match Self::map_devmem() {
+                Ok(ptr) => Ok((ptr, PATH_DEV_MEM)),
+                Err(Error::PermissionDenied(_)) => {
+                    // Did /dev/gpiomem also give us a Permission Denied error? If so, return
+                    // that path instead of /dev/mem. Solving /dev/gpiomem issues should be
+                    // preferred (add user to gpio group) over /dev/mem (use sudo),
+                    match gpiomem_err {
+                        Error::Io(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                            Err(Error::PermissionDenied(String::from(PATH_DEV_GPIOMEM)))
+                        }
+                        _ => Err(Error::PermissionDenied(String::from(PATH_DEV_MEM))),
+                    }
+                }
+                Err(Error::UnknownModel) => Err(Error::UnknownModel),
+                _ => Err(gpiomem_err),
+            },
+        }
+    }
+
+    fn map_devgpiomem(path: &Path) -> Result<*mut u32> {
+        // Open gpiomem with read/write/sync flags. This might fail if
         // /dev/gpiomem doesn't exist (< Raspbian Jessie), or /dev/gpiomem
         // doesn't have the appropriate permissions, or the current user is
         // not a member of the gpio group.
@@ -99,9 +135,9 @@ impl GpioMem {
             .read(true)
             .write(true)
             .custom_flags(O_SYNC)
-            .open(PATH_DEV_GPIOMEM)?;
+            .open(path)?;
 
-        // Memory-map /dev/gpiomem at offset 0
+        // Memory-map gpiomem at offset 0
         let gpiomem_ptr = unsafe {
             libc::mmap(
                 ptr::null_mut(),
@@ -124,11 +160,18 @@ impl GpioMem {
         // Identify which SoC we're using, so we know what offset to start at
         let device_info = DeviceInfo::new().map_err(|_| Error::UnknownModel)?;
 
-        let mem_file = OpenOptions::new()
+        let mem_file = match OpenOptions::new()
             .read(true)
             .write(true)
             .custom_flags(O_SYNC)
-            .open(PATH_DEV_MEM)?;
+            .open(PATH_DEV_MEM)
+        {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                return Err(Error::PermissionDenied(String::from(PATH_DEV_MEM)))
+            }
+            Err(e) => return Err(Error::Io(e)),
+        };
 
         // Memory-map /dev/mem at the appropriate offset for our SoC
         let mem_ptr = unsafe {
@@ -149,6 +192,41 @@ impl GpioMem {
         Ok(mem_ptr as *mut u32)
     }
 
+    fn map_devmem_pads() -> Result<*mut u32> {
+        let device_info = DeviceInfo::new().map_err(|_| Error::UnknownModel)?;
+
+        let mem_file = match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_SYNC)
+            .open(PATH_DEV_MEM)
+        {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                return Err(Error::PermissionDenied(String::from(PATH_DEV_MEM)))
+            }
+            Err(e) => return Err(Error::Io(e)),
+        };
+
+        // Memory-map /dev/mem at the PADS offset for our SoC
+        let mem_ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                PADS_MEM_SIZE,
+                PROT_READ | PROT_WRITE,
+                MAP_SHARED,
+                mem_file.as_raw_fd(),
+                (device_info.peripheral_base() + PADS_OFFSET) as off_t,
+            )
+        };
+
+        if mem_ptr == MAP_FAILED {
+            return Err(Error::Io(io::Error::last_os_error()));
+        }
+
+        Ok(mem_ptr as *mut u32)
+    }
+
     #[inline(always)]
     fn read(&self, offset: usize) -> u32 {
         unsafe { ptr::read_volatile(self.mem_ptr.add(offset)) }
@@ -167,10 +245,21 @@ impl Drop for GpioMem {
         unsafe {
             libc::munmap(self.mem_ptr as *mut c_void, GPIO_MEM_SIZE as size_t);
         }
+
+        let pads_ptr = *self.pads_ptr.get_mut().unwrap_or_else(|e| e.into_inner());
+        if !pads_ptr.is_null() {
+            unsafe {
+                libc::munmap(pads_ptr as *mut c_void, PADS_MEM_SIZE as size_t);
+            }
+        }
     }
 }
 
 impl GpioRegisters for GpioMem {
+    fn backend(&self) -> Backend {
+        Backend::Bcm(self.path.clone())
+    }
+
     #[inline(always)]
     fn set_high(&self, pin: u8) {
         let offset = GPSET0 + pin as usize / 32;
@@ -187,6 +276,21 @@ impl GpioRegisters for GpioMem {
         self.write(offset, 1 << shift);
     }
 
+    fn set_levels(&self, high: u64, low: u64) {
+        // GPSET0/GPCLR0 cover GPIO 0-31, GPSET1/GPCLR1 cover GPIO 32-57
+        for bank in 0..2 {
+            let high = (high >> (bank * 32)) as u32;
+            if high > 0 {
+                self.write(GPSET0 + bank, high);
+            }
+
+            let low = (low >> (bank * 32)) as u32;
+            if low > 0 {
+                self.write(GPCLR0 + bank, low);
+            }
+        }
+    }
+
     #[inline(always)]
     fn level(&self, pin: u8) -> Level {
         let offset = GPLEV0 + pin as usize / 32;
@@ -196,6 +300,11 @@ impl GpioRegisters for GpioMem {
         unsafe { std::mem::transmute((reg_value >> shift) as u8 & 0b1) }
     }
 
+    fn levels(&self) -> u64 {
+        // GPLEV0 covers GPIO 0-31, GPLEV1 covers GPIO 32-57
+        u64::from(self.read(GPLEV0)) | (u64::from(self.read(GPLEV0 + 1)) << 32)
+    }
+
     fn mode(&self, pin: u8) -> Mode {
         let offset = GPFSEL0 + pin as usize / 10;
         let shift = (pin % 10) * 3;
@@ -248,14 +357,32 @@ impl GpioRegisters for GpioMem {
         self.locks[offset].store(false, Ordering::SeqCst);
     }
 
+    fn bias(&self, pin: u8) -> Option<Bias> {
+        // Only the BCM2711 (RPi4) allows us to read back the current configuration.
+        // On older SoCs, GPPUD is write-only.
+        if self.soc != SoC::Bcm2711 {
+            return None;
+        }
+
+        let offset = GPPUD_CNTRL_REG0 + pin as usize / 16;
+        let shift = pin % 16 * 2;
+
+        match (self.read(offset) >> shift) & 0b11 {
+            0b00 => Some(Bias::Off),
+            0b01 => Some(Bias::PullUp),
+            0b10 => Some(Bias::PullDown),
+            _ => None,
+        }
+    }
+
     fn set_bias(&self, pin: u8, bias: Bias) {
         // Offset for register.
         let offset: usize;
         // Bit shift for pin position within register value.
         let shift: u8;
 
-        // BCM2711 (RPi4) and BCM2712 (RPi5) need special handling.
-        if self.soc == SoC::Bcm2711 || self.soc == SoC::Bcm2712 {
+        // BCM2711 (RPi4) needs special handling.
+        if self.soc == SoC::Bcm2711 {
             offset = GPPUD_CNTRL_REG0 + pin as usize / 16;
             shift = pin % 16 * 2;
 
@@ -326,6 +453,39 @@ impl GpioRegisters for GpioMem {
             self.locks[GPPUD].store(false, Ordering::SeqCst);
         }
     }
+
+    fn set_drive_strength(&self, pin: u8, milliamps: u8) -> Result<()> {
+        // Drive strength is configured in 2 mA steps, ranging from 2 mA (0b000) to 16 mA (0b111)
+        if !(2..=16).contains(&milliamps) || milliamps % 2 != 0 {
+            return Err(Error::InvalidValue);
+        }
+
+        let offset = PADS_GPIO_0_27
+            + match pin {
+                0..=27 => 0,
+                28..=45 => 1,
+                _ => 2,
+            };
+
+        let mut pads_ptr = self.pads_ptr.lock().unwrap_or_else(|e| e.into_inner());
+        if pads_ptr.is_null() {
+            *pads_ptr = Self::map_devmem_pads()?;
+        }
+        let pads_ptr = *pads_ptr;
+
+        unsafe {
+            let reg_value = ptr::read_volatile(pads_ptr.add(offset));
+            // Writes are ignored unless the top byte contains the password
+            ptr::write_volatile(
+                pads_ptr.add(offset),
+                PADS_PASSWORD
+                    | (reg_value & 0x00ff_ffff & !PADS_DRIVE_MASK)
+                    | (u32::from(milliamps / 2 - 1) & PADS_DRIVE_MASK),
+            );
+        }
+
+        Ok(())
+    }
 }
 
 // Required because of the raw pointer to our memory-mapped file
@@ -0,0 +1,230 @@
+use std::fs::{self, File};
+use std::os::unix::io::AsRawFd;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::gpio::gpiomem::GpioRegisters;
+use crate::gpio::ioctl::{self, HandleRequest, LineInfo};
+use crate::gpio::{Backend, Bias, Error, Level, Mode, Result};
+
+#[derive(Debug)]
+struct Line {
+    // Mode set through set_mode. None if the mode hasn't been changed.
+    mode: Option<Mode>,
+    // Bias set through set_bias. None if the bias hasn't been changed.
+    bias: Option<Bias>,
+    // Output level, which is applied when the line is configured as an output
+    latch: Level,
+    // Outputs need to be requested for as long as they're in use, or they
+    // might revert to their previous state. Inputs aren't requested, so they
+    // can still be used for interrupts.
+    handle: Option<HandleRequest>,
+}
+
+// GPIO lines accessed through the gpiochip character device, used when the
+// GPIO registers can't be memory-mapped. Only input and output modes are
+// supported, and the pull-up/pull-down state and output level of a line
+// can't be read back unless they were configured by us.
+#[derive(Debug)]
+pub struct GpioMem {
+    cdev: File,
+    lines: Mutex<Vec<Line>>,
+    path: PathBuf,
+}
+
+impl GpioMem {
+    pub fn open(cdev: &File, gpio_lines: u8) -> Result<GpioMem> {
+        let cdev = cdev.try_clone()?;
+
+        // Make sure the character device responds to line requests
+        LineInfo::with_pin(cdev.as_raw_fd(), 0)?;
+
+        let lines = (0..gpio_lines)
+            .map(|_| Line {
+                mode: None,
+                bias: None,
+                latch: Level::Low,
+                handle: None,
+            })
+            .collect();
+
+        // The gpiochip device may have been found by searching /dev, so look up
+        // its path through the file descriptor
+        let path = fs::read_link(format!("/proc/self/fd/{}", cdev.as_raw_fd())).unwrap_or_default();
+
+        Ok(GpioMem {
+            cdev,
+            lines: Mutex::new(lines),
+            path,
+        })
+    }
+
+    fn lines(&self) -> MutexGuard<'_, Vec<Line>> {
+        self.lines.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Requests pin with its current mode and bias, or releases it if it's not an output
+    fn configure(&self, pin: u8, line: &mut Line) -> Result<()> {
+        // Close the old handle before requesting a new one
+        line.handle = None;
+
+        match line.mode {
+            Some(Mode::Output) => {
+                line.handle = Some(HandleRequest::with_flags(
+                    self.cdev.as_raw_fd(),
+                    &[pin],
+                    ioctl::handle_flags(line.mode, line.bias),
+                    &[line.latch],
+                )?);
+            }
+            // An input only needs to be requested briefly to apply the direction and bias
+            _ if line.mode.is_some() || line.bias.is_some() => {
+                HandleRequest::with_flags(
+                    self.cdev.as_raw_fd(),
+                    &[pin],
+                    ioctl::handle_flags(Some(Mode::Input), line.bias),
+                    &[],
+                )?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    // Lines that are monitored for interrupts can't be requested separately,
+    // in which case the level is unavailable
+    fn line_level(&self, pin: u8, line: &Line) -> Result<Level> {
+        let handle_data = match line.handle {
+            Some(ref handle) => handle.levels()?,
+            // Requesting the line without any flags leaves its configuration unchanged
+            None => HandleRequest::new(self.cdev.as_raw_fd(), &[pin])?.levels()?,
+        };
+
+        if handle_data.values[0] > 0 {
+            Ok(Level::High)
+        } else {
+            Ok(Level::Low)
+        }
+    }
+
+    // The GpioRegisters getters can't return errors, so a failed read is logged,
+    // and reported as the last level written to the line.
+    fn line_level_or_latch(&self, pin: u8, line: &Line) -> Level {
+        self.line_level(pin, line).unwrap_or_else(|e| {
+            log_warn!("Failed to read the level of pin {}: {}", pin, e);
+            line.latch
+        })
+    }
+
+    fn set_line_level(&self, pin: u8, line: &mut Line, level: Level) {
+        line.latch = level;
+
+        if let Some(ref mut handle) = line.handle {
+            if let Err(e) = handle.set_levels(&[level]) {
+                log_warn!("Failed to set pin {} to {}: {}", pin, level, e);
+            }
+        }
+    }
+
+    fn reconfigure(&self, pin: u8, line: &mut Line) {
+        if let Err(e) = self.configure(pin, line) {
+            log_warn!("Failed to configure pin {}: {}", pin, e);
+        }
+    }
+}
+
+impl GpioRegisters for GpioMem {
+    fn backend(&self) -> Backend {
+        Backend::Cdev(self.path.clone())
+    }
+
+    fn set_high(&self, pin: u8) {
+        if let Some(line) = self.lines().get_mut(pin as usize) {
+            self.set_line_level(pin, line, Level::High);
+        }
+    }
+
+    fn set_low(&self, pin: u8) {
+        if let Some(line) = self.lines().get_mut(pin as usize) {
+            self.set_line_level(pin, line, Level::Low);
+        }
+    }
+
+    fn set_levels(&self, high: u64, low: u64) {
+        for (pin, line) in self.lines().iter_mut().enumerate() {
+            if high & (1 << pin) > 0 {
+                self.set_line_level(pin as u8, line, Level::High);
+            } else if low & (1 << pin) > 0 {
+                self.set_line_level(pin as u8, line, Level::Low);
+            }
+        }
+    }
+
+    fn level(&self, pin: u8) -> Level {
+        match self.lines().get(pin as usize) {
+            Some(line) => self.line_level_or_latch(pin, line),
+            None => Level::Low,
+        }
+    }
+
+    fn levels(&self) -> u64 {
+        self.lines()
+            .iter()
+            .enumerate()
+            .fold(0, |levels, (pin, line)| {
+                if self.line_level_or_latch(pin as u8, line) == Level::High {
+                    levels | (1 << pin)
+                } else {
+                    levels
+                }
+            })
+    }
+
+    fn mode(&self, pin: u8) -> Mode {
+        match self.lines().get(pin as usize) {
+            Some(Line {
+                mode: Some(mode), ..
+            }) => *mode,
+            _ => match LineInfo::with_pin(self.cdev.as_raw_fd(), pin) {
+                Ok(line_info) if line_info.is_output() => Mode::Output,
+                _ => Mode::Input,
+            },
+        }
+    }
+
+    fn set_mode(&self, pin: u8, mode: Mode) {
+        // Alternate functions can't be selected through the character device,
+        // so the line is left unchanged rather than reporting the wrong mode.
+        if mode != Mode::Input && mode != Mode::Output {
+            log_warn!(
+                "Failed to set pin {} to {}: alternate functions aren't supported by the {} backend",
+                pin,
+                mode,
+                self.backend()
+            );
+            return;
+        }
+
+        if let Some(line) = self.lines().get_mut(pin as usize) {
+            line.mode = Some(mode);
+            self.reconfigure(pin, line);
+        }
+    }
+
+    fn bias(&self, pin: u8) -> Option<Bias> {
+        self.lines().get(pin as usize).and_then(|line| line.bias)
+    }
+
+    fn set_bias(&self, pin: u8, bias: Bias) {
+        if let Some(line) = self.lines().get_mut(pin as usize) {
+            line.bias = Some(bias);
+            self.reconfigure(pin, line);
+        }
+    }
+
+    fn set_drive_strength(&self, _pin: u8, _milliamps: u8) -> Result<()> {
+        // The character device doesn't provide access to the pad control registers
+        Err(Error::InvalidValue)
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::Mutex;
+
+use crate::gpio::gpiomem::GpioRegisters;
+use crate::gpio::{Backend, Bias, Error, Level, Mode, Result};
+
+// Same number of GPIO lines as the BCM283x
+pub const GPIO_LINES: u8 = 54;
+
+#[derive(Debug)]
+struct State {
+    modes: [Mode; GPIO_LINES as usize],
+    biases: [Bias; GPIO_LINES as usize],
+    // Output latch, set through set_high/set_low
+    outputs: u64,
+    // Externally applied input levels, set through set_input_level
+    inputs: u64,
+    // Pins with an externally applied input level
+    inputs_set: u64,
+    // Level changes of pins configured as outputs, in the order they occurred
+    transitions: Vec<(u8, Level)>,
+}
+
+// In-memory GPIO registers, used when the mock feature is enabled and the
+// RPPAL_MOCK environment variable is set.
+#[derive(Debug)]
+pub struct GpioMem {
+    state: Mutex<State>,
+}
+
+impl GpioMem {
+    pub fn new() -> GpioMem {
+        GpioMem {
+            state: Mutex::new(State {
+                modes: [Mode::Input; GPIO_LINES as usize],
+                biases: [Bias::Off; GPIO_LINES as usize],
+                outputs: 0,
+                inputs: 0,
+                inputs_set: 0,
+                transitions: Vec::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Applies a change to the pin state, and records the level of any output pin that
+    // was either switched to a different level, or newly configured as an output.
+    fn update(&self, f: impl FnOnce(&mut State)) {
+        let mut state = self.state();
+        let outputs_before = state.outputs;
+        let output_pins_before = state.output_pins();
+
+        f(&mut state);
+
+        let changed = !output_pins_before | (outputs_before ^ state.outputs);
+        let changed = state.output_pins() & changed;
+        for pin in 0..GPIO_LINES {
+            if changed & (1 << pin) > 0 {
+                let level = state.level(pin);
+                state.transitions.push((pin, level));
+            }
+        }
+    }
+
+    // Returns and clears the recorded output transitions.
+    pub fn take_transitions(&self) -> Vec<(u8, Level)> {
+        std::mem::take(&mut self.state().transitions)
+    }
+
+    // Applies an external logic level to an input pin. None removes the
+    // level, after which the pin follows its pull-up/pull-down setting.
+    pub fn set_input_level(&self, pin: u8, level: Option<Level>) {
+        let mut state = self.state();
+        let mask = 1 << pin;
+
+        match level {
+            Some(Level::High) => {
+                state.inputs |= mask;
+                state.inputs_set |= mask;
+            }
+            Some(Level::Low) => {
+                state.inputs &= !mask;
+                state.inputs_set |= mask;
+            }
+            None => state.inputs_set &= !mask,
+        }
+    }
+}
+
+impl State {
+    fn output_pins(&self) -> u64 {
+        (0..GPIO_LINES).fold(0, |pins, pin| {
+            if self.modes[pin as usize] == Mode::Output {
+                pins | (1 << pin)
+            } else {
+                pins
+            }
+        })
+    }
+
+    fn level(&self, pin: u8) -> Level {
+        let mask = 1 << pin;
+
+        let high = if self.modes[pin as usize] == Mode::Output {
+            self.outputs & mask > 0
+        } else if self.inputs_set & mask > 0 {
+            self.inputs & mask > 0
+        } else {
+            self.biases[pin as usize] == Bias::PullUp
+        };
+
+        if high {
+            Level::High
+        } else {
+            Level::Low
+        }
+    }
+}
+
+impl GpioRegisters for GpioMem {
+    fn backend(&self) -> Backend {
+        Backend::Mock
+    }
+
+    fn set_high(&self, pin: u8) {
+        self.update(|state| state.outputs |= 1 << pin);
+    }
+
+    fn set_low(&self, pin: u8) {
+        self.update(|state| state.outputs &= !(1 << pin));
+    }
+
+    fn set_levels(&self, high: u64, low: u64) {
+        self.update(|state| state.outputs = (state.outputs | high) & !low);
+    }
+
+    fn level(&self, pin: u8) -> Level {
+        self.state().level(pin)
+    }
+
+    fn levels(&self) -> u64 {
+        let state = self.state();
+
+        (0..GPIO_LINES).fold(0, |levels, pin| {
+            if state.level(pin) == Level::High {
+                levels | (1 << pin)
+            } else {
+                levels
+            }
+        })
+    }
+
+    fn mode(&self, pin: u8) -> Mode {
+        self.state().modes[pin as usize]
+    }
+
+    fn set_mode(&self, pin: u8, mode: Mode) {
+        self.update(|state| state.modes[pin as usize] = mode);
+    }
+
+    fn bias(&self, pin: u8) -> Option<Bias> {
+        Some(self.state().biases[pin as usize])
+    }
+
+    fn set_bias(&self, pin: u8, bias: Bias) {
+        self.state().biases[pin as usize] = bias;
+    }
+
+    fn set_drive_strength(&self, _pin: u8, milliamps: u8) -> Result<()> {
+        // Accepts the same values as the BCM283x PADS registers
+        if !(2..=16).contains(&milliamps) || milliamps % 2 != 0 {
+            return Err(Error::InvalidValue);
+        }
+
+        Ok(())
+    }
+
+    fn as_mock(&self) -> Option<&GpioMem> {
+        Some(self)
+    }
+}
@@ -5,11 +5,12 @@ use std::fs::OpenOptions;
 use std::io;
 use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 use std::ptr;
 
 use libc::{self, c_void, size_t, MAP_FAILED, MAP_SHARED, O_SYNC, PROT_READ, PROT_WRITE};
 
-use crate::gpio::{Bias, Error, Level, Mode, Result};
+use crate::gpio::{Backend, Bias, Error, Level, Mode, Result};
 use crate::system::{DeviceInfo, SoC};
 
 use super::GpioRegisters;
@@ -73,6 +74,9 @@ const PADS_OUT_DISABLE_MASK: u32 = 0x80;
 const PADS_BIAS_MASK: u32 = 0x0c;
 const PADS_BIAS_LSB: u32 = 2;
 
+const PADS_DRIVE_MASK: u32 = 0x30;
+const PADS_DRIVE_LSB: u32 = 4;
+
 const PADS_BIAS_OFF: u32 = 0;
 const PADS_BIAS_DOWN: u32 = 1;
 const PADS_BIAS_UP: u32 = 2;
@@ -87,6 +91,7 @@ const RIO_IN: usize = 0x08;
 pub struct GpioMem {
     mem_ptr: *mut u32,
     soc: SoC,
+    path: PathBuf,
 }
 
 impl fmt::Debug for GpioMem {
@@ -94,21 +99,33 @@ impl fmt::Debug for GpioMem {
         f.debug_struct("GpioMem")
             .field("mem_ptr", &self.mem_ptr)
             .field("soc", &self.soc)
+            .field("path", &self.path)
             .finish()
     }
 }
 
 impl GpioMem {
-    pub fn open() -> Result<GpioMem> {
-        let mem_ptr = Self::map_devgpiomem()?;
+    pub fn open(path: Option<&Path>) -> Result<GpioMem> {
+        let path = path.unwrap_or_else(|| Path::new(PATH_DEV_GPIOMEM));
+
+        let mem_ptr = match Self::map_devgpiomem(path) {
+            Err(Error::Io(ref e)) if e.kind() == io::ErrorKind::PermissionDenied => {
+                return Err(Error::PermissionDenied(path.display().to_string()));
+            }
+            mem_ptr => mem_ptr?,
+        };
 
         // Identify which SoC we're using.
         let soc = DeviceInfo::new().map_err(|_| Error::UnknownModel)?.soc();
 
-        Ok(GpioMem { mem_ptr, soc })
+        Ok(GpioMem {
+            mem_ptr,
+            soc,
+            path: path.to_path_buf(),
+        })
     }
 
-    fn map_devgpiomem() -> Result<*mut u32> {
+    fn map_devgpiomem(path: &Path) -> Result<*mut u32> {
         // Open gpiomem with read/write/sync flags. This might fail if the
         // gpiomem cdev doesn't exist (< Raspbian Jessie), or gpiomem
         // doesn't have the appropriate permissions, or the current user is
@@ -117,7 +134,7 @@ impl GpioMem {
             .read(true)
             .write(true)
             .custom_flags(O_SYNC)
-            .open(PATH_DEV_GPIOMEM)?;
+            .open(path)?;
 
         // Memory-map gpiomem at offset 0
         let gpiomem_ptr = unsafe {
@@ -186,6 +203,10 @@ impl GpioMem {
 }
 
 impl GpioRegisters for GpioMem {
+    fn backend(&self) -> Backend {
+        Backend::Rp1(self.path.clone())
+    }
+
     #[inline(always)]
     fn set_high(&self, pin: u8) {
         let offset = (SYS_RIO0_OFFSET + RIO_OUT + SET_OFFSET) / REG_SIZE;
@@ -200,6 +221,19 @@ impl GpioRegisters for GpioMem {
         self.write(offset, 1 << pin);
     }
 
+    fn set_levels(&self, high: u64, low: u64) {
+        // SYS_RIO0 only covers GPIO 0-27
+        let high = high as u32;
+        if high > 0 {
+            self.write((SYS_RIO0_OFFSET + RIO_OUT + SET_OFFSET) / REG_SIZE, high);
+        }
+
+        let low = low as u32;
+        if low > 0 {
+            self.write((SYS_RIO0_OFFSET + RIO_OUT + CLR_OFFSET) / REG_SIZE, low);
+        }
+    }
+
     #[inline(always)]
     fn level(&self, pin: u8) -> Level {
         let offset = (SYS_RIO0_OFFSET + RIO_IN) / REG_SIZE;
@@ -208,6 +242,11 @@ impl GpioRegisters for GpioMem {
         unsafe { std::mem::transmute((reg_value >> pin) as u8 & 0b1) }
     }
 
+    fn levels(&self) -> u64 {
+        // SYS_RIO0 only covers GPIO 0-27
+        u64::from(self.read((SYS_RIO0_OFFSET + RIO_IN) / REG_SIZE))
+    }
+
     fn mode(&self, pin: u8) -> Mode {
         let offset =
             (IO_BANK0_OFFSET + GPIO_CTRL + (pin as usize * GPIO_OFFSET) + RW_OFFSET) / REG_SIZE;
@@ -261,6 +300,19 @@ impl GpioRegisters for GpioMem {
         self.write(offset, reg_value);
     }
 
+    fn bias(&self, pin: u8) -> Option<Bias> {
+        let offset =
+            (PADS_BANK0_OFFSET + PADS_GPIO + (pin as usize * PADS_OFFSET) + RW_OFFSET) / REG_SIZE;
+        let reg_value = self.read(offset);
+
+        match (reg_value & PADS_BIAS_MASK) >> PADS_BIAS_LSB {
+            PADS_BIAS_OFF => Some(Bias::Off),
+            PADS_BIAS_DOWN => Some(Bias::PullDown),
+            PADS_BIAS_UP => Some(Bias::PullUp),
+            _ => None,
+        }
+    }
+
     fn set_bias(&self, pin: u8, bias: Bias) {
         let offset =
             (PADS_BANK0_OFFSET + PADS_GPIO + (pin as usize * PADS_OFFSET) + RW_OFFSET) / REG_SIZE;
@@ -274,6 +326,27 @@ impl GpioRegisters for GpioMem {
 
         self.write(offset, reg_value);
     }
+
+    fn set_drive_strength(&self, pin: u8, milliamps: u8) -> Result<()> {
+        let drive = match milliamps {
+            2 => 0,
+            4 => 1,
+            8 => 2,
+            12 => 3,
+            _ => return Err(Error::InvalidValue),
+        };
+
+        let offset =
+            (PADS_BANK0_OFFSET + PADS_GPIO + (pin as usize * PADS_OFFSET) + RW_OFFSET) / REG_SIZE;
+        let reg_value = self.read(offset);
+
+        self.write(
+            offset,
+            (reg_value & !PADS_DRIVE_MASK) | (drive << PADS_DRIVE_LSB),
+        );
+
+        Ok(())
+    }
 }
 
 impl Drop for GpioMem {
@@ -0,0 +1,77 @@
+// BCM GPIO numbers for each position on the 40-pin GPIO header. Power and
+// ground pins are set to None.
+const HEADER_PINS: [Option<u8>; 40] = [
+    None,     // 1: 3.3 V
+    None,     // 2: 5 V
+    Some(2),  // 3
+    None,     // 4: 5 V
+    Some(3),  // 5
+    None,     // 6: Ground
+    Some(4),  // 7
+    Some(14), // 8
+    None,     // 9: Ground
+    Some(15), // 10
+    Some(17), // 11
+    Some(18), // 12
+    Some(27), // 13
+    None,     // 14: Ground
+    Some(22), // 15
+    Some(23), // 16
+    None,     // 17: 3.3 V
+    Some(24), // 18
+    Some(10), // 19
+    None,     // 20: Ground
+    Some(9),  // 21
+    Some(25), // 22
+    Some(11), // 23
+    Some(8),  // 24
+    None,     // 25: Ground
+    Some(7),  // 26
+    Some(0),  // 27
+    Some(1),  // 28
+    Some(5),  // 29
+    None,     // 30: Ground
+    Some(6),  // 31
+    Some(12), // 32
+    Some(13), // 33
+    None,     // 34: Ground
+    Some(19), // 35
+    Some(16), // 36
+    Some(26), // 37
+    Some(20), // 38
+    None,     // 39: Ground
+    Some(21), // 40
+];
+
+/// Returns the BCM GPIO number for the specified physical pin on the 40-pin GPIO header.
+///
+/// Physical pins are numbered from `1` to `40`, starting at the pin closest to the SD card
+/// slot (or the corner of the board on models without one), with odd numbers on the inner
+/// row and even numbers on the outer row.
+///
+/// Returns `None` if `header_pin` is a power or ground pin, or if it's outside of the
+/// range `1` to `40`.
+///
+/// The 40-pin layout also applies to physical pins `1` to `26` on the Raspberry Pi A and
+/// B Rev 2, which use a 26-pin header. The Raspberry Pi B Rev 1 uses a slightly different
+/// layout, where physical pins 3, 5 and 13 are connected to BCM GPIO 0, 1 and 21.
+pub fn bcm_from_physical(header_pin: u8) -> Option<u8> {
+    match header_pin {
+        1..=40 => HEADER_PINS[(header_pin - 1) as usize],
+        _ => None,
+    }
+}
+
+/// Returns the physical pin on the 40-pin GPIO header for the specified BCM GPIO number.
+///
+/// Returns `None` if the BCM GPIO pin isn't exposed on the GPIO header.
+///
+/// See [`bcm_from_physical`] for more details on the physical pin numbering.
+///
+/// [`bcm_from_physical`]: fn.bcm_from_physical.html
+pub fn physical_from_bcm(pin: u8) -> Option<u8> {
+    HEADER_PINS
+        .iter()
+        .position(|&bcm| bcm == Some(pin))
+        .map(|index| index as u8 + 1)
+}
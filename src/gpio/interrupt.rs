@@ -1,29 +1,91 @@
 #![allow(dead_code)]
 
 use std::fmt;
+use std::io;
+use std::mem;
+use std::os::unix::thread::JoinHandleExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use crate::gpio::epoll::{epoll_event, Epoll, EventFd, EPOLLERR, EPOLLET, EPOLLIN, EPOLLPRI};
 use crate::gpio::ioctl;
 use crate::gpio::pin::InputPin;
-use crate::gpio::{Error, Level, Result, Trigger};
+use crate::gpio::{EdgeEvent, Error, Level, Result, SchedPolicy, Trigger};
+use crate::time::Instant;
+
+// Statistics shared between an InputPin and its (a)synchronous interrupts
+#[derive(Debug, Default)]
+pub struct InterruptStats {
+    overruns: AtomicUsize,
+    count: AtomicUsize,
+    last_interrupt: Mutex<Option<Instant>>,
+}
+
+impl InterruptStats {
+    pub fn overruns(&self) -> usize {
+        self.overruns.load(Ordering::Relaxed)
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn last_interrupt(&self) -> Option<Instant> {
+        *self
+            .last_interrupt
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn record(&self, timestamp: Instant) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        *self
+            .last_interrupt
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(timestamp);
+    }
+}
 
 #[derive(Debug)]
 struct Interrupt {
     pin: u8,
     trigger: Trigger,
+    debounce: Option<Duration>,
+    last_timestamp: Option<Duration>,
+    last_level: Option<Level>,
+    stats: Arc<InterruptStats>,
     cdev_fd: i32,
     event_request: ioctl::EventRequest,
 }
 
 impl Interrupt {
-    fn new(cdev_fd: i32, pin: u8, trigger: Trigger) -> Result<Interrupt> {
+    fn new(
+        cdev_fd: i32,
+        pin: u8,
+        trigger: Trigger,
+        debounce: Option<Duration>,
+        stats: Arc<InterruptStats>,
+    ) -> Result<Interrupt> {
+        let event_request = ioctl::EventRequest::new(cdev_fd, pin, trigger)?;
+
+        log_debug!(
+            "Configured interrupt on pin {} (trigger: {}, debounce: {:?})",
+            pin,
+            trigger,
+            debounce
+        );
+
         Ok(Interrupt {
             pin,
             trigger,
+            debounce,
+            last_timestamp: None,
+            last_level: None,
+            stats,
             cdev_fd,
-            event_request: ioctl::EventRequest::new(cdev_fd, pin, trigger)?,
+            event_request,
         })
     }
 
@@ -45,25 +107,117 @@ impl Interrupt {
         self.reset()
     }
 
-    fn event(&mut self) -> Result<ioctl::Event> {
+    fn set_debounce(&mut self, debounce: Option<Duration>) {
+        self.debounce = debounce;
+        self.last_timestamp = None;
+    }
+
+    fn event(&mut self) -> Result<Option<ioctl::Event>> {
         // This might block if there are no events waiting
-        ioctl::get_event(self.event_request.fd)
+        let event = ioctl::get_event(self.event_request.fd)?;
+
+        Ok(self.process(event))
+    }
+
+    // Reads a single batch of queued events without blocking, and appends the ones
+    // that pass the debounce filter to events. The batch is larger than the kernel's
+    // event buffer, and reading only once keeps a pin that triggers continuously from
+    // stalling the caller.
+    fn drain(&mut self, events: &mut Vec<EdgeEvent>) -> Result<usize> {
+        let len = events.len();
+
+        if self.is_readable()? {
+            let fd = self.event_request.fd;
+
+            ioctl::get_events(fd, |event| {
+                if let Some(event) = self.process(event) {
+                    events.push(EdgeEvent {
+                        level: event.level(),
+                        instant: Instant::from_nanos(event.timestamp().as_nanos()),
+                    });
+                }
+            })?;
+        }
+
+        Ok(events.len() - len)
+    }
+
+    // Returns true if at least one event is waiting to be read
+    fn is_readable(&self) -> Result<bool> {
+        let mut pfd = libc::pollfd {
+            fd: self.event_request.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        Ok(parse_retval!(unsafe { libc::poll(&mut pfd, 1, 0) })? > 0)
+    }
+
+    fn process(&mut self, event: ioctl::Event) -> Option<ioctl::Event> {
+        self.stats
+            .record(Instant::from_nanos(event.timestamp().as_nanos()));
+
+        // The kernel discards new events when its event buffer is full, without reporting
+        // it through the v1 ABI. When both edges are monitored, two consecutive events with
+        // the same level indicate at least one edge was lost. This is only a lower bound,
+        // since an even number of lost edges can't be detected.
+        if self.trigger == Trigger::Both
+            && self.last_level.replace(event.level()) == Some(event.level())
+        {
+            self.stats.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+
+        // Any edge that occurs within the debounce period of the previous edge
+        // is discarded. Every discarded edge restarts the debounce period, so
+        // contact bounce that continues past the end of the initial period
+        // doesn't result in additional events.
+        if let Some(debounce) = self.debounce {
+            if let Some(last_timestamp) = self.last_timestamp.replace(event.timestamp()) {
+                if event.timestamp().saturating_sub(last_timestamp) < debounce {
+                    return None;
+                }
+            }
+        }
+
+        log_trace!(
+            "Interrupt on pin {} (level: {}, timestamp: {:?})",
+            self.pin,
+            event.level(),
+            Instant::from_nanos(event.timestamp().as_nanos())
+        );
+
+        Some(event)
     }
 
     fn reset(&mut self) -> Result<()> {
         // Close the old event fd before opening a new one
         self.event_request.close();
         self.event_request = ioctl::EventRequest::new(self.cdev_fd, self.pin, self.trigger)?;
+        self.last_level = None;
+
+        log_debug!(
+            "Reconfigured interrupt on pin {} (trigger: {})",
+            self.pin,
+            self.trigger
+        );
 
         Ok(())
     }
 }
 
+#[cfg(feature = "log")]
+impl Drop for Interrupt {
+    fn drop(&mut self) {
+        log_debug!("Removed interrupt on pin {}", self.pin);
+    }
+}
+
 #[derive(Debug)]
 struct TriggerStatus {
     interrupt: Option<Interrupt>,
     triggered: bool,
     level: Level,
+    timestamp: Instant,
 }
 
 pub struct EventLoop {
@@ -94,6 +248,7 @@ impl EventLoop {
                 interrupt: None,
                 triggered: false,
                 level: Level::Low,
+                timestamp: Instant::from_nanos(0),
             });
         }
 
@@ -110,7 +265,7 @@ impl EventLoop {
         pins: &[&'a InputPin],
         reset: bool,
         timeout: Option<Duration>,
-    ) -> Result<Option<(&'a InputPin, Level)>> {
+    ) -> Result<Option<(&'a InputPin, Level, Instant)>> {
         for pin in pins {
             let trigger_status = &mut self.trigger_status[pin.pin() as usize];
 
@@ -119,7 +274,7 @@ impl EventLoop {
                 trigger_status.triggered = false;
 
                 if !reset {
-                    return Ok(Some((pin, trigger_status.level)));
+                    return Ok(Some((pin, trigger_status.level, trigger_status.timestamp)));
                 }
             }
 
@@ -138,7 +293,7 @@ impl EventLoop {
         }
 
         // Loop until we get any of the events we're waiting for, or a timeout occurs
-        let now = Instant::now();
+        let now = std::time::Instant::now();
         loop {
             let num_events = self.poll.wait(&mut self.events, timeout)?;
 
@@ -153,8 +308,12 @@ impl EventLoop {
                 let trigger_status = &mut self.trigger_status[pin];
 
                 if let Some(ref mut interrupt) = trigger_status.interrupt {
-                    trigger_status.level = interrupt.event()?.level();
-                    trigger_status.triggered = true;
+                    if let Some(event) = interrupt.event()? {
+                        trigger_status.level = event.level();
+                        trigger_status.timestamp =
+                            Instant::from_nanos(event.timestamp().as_nanos());
+                        trigger_status.triggered = true;
+                    }
                 };
             }
 
@@ -165,7 +324,7 @@ impl EventLoop {
 
                 if trigger_status.triggered {
                     trigger_status.triggered = false;
-                    return Ok(Some((pin, trigger_status.level)));
+                    return Ok(Some((pin, trigger_status.level, trigger_status.timestamp)));
                 }
             }
 
@@ -181,13 +340,22 @@ impl EventLoop {
         }
     }
 
-    pub fn set_interrupt(&mut self, pin: u8, trigger: Trigger) -> Result<()> {
+    pub fn set_interrupt(
+        &mut self,
+        pin: u8,
+        trigger: Trigger,
+        debounce: Option<Duration>,
+        stats: Arc<InterruptStats>,
+    ) -> Result<()> {
         let trigger_status = &mut self.trigger_status[pin as usize];
 
         trigger_status.triggered = false;
 
         // Interrupt already exists. We just need to change the trigger.
         if let Some(ref mut interrupt) = trigger_status.interrupt {
+            interrupt.set_debounce(debounce);
+            interrupt.stats = stats;
+
             if interrupt.trigger != trigger {
                 // This requires a new event request, so the fd might change
                 self.poll.delete(interrupt.fd())?;
@@ -200,7 +368,7 @@ impl EventLoop {
         }
 
         // Register a new interrupt
-        let interrupt = Interrupt::new(self.cdev_fd, pin, trigger)?;
+        let interrupt = Interrupt::new(self.cdev_fd, pin, trigger, debounce, stats)?;
         self.poll
             .add(interrupt.fd(), u64::from(pin), EPOLLIN | EPOLLPRI)?;
         trigger_status.interrupt = Some(interrupt);
@@ -208,6 +376,28 @@ impl EventLoop {
         Ok(())
     }
 
+    pub fn drain_events(&mut self, pin: u8, events: &mut Vec<EdgeEvent>) -> Result<usize> {
+        let trigger_status = &mut self.trigger_status[pin as usize];
+        let mut count = 0;
+
+        // Return any event cached during a previous poll first
+        if trigger_status.triggered {
+            trigger_status.triggered = false;
+
+            events.push(EdgeEvent {
+                level: trigger_status.level,
+                instant: trigger_status.timestamp,
+            });
+            count += 1;
+        }
+
+        if let Some(ref mut interrupt) = trigger_status.interrupt {
+            count += interrupt.drain(events)?;
+        }
+
+        Ok(count)
+    }
+
     pub fn clear_interrupt(&mut self, pin: u8) -> Result<()> {
         let trigger_status = &mut self.trigger_status[pin as usize];
 
@@ -221,6 +411,189 @@ impl EventLoop {
     }
 }
 
+// Blocks until a complete pulse at the specified level has been detected, and
+// returns its duration based on the kernel's event timestamps. A new event
+// request is used, so the pin can't have any other interrupts configured.
+pub fn measure_pulse(cdev_fd: i32, pin: u8, level: Level, timeout: Duration) -> Result<Duration> {
+    let deadline = std::time::Instant::now() + timeout;
+
+    let mut interrupt = Interrupt::new(cdev_fd, pin, Trigger::Both, None, Arc::default())?;
+
+    let poll = Epoll::new()?;
+    poll.add(interrupt.fd(), u64::from(pin), EPOLLIN | EPOLLPRI)?;
+
+    let mut events = [epoll_event { events: 0, u64: 0 }; 1];
+    let mut start: Option<Duration> = None;
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if poll.wait(&mut events, Some(remaining))? == 0 {
+            return Err(Error::TimedOut);
+        }
+
+        if let Some(event) = interrupt.event()? {
+            match start {
+                // Any edge that leaves the pin at the target level marks the start of the pulse
+                None if event.level() == level => start = Some(event.timestamp()),
+                Some(start) if event.level() != level => {
+                    return Ok(event.timestamp().saturating_sub(start))
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+// Blocks until the pin is at the specified level. The event request is created before
+// the current level is checked, so an edge that occurs in between isn't missed. A new
+// event request is used, so the pin can't have any other interrupts configured.
+pub fn wait_for_level(cdev_fd: i32, pin: u8, level: Level, timeout: Duration) -> Result<()> {
+    let deadline = std::time::Instant::now() + timeout;
+
+    let trigger = match level {
+        Level::Low => Trigger::FallingEdge,
+        Level::High => Trigger::RisingEdge,
+    };
+
+    let mut interrupt = Interrupt::new(cdev_fd, pin, trigger, None, Arc::default())?;
+
+    if interrupt.event_request.level()? == level {
+        return Ok(());
+    }
+
+    let poll = Epoll::new()?;
+    poll.add(interrupt.fd(), u64::from(pin), EPOLLIN | EPOLLPRI)?;
+
+    let mut events = [epoll_event { events: 0, u64: 0 }; 1];
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if poll.wait(&mut events, Some(remaining))? == 0 {
+            return Err(Error::TimedOut);
+        }
+
+        if interrupt.event()?.is_some() {
+            return Ok(());
+        }
+    }
+}
+
+// Counts the number of trigger events that occur within the specified time window.
+// A new event request is used, which is closed again before returning.
+pub fn count_edges(cdev_fd: i32, pin: u8, trigger: Trigger, window: Duration) -> Result<u32> {
+    let mut interrupt = Interrupt::new(cdev_fd, pin, trigger, None, Arc::default())?;
+
+    // Event timestamps use the same clock, so we can determine whether any events
+    // that are still queued after the deadline passes occurred within the window.
+    let end = Instant::now() + window;
+
+    let poll = Epoll::new()?;
+    poll.add(interrupt.fd(), u64::from(pin), EPOLLIN | EPOLLPRI)?;
+
+    let mut events = [epoll_event { events: 0, u64: 0 }; 1];
+    let mut count: u32 = 0;
+    loop {
+        let now = Instant::now();
+        let remaining = if now < end { end - now } else { Duration::ZERO };
+
+        if poll.wait(&mut events, Some(remaining))? == 0 {
+            return Ok(count);
+        }
+
+        if let Some(event) = interrupt.event()? {
+            if Instant::from_nanos(event.timestamp().as_nanos()) > end {
+                return Ok(count);
+            }
+
+            count = count.saturating_add(1);
+        }
+    }
+}
+
+// Captures the timestamps of the specified number of rising edges, and returns the
+// frequency based on the average period between them. A new event request is used,
+// which is closed again before returning.
+pub fn measure_frequency(cdev_fd: i32, pin: u8, samples: usize, timeout: Duration) -> Result<f64> {
+    if samples < 2 {
+        return Err(Error::InvalidValue);
+    }
+
+    let deadline = std::time::Instant::now() + timeout;
+
+    let mut interrupt = Interrupt::new(cdev_fd, pin, Trigger::RisingEdge, None, Arc::default())?;
+
+    let poll = Epoll::new()?;
+    poll.add(interrupt.fd(), u64::from(pin), EPOLLIN | EPOLLPRI)?;
+
+    let mut events = [epoll_event { events: 0, u64: 0 }; 1];
+    let mut first: Option<Duration> = None;
+    let mut captured: usize = 0;
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if poll.wait(&mut events, Some(remaining))? == 0 {
+            return Err(Error::IncompleteMeasurement(captured));
+        }
+
+        if let Some(event) = interrupt.event()? {
+            captured += 1;
+
+            match first {
+                // The time before the first edge is only a partial period, so the
+                // measurement starts at the first edge
+                None => first = Some(event.timestamp()),
+                Some(first) if captured == samples => {
+                    let elapsed = event.timestamp().saturating_sub(first).as_secs_f64();
+
+                    return if elapsed > 0.0 {
+                        Ok((samples - 1) as f64 / elapsed)
+                    } else {
+                        Ok(0.0)
+                    };
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+// Maximum number of CPUs that fit in a cpu_set_t
+pub const MAX_CPUS: usize = 8 * mem::size_of::<libc::cpu_set_t>();
+
+// Scheduling settings for the threads that handle asynchronous interrupts
+#[derive(Debug, Clone, Default)]
+pub struct ThreadConfig {
+    pub priority: Option<(SchedPolicy, u8)>,
+    pub affinity: Option<Vec<usize>>,
+}
+
+impl ThreadConfig {
+    fn apply(&self, thread: libc::pthread_t) -> Result<()> {
+        if let Some((policy, priority)) = self.priority {
+            let mut param: libc::sched_param = unsafe { mem::zeroed() };
+            param.sched_priority = priority.into();
+
+            let result = unsafe { libc::pthread_setschedparam(thread, policy.as_raw(), &param) };
+            if result != 0 {
+                return Err(Error::Io(io::Error::from_raw_os_error(result)));
+            }
+        }
+
+        if let Some(ref cpus) = self.affinity {
+            let mut cpu_set: libc::cpu_set_t = unsafe { mem::zeroed() };
+            for &cpu in cpus {
+                unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+            }
+
+            let result = unsafe {
+                libc::pthread_setaffinity_np(thread, mem::size_of::<libc::cpu_set_t>(), &cpu_set)
+            };
+            if result != 0 {
+                return Err(Error::Io(io::Error::from_raw_os_error(result)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct AsyncInterrupt {
     poll_thread: Option<thread::JoinHandle<Result<()>>>,
@@ -228,9 +601,17 @@ pub struct AsyncInterrupt {
 }
 
 impl AsyncInterrupt {
-    pub fn new<C>(fd: i32, pin: u8, trigger: Trigger, mut callback: C) -> Result<AsyncInterrupt>
+    pub fn new<C>(
+        fd: i32,
+        pin: u8,
+        trigger: Trigger,
+        debounce: Option<Duration>,
+        stats: Arc<InterruptStats>,
+        thread_config: ThreadConfig,
+        mut callback: C,
+    ) -> Result<AsyncInterrupt>
     where
-        C: FnMut(Level) + Send + 'static,
+        C: FnMut(ioctl::Event) + Send + 'static,
     {
         let tx = EventFd::new()?;
         let rx = tx.fd();
@@ -241,7 +622,7 @@ impl AsyncInterrupt {
             // rx becomes readable when the main thread calls notify()
             poll.add(rx, rx as u64, EPOLLERR | EPOLLET | EPOLLIN)?;
 
-            let mut interrupt = Interrupt::new(fd, pin, trigger)?;
+            let mut interrupt = Interrupt::new(fd, pin, trigger, debounce, stats)?;
             poll.add(interrupt.fd(), interrupt.fd() as u64, EPOLLIN | EPOLLPRI)?;
 
             let mut events = [epoll_event { events: 0, u64: 0 }; 2];
@@ -253,18 +634,26 @@ impl AsyncInterrupt {
                         if fd == rx {
                             return Ok(()); // The main thread asked us to stop
                         } else if fd == interrupt.fd() {
-                            let level = interrupt.event()?.level();
-                            callback(level);
+                            if let Some(event) = interrupt.event()? {
+                                callback(event);
+                            }
                         }
                     }
                 }
             }
         });
 
-        Ok(AsyncInterrupt {
+        let pthread = poll_thread.as_pthread_t();
+        let async_interrupt = AsyncInterrupt {
             poll_thread: Some(poll_thread),
             tx,
-        })
+        };
+
+        // If the settings can't be applied, the poll thread is stopped when
+        // async_interrupt is dropped.
+        thread_config.apply(pthread)?;
+
+        Ok(async_interrupt)
     }
 
     pub fn stop(&mut self) -> Result<()> {
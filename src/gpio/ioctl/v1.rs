@@ -1,7 +1,7 @@
 #![allow(clippy::unnecessary_cast)]
 #![allow(dead_code)]
 
-use crate::gpio::{Error, Level, Result, Trigger};
+use crate::gpio::{Bias, Error, Level, Mode, Result, Trigger};
 use libc::{self, c_int, c_ulong, c_void, ENOENT};
 use std::ffi::CString;
 use std::fmt;
@@ -9,6 +9,7 @@ use std::fs::{File, OpenOptions};
 use std::io;
 use std::mem;
 use std::os::unix::io::AsRawFd;
+use std::path::Path;
 use std::time::Duration;
 
 #[cfg(target_env = "gnu")]
@@ -121,6 +122,64 @@ impl LineInfo {
             consumer: [0u8; LABEL_BUFSIZE],
         }
     }
+
+    pub fn with_pin(cdev_fd: i32, pin: u8) -> Result<LineInfo> {
+        let mut line_info = LineInfo::new();
+        line_info.line_offset = u32::from(pin);
+
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_INFO, &mut line_info) })?;
+
+        Ok(line_info)
+    }
+
+    pub fn is_output(&self) -> bool {
+        (self.flags & LINE_FLAG_IS_OUT) > 0
+    }
+
+    // Line is requested by a process or kernel driver
+    pub fn is_used(&self) -> bool {
+        (self.flags & LINE_FLAG_KERNEL) > 0
+    }
+
+    pub fn consumer(&self) -> String {
+        cbuf_to_cstring(&self.consumer)
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+// Returns the consumer label if the line is in use by another process or a kernel
+// driver. Lines requested with our own consumer label are ignored.
+pub fn line_consumer(cdev_fd: i32, pin: u8) -> Result<Option<String>> {
+    let line_info = LineInfo::with_pin(cdev_fd, pin)?;
+
+    if line_info.is_used() && line_info.consumer() != CONSUMER_LABEL {
+        Ok(Some(line_info.consumer()))
+    } else {
+        Ok(None)
+    }
+}
+
+// Replaces an EBUSY error returned by a line request with Error::PinClaimed for
+// the first of the requested pins that's in use by a process or kernel driver.
+// Lines requested with our own label, such as pins monitored for interrupts,
+// are reported as Error::PinUsed instead, the same way line_consumer ignores them.
+fn claimed_error(err: io::Error, cdev_fd: i32, pins: &[u8]) -> Error {
+    if err.raw_os_error() != Some(libc::EBUSY) {
+        return Error::Io(err);
+    }
+
+    pins.iter()
+        .find_map(|&pin| match LineInfo::with_pin(cdev_fd, pin) {
+            Ok(line_info) if line_info.is_used() && line_info.consumer() == CONSUMER_LABEL => {
+                Some(Error::PinUsed(pin))
+            }
+            Ok(line_info) if line_info.is_used() => {
+                Some(Error::PinClaimed(pin, line_info.consumer()))
+            }
+            _ => None,
+        })
+        .unwrap_or(Error::Io(err))
 }
 
 impl fmt::Debug for LineInfo {
@@ -140,6 +199,28 @@ const HANDLE_FLAG_OUTPUT: u32 = 0x02;
 const HANDLE_FLAG_ACTIVE_LOW: u32 = 0x04;
 const HANDLE_FLAG_OPEN_DRAIN: u32 = 0x08;
 const HANDLE_FLAG_OPEN_SOURCE: u32 = 0x10;
+// Bias flags require kernel 5.5 or later, and can only be combined with a direction flag
+const HANDLE_FLAG_BIAS_PULL_UP: u32 = 0x20;
+const HANDLE_FLAG_BIAS_PULL_DOWN: u32 = 0x40;
+const HANDLE_FLAG_BIAS_DISABLE: u32 = 0x80;
+
+// Returns the handle request flags for the specified mode and bias. If mode is
+// None, the line's current direction is left unchanged, and bias is ignored.
+pub fn handle_flags(mode: Option<Mode>, bias: Option<Bias>) -> u32 {
+    let direction = match mode {
+        Some(Mode::Output) => HANDLE_FLAG_OUTPUT,
+        Some(_) => HANDLE_FLAG_INPUT,
+        None => return 0,
+    };
+
+    direction
+        | match bias {
+            Some(Bias::Off) => HANDLE_FLAG_BIAS_DISABLE,
+            Some(Bias::PullDown) => HANDLE_FLAG_BIAS_PULL_DOWN,
+            Some(Bias::PullUp) => HANDLE_FLAG_BIAS_PULL_UP,
+            None => 0,
+        }
+}
 
 #[repr(C)]
 pub struct HandleRequest {
@@ -153,9 +234,19 @@ pub struct HandleRequest {
 
 impl HandleRequest {
     pub fn new(cdev_fd: c_int, pins: &[u8]) -> Result<HandleRequest> {
+        HandleRequest::with_flags(cdev_fd, pins, 0, &[])
+    }
+
+    // default_values sets the initial level for lines that are configured as outputs
+    pub fn with_flags(
+        cdev_fd: i32,
+        pins: &[u8],
+        flags: u32,
+        default_values: &[Level],
+    ) -> Result<HandleRequest> {
         let mut handle_request = HandleRequest {
             line_offsets: [0u32; HANDLES_MAX],
-            flags: 0,
+            flags,
             default_values: [0u8; HANDLES_MAX],
             consumer_label: [0u8; LABEL_BUFSIZE],
             lines: 0,
@@ -174,11 +265,16 @@ impl HandleRequest {
             handle_request.line_offsets[idx] = u32::from(*pin);
         }
 
+        for (idx, level) in default_values.iter().take(HANDLES_MAX).enumerate() {
+            handle_request.default_values[idx] = *level as u8;
+        }
+
         // Set consumer label, so other processes know we're using these pins
         handle_request.consumer_label[0..CONSUMER_LABEL.len()]
             .copy_from_slice(CONSUMER_LABEL.as_bytes());
 
-        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_HANDLE, &mut handle_request) })?;
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_HANDLE, &mut handle_request) })
+            .map_err(|e| claimed_error(e, cdev_fd, pins))?;
 
         // If the handle fd is zero or negative, an error occurred
         if handle_request.fd <= 0 {
@@ -298,7 +394,8 @@ impl EventRequest {
         event_request.consumer_label[0..CONSUMER_LABEL.len()]
             .copy_from_slice(CONSUMER_LABEL.as_bytes());
 
-        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_EVENT, &mut event_request) })?;
+        parse_retval!(unsafe { libc::ioctl(cdev_fd, REQ_GET_LINE_EVENT, &mut event_request) })
+            .map_err(|e| claimed_error(e, cdev_fd, &[pin]))?;
 
         // If the event fd is zero or negative, an error occurred
         if event_request.fd <= 0 {
@@ -308,6 +405,15 @@ impl EventRequest {
         }
     }
 
+    // Read the current level of the monitored line
+    pub fn level(&self) -> Result<Level> {
+        let mut handle_data = HandleData::new();
+
+        parse_retval!(unsafe { libc::ioctl(self.fd, REQ_GET_LINE_VALUES, &mut handle_data) })?;
+
+        Ok(Level::from(handle_data.values[0]))
+    }
+
     pub fn close(&mut self) {
         if self.fd > 0 {
             unsafe {
@@ -337,6 +443,9 @@ impl fmt::Debug for EventRequest {
     }
 }
 
+// Maximum number of events read by get_events in a single call
+const EVENT_BATCH_SIZE: usize = 64;
+
 const EVENT_TYPE_RISING_EDGE: u32 = 0x01;
 const EVENT_TYPE_FALLING_EDGE: u32 = 0x02;
 
@@ -396,6 +505,10 @@ impl Event {
         self.trigger
     }
 
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
     pub fn level(&self) -> Level {
         match self.trigger {
             Trigger::RisingEdge => Level::High,
@@ -414,6 +527,45 @@ pub fn get_event(event_fd: c_int) -> Result<Event> {
     Ok(Event::from_event_data(event_data))
 }
 
+// Read up to EVENT_BATCH_SIZE interrupt events with a single read() call, and
+// pass each of them to f. The kernel copies as many queued events as fit, and only
+// blocks if none are available.
+pub fn get_events<F: FnMut(Event)>(event_fd: i32, f: F) -> Result<usize> {
+    let mut event_data = [EventData::default(); EVENT_BATCH_SIZE];
+
+    let bytes_read = parse_retval!(unsafe {
+        libc::read(
+            event_fd,
+            event_data.as_mut_ptr() as *mut c_void,
+            mem::size_of_val(&event_data),
+        )
+    })? as usize;
+
+    let num_events = bytes_read / mem::size_of::<EventData>();
+    event_data[..num_events]
+        .iter()
+        .map(|&data| Event::from_event_data(data))
+        .for_each(f);
+
+    Ok(num_events)
+}
+
+// Open the specified gpiochip device, without checking its label
+pub fn open_gpiochip(path: &Path) -> Result<File> {
+    match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => {
+            // Make sure we're dealing with a gpiochip device
+            ChipInfo::new(file.as_raw_fd())?;
+
+            Ok(file)
+        }
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            Err(Error::PermissionDenied(path.display().to_string()))
+        }
+        Err(e) => Err(Error::from(e)),
+    }
+}
+
 // Find the correct gpiochip device based on its label
 pub fn find_gpiochip() -> Result<File> {
     for id in 0..=255 {
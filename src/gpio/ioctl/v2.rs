@@ -687,6 +687,10 @@ impl Event {
         self.trigger
     }
 
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+
     pub fn level(&self) -> Level {
         match self.trigger {
             Trigger::RisingEdge => Level::High,
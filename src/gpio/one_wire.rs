@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use crate::gpio::{Level, OutputPin};
+use crate::time;
+
+// Standard speed timing, as recommended by Maxim Integrated's application note 126.
+// Write 1: drive low for A, release for B. Write 0: drive low for C, release for D.
+// Read: drive low for A, sample after E, wait for F. Reset: drive low for H, sample
+// after I, wait for J.
+const TIME_A: Duration = Duration::from_micros(6);
+const TIME_B: Duration = Duration::from_micros(64);
+const TIME_C: Duration = Duration::from_micros(60);
+const TIME_D: Duration = Duration::from_micros(10);
+const TIME_E: Duration = Duration::from_micros(9);
+const TIME_F: Duration = Duration::from_micros(55);
+const TIME_H: Duration = Duration::from_micros(480);
+const TIME_I: Duration = Duration::from_micros(70);
+const TIME_J: Duration = Duration::from_micros(410);
+
+/// Bit-banged 1-Wire bus master.
+///
+/// `OneWire` implements the low-level 1-Wire primitives on a single GPIO pin, using
+/// the standard speed timing. The reset and presence pulse, and the individual read and
+/// write time slots, can be used to build drivers for devices such as the DS18B20
+/// temperature sensor.
+///
+/// The pin is configured as an open-drain output, which only actively drives the line
+/// low. An external pull-up resistor (usually 4.7 kΩ) to 3.3 V is needed to pull the
+/// line high while the bus is idle. More information on open-drain output can be found
+/// in the documentation for [`OutputPin::set_open_drain`].
+///
+/// The time slots are generated by busy-waiting on the monotonic system clock through
+/// [`time::sleep_busy`], which keeps the CPU busy for the duration of each operation.
+/// A userspace thread can still be preempted at any time. If the kernel interrupts the
+/// thread while the line is held low, the time slot may be stretched enough to corrupt
+/// the transferred bit, so any data read from the bus should be checked for errors, for
+/// instance by verifying the CRC included by most devices. Running the thread with a
+/// real-time scheduling policy reduces the chance of that occurring.
+///
+/// [`OutputPin::set_open_drain`]: struct.OutputPin.html#method.set_open_drain
+/// [`time::sleep_busy`]: ../time/fn.sleep_busy.html
+#[derive(Debug)]
+pub struct OneWire {
+    pin: OutputPin,
+}
+
+impl OneWire {
+    /// Constructs a new `OneWire` bus master on `pin`.
+    ///
+    /// `new` enables open-drain output on `pin`, and releases the line.
+    pub fn new(mut pin: OutputPin) -> OneWire {
+        pin.set_open_drain(true);
+        pin.set_high();
+
+        OneWire { pin }
+    }
+
+    /// Consumes the `OneWire`, and returns the underlying [`OutputPin`].
+    ///
+    /// Open-drain output remains enabled.
+    ///
+    /// [`OutputPin`]: struct.OutputPin.html
+    pub fn into_inner(self) -> OutputPin {
+        self.pin
+    }
+
+    /// Sends a reset pulse, and returns `true` if one or more devices responded
+    /// with a presence pulse.
+    ///
+    /// A reset is needed at the start of each transaction, before sending a ROM
+    /// command.
+    pub fn reset(&mut self) -> bool {
+        self.pin.set_low();
+        time::sleep_busy(TIME_H);
+        self.pin.set_high();
+        time::sleep_busy(TIME_I);
+
+        // Devices pull the line low to indicate their presence
+        let presence = self.pin.read() == Level::Low;
+
+        time::sleep_busy(TIME_J);
+
+        presence
+    }
+
+    /// Sends a single bit.
+    pub fn write_bit(&mut self, bit: bool) {
+        let (low, high) = if bit {
+            (TIME_A, TIME_B)
+        } else {
+            (TIME_C, TIME_D)
+        };
+
+        self.pin.set_low();
+        time::sleep_busy(low);
+        self.pin.set_high();
+        time::sleep_busy(high);
+    }
+
+    /// Receives a single bit.
+    pub fn read_bit(&mut self) -> bool {
+        self.pin.set_low();
+        time::sleep_busy(TIME_A);
+        self.pin.set_high();
+        time::sleep_busy(TIME_E);
+
+        let bit = self.pin.read() == Level::High;
+
+        time::sleep_busy(TIME_F);
+
+        bit
+    }
+
+    /// Sends a single byte, least-significant bit first.
+    pub fn write_byte(&mut self, byte: u8) {
+        for bit in 0..8 {
+            self.write_bit(byte & (1 << bit) != 0);
+        }
+    }
+
+    /// Receives a single byte, least-significant bit first.
+    pub fn read_byte(&mut self) -> u8 {
+        (0..8).fold(0, |byte, bit| {
+            if self.read_bit() {
+                byte | (1 << bit)
+            } else {
+                byte
+            }
+        })
+    }
+
+    /// Sends the contents of `buffer`.
+    pub fn write(&mut self, buffer: &[u8]) {
+        for &byte in buffer {
+            self.write_byte(byte);
+        }
+    }
+
+    /// Fills `buffer` with incoming data.
+    pub fn read(&mut self, buffer: &mut [u8]) {
+        for byte in buffer.iter_mut() {
+            *byte = self.read_byte();
+        }
+    }
+}
@@ -1,13 +1,33 @@
+use std::collections::VecDeque;
 use std::os::unix::io::AsRawFd;
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Duration;
 
-use super::soft_pwm::SoftPwm;
-use crate::gpio::{interrupt::AsyncInterrupt, Bias, GpioState, Level, Mode, Result, Trigger};
+use super::cleanup;
+use super::soft_pwm;
+#[cfg(feature = "stream")]
+use super::stream::InterruptStream;
+use crate::gpio::interrupt::{self, AsyncInterrupt, InterruptStats};
+use crate::gpio::{Backend, Bias, EdgeEvent, Error, GpioState, Level, Mode, Result, Trigger};
+use crate::time::Instant;
 
 const NANOS_PER_SEC: f64 = 1_000_000_000.0;
 
+// Trigger events recorded by InputPin::start_recording
+type Recording = VecDeque<(Level, Instant)>;
+
+// Channels that receive the trigger events for InputPin::add_interrupt_listener
+type Senders = Mutex<Vec<Sender<(Level, Instant)>>>;
+
+#[derive(Debug)]
+struct Listeners {
+    trigger: Trigger,
+    debounce: Option<Duration>,
+    senders: Arc<Senders>,
+}
+
 macro_rules! impl_pin {
     () => {
         /// Returns the GPIO pin number.
@@ -17,6 +37,22 @@ macro_rules! impl_pin {
         pub fn pin(&self) -> u8 {
             self.pin.pin
         }
+
+        /// Consumes the pin, and returns an unconfigured [`Pin`] for the same GPIO pin.
+        ///
+        /// `release` cleans up the same way as when the pin goes out of scope, which
+        /// includes resetting the pin's mode if [`reset_on_drop`] is set to `true`
+        /// (default). Unlike dropping the pin and retrieving it again through
+        /// [`Gpio::get`], the pin remains reserved for the returned [`Pin`] the entire
+        /// time, so the pin can be reconfigured through any of the `into_` methods
+        /// without another thread being able to claim it in between.
+        ///
+        /// [`Pin`]: struct.Pin.html
+        /// [`reset_on_drop`]: #method.reset_on_drop
+        /// [`Gpio::get`]: struct.Gpio.html#method.get
+        pub fn release(mut self) -> Pin {
+            self.pin.transfer()
+        }
     };
 }
 
@@ -51,7 +87,7 @@ macro_rules! impl_output {
         /// Sets the pin's output state.
         #[inline]
         pub fn write(&mut self, level: Level) {
-            self.pin.write(level)
+            self.write_level(level)
         }
 
         /// Sets the pin's output state to [`Low`].
@@ -59,7 +95,7 @@ macro_rules! impl_output {
         /// [`Low`]: enum.Level.html#variant.Low
         #[inline]
         pub fn set_low(&mut self) {
-            self.pin.set_low()
+            self.write_level(Level::Low)
         }
 
         /// Sets the pin's output state to [`High`].
@@ -67,16 +103,26 @@ macro_rules! impl_output {
         /// [`High`]: enum.Level.html#variant.High
         #[inline]
         pub fn set_high(&mut self) {
-            self.pin.set_high()
+            self.write_level(Level::High)
         }
 
         /// Toggles the pin's output state between [`Low`] and [`High`].
         ///
+        /// The new output state is applied with a single register write, and is
+        /// immediately reflected by `is_set_low` and `is_set_high`.
+        ///
+        /// The GPIO peripheral doesn't provide access to the output latch, so unless
+        /// open-drain output is enabled, the current output state is derived from the
+        /// pin's logic level, the same way `is_set_low` and `is_set_high` determine it.
+        /// If the line is held at a different level by an external device, for instance
+        /// due to a short, `toggle` always switches to the opposite of the level that's
+        /// actually on the line.
+        ///
         /// [`Low`]: enum.Level.html#variant.Low
         /// [`High`]: enum.Level.html#variant.High
         #[inline]
         pub fn toggle(&mut self) {
-            if self.pin.read() == Level::Low {
+            if self.output_level() == Level::Low {
                 self.set_high();
             } else {
                 self.set_low();
@@ -97,13 +143,26 @@ macro_rules! impl_output {
         /// If `set_pwm` is called when a PWM thread is already active, the existing thread
         /// will be reconfigured at the end of the current cycle.
         ///
+        /// Returns [`Error::PwmConflict`] if the pin was configured for its hardware PWM
+        /// function before it was retrieved, which usually indicates a PWM channel was enabled
+        /// for this pin through `/boot/config.txt`. The GPIO pins that support hardware PWM
+        /// are listed [in the `pwm` module documentation].
+        ///
         /// [`Pwm`]: ../pwm/struct.Pwm.html
         /// [here]: index.html#software-based-pwm
+        /// [`Error::PwmConflict`]: enum.Error.html#variant.PwmConflict
+        /// [in the `pwm` module documentation]: ../pwm/index.html#pwm-channels
         pub fn set_pwm(&mut self, period: Duration, pulse_width: Duration) -> Result<()> {
+            if self.prev_mode.is_some()
+                && self.prev_mode == hardware_pwm_mode(&self.pin.gpio_state, self.pin.pin)
+            {
+                return Err(Error::PwmConflict(self.pin.pin));
+            }
+
             if let Some(ref mut soft_pwm) = self.soft_pwm {
                 soft_pwm.reconfigure(period, pulse_width);
             } else {
-                self.soft_pwm = Some(SoftPwm::new(
+                self.soft_pwm = Some(soft_pwm::SoftPwmThread::new(
                     self.pin.pin,
                     self.pin.gpio_state.clone(),
                     period,
@@ -193,6 +252,7 @@ macro_rules! impl_reset_on_drop {
         /// [`simple_signal`]: https://crates.io/crates/simple-signal
         pub fn set_reset_on_drop(&mut self, reset_on_drop: bool) {
             self.reset_on_drop = reset_on_drop;
+            self.update_cleanup_action();
         }
     };
 }
@@ -265,12 +325,29 @@ macro_rules! impl_eq {
 pub struct Pin {
     pub(crate) pin: u8,
     gpio_state: Arc<GpioState>,
+    // Cleared when the reservation has been handed over to a new Pin
+    reserved: bool,
 }
 
 impl Pin {
     #[inline]
     pub(crate) fn new(pin: u8, gpio_state: Arc<GpioState>) -> Pin {
-        Pin { pin, gpio_state }
+        // A new Pin starts out with reset_on_drop enabled
+        gpio_state.pins_cleanup[pin as usize].store(cleanup::ACTION_RESET, Ordering::SeqCst);
+
+        Pin {
+            pin,
+            gpio_state,
+            reserved: true,
+        }
+    }
+
+    // Returns a new Pin for the same GPIO pin, which takes over the reservation,
+    // so dropping self doesn't release the pin
+    fn transfer(&mut self) -> Pin {
+        self.reserved = false;
+
+        Pin::new(self.pin, self.gpio_state.clone())
     }
 
     /// Returns the GPIO pin number.
@@ -293,6 +370,17 @@ impl Pin {
         self.gpio_state.gpio_mem.level(self.pin)
     }
 
+    /// Returns the current configuration of the pin's built-in pull-up/pull-down resistors.
+    ///
+    /// The configuration is read directly from the relevant registers, and isn't changed.
+    ///
+    /// Returns `None` if the SoC doesn't support reading back the configuration. This is
+    /// the case for all models prior to the Raspberry Pi 4, where GPPUD is write-only.
+    #[inline]
+    pub fn bias(&self) -> Option<Bias> {
+        self.gpio_state.gpio_mem.bias(self.pin)
+    }
+
     /// Consumes the `Pin` and returns an [`InputPin`]. Sets the mode to [`Input`]
     /// and disables the pin's built-in pull-up/pull-down resistors.
     ///
@@ -370,6 +458,12 @@ impl Pin {
         self.gpio_state.gpio_mem.set_mode(self.pin, mode);
     }
 
+    // Configures what the cleanup handler does with the pin
+    #[inline]
+    pub(crate) fn set_cleanup_action(&self, action: u8) {
+        self.gpio_state.pins_cleanup[self.pin as usize].store(action, Ordering::SeqCst);
+    }
+
     #[inline]
     pub(crate) fn set_bias(&mut self, bias: Bias) {
         self.gpio_state.gpio_mem.set_bias(self.pin, bias);
@@ -396,8 +490,10 @@ impl Pin {
 
 impl Drop for Pin {
     fn drop(&mut self) {
-        // Release taken pin
-        self.gpio_state.pins_taken[self.pin as usize].store(false, Ordering::SeqCst);
+        // Release taken pin, unless the reservation was transferred
+        if self.reserved {
+            self.gpio_state.pins_taken[self.pin as usize].store(false, Ordering::SeqCst);
+        }
     }
 }
 
@@ -427,6 +523,9 @@ pub struct InputPin {
     pub(crate) pin: Pin,
     prev_mode: Option<Mode>,
     async_interrupt: Option<AsyncInterrupt>,
+    interrupt_stats: Arc<InterruptStats>,
+    recording: Option<Arc<Mutex<Recording>>>,
+    listeners: Option<Listeners>,
     reset_on_drop: bool,
     bias: Bias,
 }
@@ -448,28 +547,91 @@ impl InputPin {
             pin,
             prev_mode,
             async_interrupt: None,
+            interrupt_stats: Arc::default(),
+            recording: None,
+            listeners: None,
             reset_on_drop: true,
             bias,
         }
     }
 
+    // Mirrors the behavior of drop for the cleanup handler
+    fn update_cleanup_action(&self) {
+        self.pin.set_cleanup_action(if self.reset_on_drop {
+            cleanup::ACTION_RESET
+        } else {
+            cleanup::ACTION_KEEP
+        });
+    }
+
     impl_pin!();
     impl_input!();
 
+    /// Returns the current configuration of the pin's built-in pull-up/pull-down resistors.
+    ///
+    /// The configuration is read directly from the relevant registers, and isn't changed.
+    ///
+    /// Returns `None` if the SoC doesn't support reading back the configuration. This is
+    /// the case for all models prior to the Raspberry Pi 4, where GPPUD is write-only.
+    #[inline]
+    pub fn bias(&self) -> Option<Bias> {
+        self.pin.bias()
+    }
+
+    /// Reconfigures the built-in pull-up/pull-down resistors, while the pin remains
+    /// configured as an input.
+    ///
+    /// On the Raspberry Pi 4 and 5, the new configuration is applied with a single write
+    /// to the relevant pull-up/pull-down control register. On earlier models, the
+    /// configuration is set through GPPUD, and then clocked into the pin through GPPUDCLK0/1,
+    /// with a set-up and hold time of 5 µs each, which blocks the calling thread for
+    /// at least 10 µs.
+    ///
+    /// Any configured (a)synchronous interrupt triggers remain active. The line may briefly
+    /// change level while the resistors are reconfigured, which can result in a trigger
+    /// event. When `Gpio` falls back to the gpiochip character device, because the GPIO
+    /// registers can't be memory-mapped, the configuration can't be changed while an
+    /// interrupt trigger is active.
+    #[inline]
+    pub fn set_bias(&mut self, bias: Bias) {
+        self.pin.set_bias(bias);
+        self.bias = bias;
+    }
+
     /// Configures a synchronous interrupt trigger.
     ///
     /// After configuring a synchronous interrupt trigger, call [`poll_interrupt`] or
     /// [`Gpio::poll_interrupts`] to block while waiting for a trigger event.
     ///
-    /// Any previously configured (a)synchronous interrupt triggers will be cleared.
+    /// Synchronous interrupt triggers don't spawn any threads. `set_interrupt` requests
+    /// edge detection from the kernel and registers the event file descriptor with an
+    /// `epoll` instance shared by all synchronous interrupt triggers, which is only waited
+    /// on in the calling thread when [`poll_interrupt`] or [`Gpio::poll_interrupts`] is
+    /// called. Trigger events that occur in between calls are queued by the kernel.
+    ///
+    /// A pin can be configured for either a synchronous or an asynchronous interrupt
+    /// trigger, but not both at the same time. `set_interrupt` clears any asynchronous
+    /// interrupt trigger configured through [`set_async_interrupt`], and stops its thread.
+    ///
+    /// `debounce` sets an optional debounce period. Any trigger events that occur
+    /// within the debounce period of the previous event are ignored. Each ignored event
+    /// restarts the debounce period, so contact bounce that continues past the end of the
+    /// initial period still results in a single trigger event. Setting `debounce` to `None`
+    /// or `Duration::ZERO` disables debouncing.
     ///
     /// [`poll_interrupt`]: #method.poll_interrupt
     /// [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
-    pub fn set_interrupt(&mut self, trigger: Trigger) -> Result<()> {
+    /// [`set_async_interrupt`]: #method.set_async_interrupt
+    pub fn set_interrupt(&mut self, trigger: Trigger, debounce: Option<Duration>) -> Result<()> {
         self.clear_async_interrupt()?;
 
         // Each pin can only be configured for a single trigger type
-        (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).set_interrupt(self.pin(), trigger)
+        (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).set_interrupt(
+            self.pin(),
+            trigger,
+            debounce,
+            self.interrupt_stats.clone(),
+        )
     }
 
     /// Removes a previously configured synchronous interrupt trigger.
@@ -494,35 +656,84 @@ impl InputPin {
     /// for interrupt trigger events, after which an `Ok(None))` is returned.
     /// `timeout` can be set to `None` to wait indefinitely.
     ///
+    /// When an interrupt event is triggered, `poll_interrupt` returns
+    /// `Ok(Some((`[`Level`]`, `[`Instant`]`)))` containing the pin's logic level right after
+    /// the edge occurred, and the timestamp captured by the kernel when the event occurred.
+    /// The level is derived from the edge direction reported by the kernel, rather than read
+    /// separately, so it isn't affected by any level changes that occur before
+    /// `poll_interrupt` returns. Likewise, the timestamp isn't affected by any scheduling
+    /// delays before the calling thread is woken up.
+    ///
     /// [`set_interrupt`]: #method.set_interrupt
     /// [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
     /// [`set_async_interrupt`]: #method.set_async_interrupt
+    /// [`Level`]: enum.Level.html
+    /// [`Instant`]: ../time/struct.Instant.html
     pub fn poll_interrupt(
         &mut self,
         reset: bool,
         timeout: Option<Duration>,
-    ) -> Result<Option<Level>> {
+    ) -> Result<Option<(Level, Instant)>> {
         let opt =
             (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).poll(&[self], reset, timeout)?;
 
-        if let Some(trigger) = opt {
-            Ok(Some(trigger.1))
-        } else {
-            Ok(None)
-        }
+        Ok(opt.map(|(_, level, timestamp)| (level, timestamp)))
+    }
+
+    /// Appends pending interrupt trigger events to `events` without blocking, and
+    /// returns the number of events that were added.
+    ///
+    /// This only works after the pin has been configured for synchronous interrupts using
+    /// [`set_interrupt`]. If no synchronous interrupt trigger is configured, or no
+    /// events are pending, `drain_events` returns `Ok(0)`.
+    ///
+    /// `drain_events` is intended for decoding high-frequency signals, where calling
+    /// [`poll_interrupt`] or an asynchronous interrupt callback for each edge adds too much
+    /// overhead. Each call reads a single batch of up to 64 events from the kernel's event
+    /// buffer, which is enough to empty the buffer, and appends the [`EdgeEvent`]s to
+    /// `events`. Edges that occur while the batch is being processed are retrieved by the
+    /// next call. `events` can be reused across calls to avoid any allocations once it has
+    /// grown to a sufficient capacity. Any configured debounce period is applied the same
+    /// way as for [`poll_interrupt`].
+    ///
+    /// The kernel's event buffer has a limited capacity, and discards any new events
+    /// while it's full, so `drain_events` should be called often enough to keep up with
+    /// the incoming edges. [`interrupt_overruns`] can be used to detect lost events.
+    ///
+    /// [`set_interrupt`]: #method.set_interrupt
+    /// [`poll_interrupt`]: #method.poll_interrupt
+    /// [`interrupt_overruns`]: #method.interrupt_overruns
+    /// [`EdgeEvent`]: struct.EdgeEvent.html
+    pub fn drain_events(&mut self, events: &mut Vec<EdgeEvent>) -> Result<usize> {
+        (*self.pin.gpio_state.sync_interrupts.lock().unwrap()).drain_events(self.pin(), events)
     }
 
     /// Configures an asynchronous interrupt trigger, which executes the callback on a
     /// separate thread when the interrupt is triggered.
     ///
-    /// The callback closure or function pointer is called with a single [`Level`] argument.
+    /// The callback closure or function pointer is called with a single [`Level`] argument,
+    /// which contains the pin's logic level right after the edge occurred. Similar to
+    /// [`poll_interrupt`], the level is derived from the edge direction reported by the kernel.
     ///
-    /// Any previously configured (a)synchronous interrupt triggers for this pin are cleared
+    /// `debounce` sets an optional debounce period, which works the same way as it does
+    /// for [`set_interrupt`]. Setting `debounce` to `None` or `Duration::ZERO` disables
+    /// debouncing.
+    ///
+    /// Each asynchronous interrupt trigger runs on its own thread. A pin can't be configured
+    /// for both a synchronous and an asynchronous interrupt trigger at the same time. Any
+    /// previously configured (a)synchronous interrupt triggers for this pin are cleared
     /// when `set_async_interrupt` is called, or when `InputPin` goes out of scope.
     ///
     /// [`clear_async_interrupt`]: #method.clear_async_interrupt
+    /// [`set_interrupt`]: #method.set_interrupt
+    /// [`poll_interrupt`]: #method.poll_interrupt
     /// [`Level`]: enum.Level.html
-    pub fn set_async_interrupt<C>(&mut self, trigger: Trigger, callback: C) -> Result<()>
+    pub fn set_async_interrupt<C>(
+        &mut self,
+        trigger: Trigger,
+        debounce: Option<Duration>,
+        mut callback: C,
+    ) -> Result<()>
     where
         C: FnMut(Level) + Send + 'static,
     {
@@ -533,7 +744,10 @@ impl InputPin {
             self.pin.gpio_state.cdev.as_raw_fd(),
             self.pin(),
             trigger,
-            callback,
+            debounce,
+            self.interrupt_stats.clone(),
+            self.pin.gpio_state.interrupt_thread.lock().unwrap().clone(),
+            move |event| callback(event.level()),
         )?);
 
         Ok(())
@@ -545,9 +759,386 @@ impl InputPin {
             interrupt.stop()?;
         }
 
+        // Disconnects any channels returned by add_interrupt_listener
+        self.listeners = None;
+
         Ok(())
     }
 
+    /// Configures an asynchronous interrupt trigger, and returns a [`Stream`] that yields
+    /// each trigger event.
+    ///
+    /// Each item contains the pin's new logic level, and the [`Instant`] the kernel
+    /// captured when the event occurred. Trigger events are buffered until they're retrieved
+    /// from the stream.
+    ///
+    /// `debounce` sets an optional debounce period, which works the same way as it does
+    /// for [`set_interrupt`].
+    ///
+    /// Any previously configured (a)synchronous interrupt triggers for this pin are cleared.
+    /// The interrupt trigger is removed when the returned [`InterruptStream`] is dropped, after
+    /// which the pin can be reconfigured.
+    ///
+    /// `interrupt_stream` is only available when the `stream` feature is enabled.
+    ///
+    /// [`Stream`]: https://docs.rs/futures-core/0.3/futures_core/stream/trait.Stream.html
+    /// [`Instant`]: ../time/struct.Instant.html
+    /// [`set_interrupt`]: #method.set_interrupt
+    /// [`InterruptStream`]: struct.InterruptStream.html
+    #[cfg(feature = "stream")]
+    pub fn interrupt_stream(
+        &mut self,
+        trigger: Trigger,
+        debounce: Option<Duration>,
+    ) -> Result<InterruptStream<'_>> {
+        self.clear_interrupt()?;
+        self.clear_async_interrupt()?;
+
+        InterruptStream::new(
+            self.pin.gpio_state.cdev.as_raw_fd(),
+            self.pin(),
+            trigger,
+            debounce,
+            self.interrupt_stats.clone(),
+            self.pin.gpio_state.interrupt_thread.lock().unwrap().clone(),
+        )
+    }
+
+    /// Starts recording interrupt trigger events in a bounded buffer.
+    ///
+    /// `start_recording` configures an asynchronous interrupt trigger, and stores the
+    /// pin's new logic level and the [`Instant`] the kernel captured for each trigger event.
+    /// Once `capacity` events have been recorded, the oldest event is discarded for each new
+    /// event. The recorded events can be retrieved with [`take_recording`], which allows
+    /// a burst of edges, such as an infrared remote control signal, to be decoded after the
+    /// fact, based on the timestamps.
+    ///
+    /// Any previously configured (a)synchronous interrupt triggers for this pin are cleared,
+    /// and any events left over from a previous recording are discarded. Recording stops when
+    /// [`clear_async_interrupt`] is called or another interrupt trigger is configured.
+    ///
+    /// Returns `Err(`[`Error::InvalidValue`]`)` if `capacity` is 0.
+    ///
+    /// [`Instant`]: ../time/struct.Instant.html
+    /// [`take_recording`]: #method.take_recording
+    /// [`clear_async_interrupt`]: #method.clear_async_interrupt
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn start_recording(&mut self, capacity: usize, trigger: Trigger) -> Result<()> {
+        if capacity == 0 {
+            return Err(Error::InvalidValue);
+        }
+
+        self.clear_interrupt()?;
+        self.clear_async_interrupt()?;
+
+        let recording = Arc::new(Mutex::new(VecDeque::with_capacity(capacity)));
+        let recording_callback = recording.clone();
+
+        self.async_interrupt = Some(AsyncInterrupt::new(
+            self.pin.gpio_state.cdev.as_raw_fd(),
+            self.pin(),
+            trigger,
+            None,
+            self.interrupt_stats.clone(),
+            self.pin.gpio_state.interrupt_thread.lock().unwrap().clone(),
+            move |event| {
+                let mut recording = recording_callback.lock().unwrap_or_else(|e| e.into_inner());
+
+                if recording.len() == capacity {
+                    recording.pop_front();
+                }
+
+                recording.push_back((
+                    event.level(),
+                    Instant::from_nanos(event.timestamp().as_nanos()),
+                ));
+            },
+        )?);
+
+        self.recording = Some(recording);
+
+        Ok(())
+    }
+
+    /// Returns all interrupt trigger events recorded since the last call to
+    /// `take_recording`, ordered from oldest to newest.
+    ///
+    /// Recording continues in the background after `take_recording` returns. If no
+    /// recording was started with [`start_recording`], an empty `Vec` is returned.
+    ///
+    /// [`start_recording`]: #method.start_recording
+    pub fn take_recording(&mut self) -> Vec<(Level, Instant)> {
+        match self.recording {
+            Some(ref recording) => recording
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .drain(..)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Adds a listener for asynchronous interrupt trigger events, and returns the
+    /// receiving end of a new channel.
+    ///
+    /// Unlike [`set_async_interrupt`], which replaces any previously configured callback,
+    /// `add_interrupt_listener` can be called multiple times to let independent parts of
+    /// an application observe the same trigger events. All listeners are fed by a single
+    /// asynchronous interrupt trigger, and each trigger event is sent to every channel
+    /// as a `(`[`Level`]`, `[`Instant`]`)` tuple, containing the pin's logic level right
+    /// after the edge occurred, and the timestamp captured by the kernel.
+    ///
+    /// The listeners share the same `trigger` and `debounce` settings, which work the
+    /// same way as they do for [`set_async_interrupt`]. If either setting differs from
+    /// the settings used by the existing listeners, the interrupt trigger is reconfigured
+    /// for all of them. Dropping a `Receiver` removes its listener when the next trigger
+    /// event occurs.
+    ///
+    /// Any previously configured synchronous interrupt trigger, or asynchronous interrupt
+    /// trigger that wasn't configured through `add_interrupt_listener`, is cleared. All
+    /// channels are disconnected when [`clear_async_interrupt`] is called, another interrupt
+    /// trigger is configured, or `InputPin` goes out of scope.
+    ///
+    /// [`set_async_interrupt`]: #method.set_async_interrupt
+    /// [`clear_async_interrupt`]: #method.clear_async_interrupt
+    /// [`Level`]: enum.Level.html
+    /// [`Instant`]: ../time/struct.Instant.html
+    pub fn add_interrupt_listener(
+        &mut self,
+        trigger: Trigger,
+        debounce: Option<Duration>,
+    ) -> Result<Receiver<(Level, Instant)>> {
+        let (tx, rx) = mpsc::channel();
+
+        if let Some(ref listeners) = self.listeners {
+            if listeners.trigger == trigger && listeners.debounce == debounce {
+                lock_senders(&listeners.senders).push(tx);
+
+                return Ok(rx);
+            }
+        }
+
+        // Keep the existing listeners when the trigger is reconfigured
+        let senders = match self.listeners.take() {
+            Some(listeners) => listeners.senders,
+            None => Arc::new(Mutex::new(Vec::new())),
+        };
+        lock_senders(&senders).push(tx);
+
+        self.clear_interrupt()?;
+        self.clear_async_interrupt()?;
+
+        let senders_callback = senders.clone();
+
+        self.async_interrupt = Some(AsyncInterrupt::new(
+            self.pin.gpio_state.cdev.as_raw_fd(),
+            self.pin(),
+            trigger,
+            debounce,
+            self.interrupt_stats.clone(),
+            self.pin.gpio_state.interrupt_thread.lock().unwrap().clone(),
+            move |event| {
+                let event = (
+                    event.level(),
+                    Instant::from_nanos(event.timestamp().as_nanos()),
+                );
+
+                // Remove any listeners whose receiver has been dropped
+                lock_senders(&senders_callback).retain(|tx| tx.send(event).is_ok());
+            },
+        )?);
+
+        self.listeners = Some(Listeners {
+            trigger,
+            debounce,
+            senders,
+        });
+
+        Ok(rx)
+    }
+
+    /// Returns a lower bound for the number of times interrupt trigger events were lost,
+    /// because they weren't retrieved in time.
+    ///
+    /// The kernel buffers up to 16 trigger events per pin until they're read by
+    /// [`poll_interrupt`], [`Gpio::poll_interrupts`], or the thread that handles
+    /// asynchronous interrupts. Any events that occur while the buffer is full are
+    /// discarded. The size of the buffer is determined by the kernel, and can't be changed.
+    ///
+    /// The gpiochip interface used by RPPAL doesn't report discarded events, so
+    /// `interrupt_overruns` is a heuristic rather than an exact count. Lost events can only
+    /// be detected when the pin is configured for [`Trigger::Both`], in which case two
+    /// consecutive events with the same logic level indicate at least one missing edge.
+    /// Each occurrence increments the counter by one, regardless of the number of events
+    /// that were discarded. An even number of lost edges leaves the levels alternating, and
+    /// goes undetected. A non-zero value reliably indicates events were lost, but a value
+    /// of zero doesn't guarantee none were. Events ignored because of the debounce period
+    /// aren't counted.
+    ///
+    /// The counter is kept for as long as the `InputPin` exists, and isn't reset when the
+    /// interrupt trigger is changed or removed.
+    ///
+    /// [`poll_interrupt`]: #method.poll_interrupt
+    /// [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
+    /// [`Trigger::Both`]: enum.Trigger.html#variant.Both
+    pub fn interrupt_overruns(&self) -> usize {
+        self.interrupt_stats.overruns()
+    }
+
+    /// Returns the number of interrupt trigger events reported by the kernel for a
+    /// synchronous or asynchronous interrupt trigger on this pin.
+    ///
+    /// Events ignored because of the debounce period are included, which makes the
+    /// counter useful to detect stuck or chattering lines. Events that were discarded
+    /// while the kernel's event buffer was full aren't included.
+    ///
+    /// Synchronous interrupt events are counted when they're read by [`poll_interrupt`]
+    /// or [`Gpio::poll_interrupts`], rather than when they occur.
+    ///
+    /// The counter is kept for as long as the `InputPin` exists, and isn't reset when the
+    /// interrupt trigger is changed or removed.
+    ///
+    /// [`poll_interrupt`]: #method.poll_interrupt
+    /// [`Gpio::poll_interrupts`]: struct.Gpio.html#method.poll_interrupts
+    pub fn interrupt_count(&self) -> usize {
+        self.interrupt_stats.count()
+    }
+
+    /// Returns the timestamp of the most recent interrupt trigger event included in
+    /// [`interrupt_count`], or `None` if no events were reported yet.
+    ///
+    /// The timestamp is captured by the kernel when the event occurred.
+    ///
+    /// [`interrupt_count`]: #method.interrupt_count
+    pub fn last_interrupt(&self) -> Option<Instant> {
+        self.interrupt_stats.last_interrupt()
+    }
+
+    /// Blocks until the pin changes to the specified logic level, and returns how long it
+    /// stays at that level.
+    ///
+    /// If the pin is already at `level` when `measure_pulse` is called, the current pulse
+    /// is ignored, and `measure_pulse` waits for the next one. The pulse duration is
+    /// calculated using the timestamps the kernel captures for each edge, which are
+    /// unaffected by any scheduling delays in the calling thread.
+    ///
+    /// The `timeout` duration indicates how long the call will block while waiting for
+    /// the start and the end of the pulse, after which an [`Error::TimedOut`] is returned.
+    ///
+    /// `measure_pulse` can't be used while the pin is configured for (a)synchronous
+    /// interrupts.
+    ///
+    /// [`Error::TimedOut`]: enum.Error.html#variant.TimedOut
+    pub fn measure_pulse(&self, level: Level, timeout: Duration) -> Result<Duration> {
+        interrupt::measure_pulse(
+            self.pin.gpio_state.cdev.as_raw_fd(),
+            self.pin(),
+            level,
+            timeout,
+        )
+    }
+
+    /// Blocks until the pin is at the specified logic level, or a timeout occurs.
+    ///
+    /// If the pin is already at `level` when `wait_for_level` is called, it returns
+    /// immediately. Otherwise, a temporary interrupt trigger is configured for the edge
+    /// that changes the pin to `level`, and `wait_for_level` blocks until that edge occurs.
+    /// The interrupt trigger is configured before the current level is checked, so any edge
+    /// that occurs in between isn't missed. Unlike [`measure_pulse`], `wait_for_level`
+    /// doesn't wait for the next pulse when the pin is already at `level`, which makes it
+    /// suitable for level-triggered handshake signals, such as a data ready output.
+    ///
+    /// The `timeout` duration indicates how long the call will block while waiting for the
+    /// pin to change to `level`, after which an [`Error::TimedOut`] is returned.
+    ///
+    /// `wait_for_level` can't be used while the pin is configured for (a)synchronous
+    /// interrupts.
+    ///
+    /// [`measure_pulse`]: #method.measure_pulse
+    /// [`Error::TimedOut`]: enum.Error.html#variant.TimedOut
+    pub fn wait_for_level(&self, level: Level, timeout: Duration) -> Result<()> {
+        interrupt::wait_for_level(
+            self.pin.gpio_state.cdev.as_raw_fd(),
+            self.pin(),
+            level,
+            timeout,
+        )
+    }
+
+    /// Counts the number of trigger events that occur within the specified time window.
+    ///
+    /// `count_edges` configures a temporary interrupt trigger, blocks for the duration of
+    /// `window` while counting trigger events, and then removes the interrupt trigger again
+    /// before returning the total. Whether an event occurred within the window is determined
+    /// using the timestamps the kernel captures for each event.
+    ///
+    /// Any previously configured (a)synchronous interrupt triggers for this pin are cleared
+    /// when `count_edges` is called.
+    pub fn count_edges(&mut self, trigger: Trigger, window: Duration) -> Result<u32> {
+        self.clear_interrupt()?;
+        self.clear_async_interrupt()?;
+
+        interrupt::count_edges(
+            self.pin.gpio_state.cdev.as_raw_fd(),
+            self.pin(),
+            trigger,
+            window,
+        )
+    }
+
+    /// Returns the number of trigger events per second, counted over the specified time window.
+    ///
+    /// `edge_frequency` is a convenience method that calls [`count_edges`], and divides
+    /// the result by the length of `window` in seconds. When `trigger` is set to
+    /// [`Trigger::Both`], the returned value is twice the frequency of the input signal.
+    ///
+    /// [`count_edges`]: #method.count_edges
+    /// [`Trigger::Both`]: enum.Trigger.html#variant.Both
+    pub fn edge_frequency(&mut self, trigger: Trigger, window: Duration) -> Result<f64> {
+        let count = self.count_edges(trigger, window)?;
+        let window_s = window.as_secs_f64();
+
+        if window_s > 0.0 {
+            Ok(f64::from(count) / window_s)
+        } else {
+            Ok(0.0)
+        }
+    }
+
+    /// Returns the frequency of the input signal in hertz (Hz), based on the average period
+    /// between the specified number of successive rising edges.
+    ///
+    /// `measure_frequency` configures a temporary interrupt trigger, and blocks until `samples`
+    /// rising edges have been captured. The time before the first edge is discarded, so the
+    /// frequency is calculated from the `samples - 1` complete periods in between, using the
+    /// timestamps the kernel captures for each edge. Unlike [`edge_frequency`], the accuracy
+    /// doesn't depend on where the edges fall within a fixed time window, which makes
+    /// `measure_frequency` more suitable for low-frequency signals.
+    ///
+    /// `samples` should be at least `2`, or an [`Error::InvalidValue`] is returned.
+    ///
+    /// The `timeout` duration indicates how long the call will block while waiting for all
+    /// samples to be captured, after which an [`Error::IncompleteMeasurement`] is returned
+    /// that contains the number of rising edges that were captured in time.
+    ///
+    /// Any previously configured (a)synchronous interrupt triggers for this pin are cleared
+    /// when `measure_frequency` is called.
+    ///
+    /// [`edge_frequency`]: #method.edge_frequency
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    /// [`Error::IncompleteMeasurement`]: enum.Error.html#variant.IncompleteMeasurement
+    pub fn measure_frequency(&mut self, samples: usize, timeout: Duration) -> Result<f64> {
+        self.clear_interrupt()?;
+        self.clear_async_interrupt()?;
+
+        interrupt::measure_frequency(
+            self.pin.gpio_state.cdev.as_raw_fd(),
+            self.pin(),
+            samples,
+            timeout,
+        )
+    }
+
     impl_reset_on_drop!();
 }
 
@@ -582,8 +1173,10 @@ pub struct OutputPin {
     pin: Pin,
     prev_mode: Option<Mode>,
     reset_on_drop: bool,
+    drop_state: Option<Level>,
     bias: Bias,
-    pub(crate) soft_pwm: Option<SoftPwm>,
+    open_drain: bool,
+    pub(crate) soft_pwm: Option<soft_pwm::SoftPwmThread>,
     // Stores the softpwm frequency. Used for embedded_hal::PwmPin.
     #[cfg(any(
         feature = "embedded-hal-0",
@@ -615,7 +1208,9 @@ impl OutputPin {
             pin,
             prev_mode,
             reset_on_drop: true,
+            drop_state: None,
             bias: Bias::Off,
+            open_drain: false,
             soft_pwm: None,
             #[cfg(any(
                 feature = "embedded-hal-0",
@@ -634,12 +1229,31 @@ impl OutputPin {
 
     impl_pin!();
 
+    /// Reads the pin's actual logic level, while it remains configured as an output.
+    ///
+    /// The pin's input buffer stays enabled in output mode, so `read` returns the electrical
+    /// level on the line, which may differ from the output state if the line is shorted or
+    /// overloaded. When open-drain output is enabled, and the pin is set to [`High`], `read`
+    /// returns the level driven by the external pull-up resistor or any of the other devices
+    /// sharing the line.
+    ///
+    /// The GPIO peripheral doesn't provide access to the output latch. Unless open-drain
+    /// output is enabled, [`is_set_low`] and [`is_set_high`] are based on the same level.
+    ///
+    /// [`High`]: enum.Level.html#variant.High
+    /// [`is_set_low`]: #method.is_set_low
+    /// [`is_set_high`]: #method.is_set_high
+    #[inline]
+    pub fn read(&self) -> Level {
+        self.pin.read()
+    }
+
     /// Returns `true` if the pin's output state is set to [`Low`].
     ///
     /// [`Low`]: enum.Level.html#variant.Low
     #[inline]
     pub fn is_set_low(&self) -> bool {
-        self.pin.read() == Level::Low
+        self.output_level() == Level::Low
     }
 
     /// Returns `true` if the pin's output state is set to [`High`].
@@ -647,14 +1261,186 @@ impl OutputPin {
     /// [`High`]: enum.Level.html#variant.High
     #[inline]
     pub fn is_set_high(&self) -> bool {
-        self.pin.read() == Level::High
+        self.output_level() == Level::High
+    }
+
+    #[inline]
+    fn write_level(&mut self, level: Level) {
+        if !self.open_drain {
+            self.pin.write(level);
+            return;
+        }
+
+        // High-impedance for High, actively driven for Low
+        match level {
+            Level::High => self.pin.set_mode(Mode::Input),
+            Level::Low => {
+                self.pin.write(Level::Low);
+                self.pin.set_mode(Mode::Output);
+            }
+        }
+    }
+
+    #[inline]
+    fn output_level(&self) -> Level {
+        if self.open_drain {
+            if self.pin.mode() == Mode::Output {
+                Level::Low
+            } else {
+                Level::High
+            }
+        } else {
+            self.pin.read()
+        }
+    }
+
+    /// Returns `true` if open-drain output is enabled.
+    pub fn is_open_drain(&self) -> bool {
+        self.open_drain
+    }
+
+    /// Enables or disables open-drain output.
+    ///
+    /// The GPIO peripheral doesn't support open-drain outputs, so they're emulated by
+    /// switching the pin's mode. When open-drain output is enabled, setting the pin
+    /// to [`Low`] configures the pin as an output and actively drives it low. Setting
+    /// the pin to [`High`] configures the pin as an input, which leaves it in a
+    /// high-impedance state. This allows multiple devices to share a single line, and
+    /// offers a safe way to interface with open-collector outputs on devices that use
+    /// a higher voltage.
+    ///
+    /// An external pull-up resistor is needed to pull the line high while none of the
+    /// connected devices drive it low. Make sure the pull-up resistor is connected to
+    /// 3.3 V, and never to a higher voltage. The built-in pull-up resistors are too weak
+    /// for most buses.
+    ///
+    /// Enabling or disabling open-drain output preserves the pin's current output state.
+    /// Software-based PWM ignores the open-drain setting.
+    ///
+    /// By default, open-drain output is disabled.
+    ///
+    /// [`Low`]: enum.Level.html#variant.Low
+    /// [`High`]: enum.Level.html#variant.High
+    pub fn set_open_drain(&mut self, open_drain: bool) {
+        if open_drain == self.open_drain {
+            return;
+        }
+
+        let level = self.output_level();
+        self.open_drain = open_drain;
+        self.update_cleanup_action();
+
+        if open_drain {
+            self.write_level(level);
+        } else {
+            self.pin.write(level);
+            self.pin.set_mode(Mode::Output);
+        }
+    }
+
+    /// Sets the drive strength in milliamperes (mA).
+    ///
+    /// On the BCM283x and BCM2711, drive strength is configured through the PADS
+    /// registers, which only support 2 mA steps ranging from 2 mA to 16 mA. The PADS
+    /// registers control groups of pins rather than individual pins, so changing the
+    /// drive strength for one pin affects all pins in its group (GPIO 0-27, GPIO 28-45
+    /// or GPIO 46-53). The PADS registers can only be accessed through `/dev/mem`, which
+    /// requires superuser privileges.
+    ///
+    /// On the BCM2712 (Raspberry Pi 5), drive strength is configured per pin. Supported
+    /// values are 2 mA, 4 mA, 8 mA and 12 mA.
+    ///
+    /// Returns [`Error::InvalidValue`] if `milliamps` isn't supported. The drive strength
+    /// is never silently rounded to the nearest supported value.
+    ///
+    /// The drive strength isn't reset when the pin goes out of scope.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_drive_strength(&mut self, milliamps: u8) -> Result<()> {
+        self.pin
+            .gpio_state
+            .gpio_mem
+            .set_drive_strength(self.pin.pin, milliamps)
     }
 
     impl_output!();
     impl_reset_on_drop!();
+
+    /// Returns the logic level the pin is set to when `OutputPin` goes out of scope.
+    pub fn drop_state(&self) -> Option<Level> {
+        self.drop_state
+    }
+
+    /// Configures the logic level the pin is set to when `OutputPin` goes out of scope.
+    ///
+    /// When `drop_state` is set to `Some(level)`, any active software-based PWM signal is
+    /// stopped, and the pin is driven to `level` when `OutputPin` goes out of scope, including
+    /// while unwinding after a panic. The pin remains configured as an output, so it's never
+    /// left floating. This takes precedence over [`reset_on_drop`].
+    ///
+    /// Setting `drop_state` to `None` (default) restores the behavior configured through
+    /// [`set_reset_on_drop`].
+    ///
+    /// ## Note
+    ///
+    /// Drop methods aren't called when a process is abnormally terminated, for
+    /// instance when a user presses <kbd>Ctrl</kbd> + <kbd>C</kbd>, and the `SIGINT` signal
+    /// isn't caught. The handler installed by [`Gpio::install_cleanup_handler`] drives
+    /// the pin to `drop_state` when the process receives `SIGINT` or `SIGTERM`.
+    ///
+    /// [`reset_on_drop`]: #method.reset_on_drop
+    /// [`set_reset_on_drop`]: #method.set_reset_on_drop
+    /// [`Gpio::install_cleanup_handler`]: struct.Gpio.html#method.install_cleanup_handler
+    pub fn set_drop_state(&mut self, drop_state: Option<Level>) {
+        self.drop_state = drop_state;
+        self.update_cleanup_action();
+    }
+
+    // Mirrors the behavior of drop for the cleanup handler. An open-drain output
+    // that's set to High is released by switching it to an input.
+    fn update_cleanup_action(&self) {
+        self.pin.set_cleanup_action(match self.drop_state {
+            Some(Level::High) if self.open_drain => cleanup::ACTION_RESET,
+            Some(Level::High) => cleanup::ACTION_DRIVE_HIGH,
+            Some(Level::Low) => cleanup::ACTION_DRIVE_LOW,
+            None if self.reset_on_drop => cleanup::ACTION_RESET,
+            None => cleanup::ACTION_KEEP,
+        });
+    }
+}
+
+impl Drop for OutputPin {
+    /// Sets the pin's logic level if a drop state is configured. Otherwise, resets the
+    /// pin's mode and disables the built-in pull-up/pull-down resistors if `reset_on_drop`
+    /// is set to `true` (default).
+    fn drop(&mut self) {
+        if let Some(level) = self.drop_state {
+            // Make sure the PWM thread can't change the logic level after we've set it
+            if let Some(mut soft_pwm) = self.soft_pwm.take() {
+                let _ = soft_pwm.stop();
+            }
+
+            self.write_level(level);
+
+            return;
+        }
+
+        if !self.reset_on_drop {
+            return;
+        }
+
+        if let Some(prev_mode) = self.prev_mode {
+            self.pin.set_mode(prev_mode);
+        } else if self.open_drain {
+            self.pin.set_mode(Mode::Output);
+        }
+
+        if self.bias != Bias::Off {
+            self.pin.set_bias(Bias::Off);
+        }
+    }
 }
 
-impl_drop!(OutputPin);
 impl_eq!(OutputPin);
 
 /// GPIO pin that can be (re)configured for any mode or alternate function.
@@ -692,7 +1478,7 @@ pub struct IoPin {
     prev_mode: Option<Mode>,
     reset_on_drop: bool,
     bias: Bias,
-    pub(crate) soft_pwm: Option<SoftPwm>,
+    pub(crate) soft_pwm: Option<soft_pwm::SoftPwmThread>,
     // Stores the softpwm frequency. Used for embedded_hal::PwmPin.
     #[cfg(any(
         feature = "embedded-hal-0",
@@ -742,6 +1528,15 @@ impl IoPin {
         }
     }
 
+    // Mirrors the behavior of drop for the cleanup handler
+    fn update_cleanup_action(&self) {
+        self.pin.set_cleanup_action(if self.reset_on_drop {
+            cleanup::ACTION_RESET
+        } else {
+            cleanup::ACTION_KEEP
+        });
+    }
+
     impl_pin!();
 
     /// Returns the pin's mode.
@@ -771,6 +1566,16 @@ impl IoPin {
         self.bias = bias;
     }
 
+    #[inline]
+    fn write_level(&mut self, level: Level) {
+        self.pin.write(level)
+    }
+
+    #[inline]
+    fn output_level(&self) -> Level {
+        self.pin.read()
+    }
+
     impl_input!();
     impl_output!();
     impl_reset_on_drop!();
@@ -778,3 +1583,19 @@ impl IoPin {
 
 impl_drop!(IoPin);
 impl_eq!(IoPin);
+
+// Returns the alternate function that connects the pin to a hardware PWM channel,
+// if the pin supports hardware PWM
+fn hardware_pwm_mode(gpio_state: &GpioState, pin: u8) -> Option<Mode> {
+    match (gpio_state.gpio_mem.backend(), pin) {
+        (Backend::Bcm(_), 12) | (Backend::Bcm(_), 13) => Some(Mode::Alt0),
+        (Backend::Bcm(_), 18) | (Backend::Bcm(_), 19) => Some(Mode::Alt5),
+        (Backend::Rp1(_), 12..=15) => Some(Mode::Alt0),
+        (Backend::Rp1(_), 18) | (Backend::Rp1(_), 19) => Some(Mode::Alt3),
+        _ => None,
+    }
+}
+
+fn lock_senders(senders: &Senders) -> MutexGuard<'_, Vec<Sender<(Level, Instant)>>> {
+    senders.lock().unwrap_or_else(|e| e.into_inner())
+}
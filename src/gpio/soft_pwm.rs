@@ -9,7 +9,7 @@ use std::time::Duration;
 
 use libc::{self, sched_param, timespec, CLOCK_MONOTONIC, PR_SET_TIMERSLACK, SCHED_RR};
 
-use super::{Error, GpioState, Result};
+use super::{Error, GpioState, OutputPin, Result};
 
 // Only call sleep() if we have enough time remaining
 const SLEEP_THRESHOLD: i64 = 250_000;
@@ -27,18 +27,18 @@ enum Msg {
 }
 
 #[derive(Debug)]
-pub(crate) struct SoftPwm {
+pub(crate) struct SoftPwmThread {
     pwm_thread: Option<thread::JoinHandle<Result<()>>>,
     sender: Sender<Msg>,
 }
 
-impl SoftPwm {
+impl SoftPwmThread {
     pub(crate) fn new(
         pin: u8,
         gpio_state: Arc<GpioState>,
         period: Duration,
         pulse_width: Duration,
-    ) -> SoftPwm {
+    ) -> SoftPwmThread {
         let (sender, receiver): (Sender<Msg>, Receiver<Msg>) = mpsc::channel();
 
         let pwm_thread = thread::spawn(move || -> Result<()> {
@@ -140,7 +140,7 @@ impl SoftPwm {
             }
         });
 
-        SoftPwm {
+        SoftPwmThread {
             pwm_thread: Some(pwm_thread),
             sender,
         }
@@ -163,7 +163,7 @@ impl SoftPwm {
     }
 }
 
-impl Drop for SoftPwm {
+impl Drop for SoftPwmThread {
     fn drop(&mut self) {
         // Don't wait for the pwm thread to exit if the main thread is panicking,
         // because we could potentially block indefinitely while unwinding if the
@@ -174,9 +174,9 @@ impl Drop for SoftPwm {
     }
 }
 
-// Required because Sender isn't Sync. Implementing Sync for SoftPwm is
+// Required because Sender isn't Sync. Implementing Sync for SoftPwmThread is
 // safe because all usage of Sender::send() is locked behind &mut self.
-unsafe impl Sync for SoftPwm {}
+unsafe impl Sync for SoftPwmThread {}
 
 #[inline(always)]
 fn get_time_ns() -> i64 {
@@ -191,3 +191,150 @@ fn get_time_ns() -> i64 {
 
     (ts.tv_sec as i64 * NANOS_PER_SEC) + ts.tv_nsec as i64
 }
+
+/// Software-based PWM signal on an [`OutputPin`].
+///
+/// `SoftPwm` takes ownership of an [`OutputPin`], and emulates a PWM signal by toggling
+/// the pin's output state on a separate thread, combined with sleep and busy-waiting.
+/// This makes it possible to generate a PWM signal on any GPIO pin, for instance to control
+/// the brightness of an LED.
+///
+/// Software-based PWM is inherently inaccurate on a multi-threaded OS due to
+/// scheduling/preemption. Typical jitter is expected to be up to 10 µs on debug builds,
+/// and up to 2 µs on release builds, but the PWM thread may occasionally sleep longer than
+/// needed, especially on a heavily loaded system. If an accurate or faster PWM signal is
+/// required, use the hardware [`Pwm`] peripheral instead. More information can be found [here].
+///
+/// When `SoftPwm` goes out of scope, the PWM thread is stopped and the pin is set low.
+///
+/// [`OutputPin`]: struct.OutputPin.html
+/// [`Pwm`]: ../pwm/struct.Pwm.html
+/// [here]: index.html#software-based-pwm
+#[derive(Debug)]
+pub struct SoftPwm {
+    pin: OutputPin,
+    frequency: f64,
+    duty_cycle: f64,
+    enabled: bool,
+}
+
+impl SoftPwm {
+    /// Constructs a new `SoftPwm`, and starts generating a PWM signal on `pin`.
+    ///
+    /// `frequency` is specified in hertz (Hz).
+    ///
+    /// `duty_cycle` is specified as a floating point value between `0.0` (0%) and `1.0` (100%).
+    ///
+    /// Returns [`Error::InvalidValue`] if `frequency` is zero, negative or not a finite value.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn new(mut pin: OutputPin, frequency: f64, duty_cycle: f64) -> Result<SoftPwm> {
+        check_frequency(frequency)?;
+
+        let duty_cycle = duty_cycle.clamp(0.0, 1.0);
+        pin.set_pwm_frequency(frequency, duty_cycle)?;
+
+        Ok(SoftPwm {
+            pin,
+            frequency,
+            duty_cycle,
+            enabled: true,
+        })
+    }
+
+    /// Returns the frequency in hertz (Hz).
+    pub fn frequency(&self) -> f64 {
+        self.frequency
+    }
+
+    /// Sets the frequency in hertz (Hz).
+    ///
+    /// If the PWM signal is enabled, the change takes effect at the end of the current cycle.
+    ///
+    /// Returns [`Error::InvalidValue`] if `frequency` is zero, negative or not a finite value.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_frequency(&mut self, frequency: f64) -> Result<()> {
+        check_frequency(frequency)?;
+
+        self.frequency = frequency;
+
+        if self.enabled {
+            self.pin
+                .set_pwm_frequency(self.frequency, self.duty_cycle)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the duty cycle as a floating point value between `0.0` (0%) and `1.0` (100%).
+    pub fn duty_cycle(&self) -> f64 {
+        self.duty_cycle
+    }
+
+    /// Sets the duty cycle as a floating point value between `0.0` (0%) and `1.0` (100%).
+    ///
+    /// If the PWM signal is enabled, the change takes effect at the end of the current cycle.
+    pub fn set_duty_cycle(&mut self, duty_cycle: f64) -> Result<()> {
+        self.duty_cycle = duty_cycle.clamp(0.0, 1.0);
+
+        if self.enabled {
+            self.pin
+                .set_pwm_frequency(self.frequency, self.duty_cycle)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the PWM signal is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables the PWM signal.
+    pub fn enable(&mut self) -> Result<()> {
+        if !self.enabled {
+            self.pin
+                .set_pwm_frequency(self.frequency, self.duty_cycle)?;
+            self.enabled = true;
+        }
+
+        Ok(())
+    }
+
+    /// Disables the PWM signal.
+    ///
+    /// The PWM thread is stopped at the end of the current cycle, which leaves the pin low.
+    pub fn disable(&mut self) -> Result<()> {
+        if self.enabled {
+            self.pin.clear_pwm()?;
+            self.pin.set_low();
+            self.enabled = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for SoftPwm {
+    /// Stops the PWM thread and sets the pin low.
+    ///
+    /// Afterwards, the pin's mode is reset if `reset_on_drop` is set to `true` (default)
+    /// on the [`OutputPin`] that was passed to [`new`].
+    ///
+    /// [`OutputPin`]: struct.OutputPin.html
+    /// [`new`]: #method.new
+    fn drop(&mut self) {
+        let _ = self.pin.clear_pwm();
+        self.pin.set_low();
+    }
+}
+
+// A zero, negative or non-finite frequency doesn't result in a valid period
+fn check_frequency(frequency: f64) -> Result<()> {
+    if !frequency.is_finite() || frequency <= 0.0 {
+        return Err(Error::InvalidValue);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use futures_core::Stream;
+
+use crate::gpio::interrupt::{AsyncInterrupt, InterruptStats, ThreadConfig};
+use crate::gpio::{InputPin, Level, Result, Trigger};
+use crate::time::Instant;
+
+#[derive(Debug, Default)]
+struct Shared {
+    events: VecDeque<(Level, Instant)>,
+    waker: Option<Waker>,
+}
+
+/// Stream of interrupt trigger events for an [`InputPin`].
+///
+/// `InterruptStream` is constructed by calling [`InputPin::interrupt_stream`]. Trigger
+/// events are read on a separate thread, and buffered until they're retrieved from the stream.
+///
+/// The interrupt trigger is removed when `InterruptStream` goes out of scope.
+///
+/// `InterruptStream` is only available when the `stream` feature is enabled.
+///
+/// [`InputPin`]: struct.InputPin.html
+/// [`InputPin::interrupt_stream`]: struct.InputPin.html#method.interrupt_stream
+pub struct InterruptStream<'a> {
+    shared: Arc<Mutex<Shared>>,
+    _interrupt: AsyncInterrupt,
+    _pin: PhantomData<&'a mut InputPin>,
+}
+
+impl<'a> InterruptStream<'a> {
+    pub(crate) fn new(
+        cdev_fd: i32,
+        pin: u8,
+        trigger: Trigger,
+        debounce: Option<Duration>,
+        stats: Arc<InterruptStats>,
+        thread_config: ThreadConfig,
+    ) -> Result<InterruptStream<'a>> {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let shared_callback = shared.clone();
+
+        let interrupt = AsyncInterrupt::new(
+            cdev_fd,
+            pin,
+            trigger,
+            debounce,
+            stats,
+            thread_config,
+            move |event| {
+                let mut shared = shared_callback.lock().unwrap();
+
+                shared.events.push_back((
+                    event.level(),
+                    Instant::from_nanos(event.timestamp().as_nanos()),
+                ));
+
+                if let Some(waker) = shared.waker.take() {
+                    waker.wake();
+                }
+            },
+        )?;
+
+        Ok(InterruptStream {
+            shared,
+            _interrupt: interrupt,
+            _pin: PhantomData,
+        })
+    }
+}
+
+impl<'a> Stream for InterruptStream<'a> {
+    type Item = (Level, Instant);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if let Some(event) = shared.events.pop_front() {
+            Poll::Ready(Some(event))
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<'a> fmt::Debug for InterruptStream<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterruptStream")
+            .field("shared", &format_args!("{{ .. }}"))
+            .finish()
+    }
+}
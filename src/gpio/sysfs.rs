@@ -0,0 +1,55 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::gpio::{Error, Result};
+
+const PATH_SYSFS_GPIO: &str = "/sys/class/gpio";
+// Labels of the gpiochips that control the GPIO header pins
+const CHIP_LABELS: [&str; 3] = ["pinctrl-bcm2835", "pinctrl-bcm2711", "pinctrl-rp1"];
+
+// Returns the global sysfs GPIO number of the first line on our gpiochip. Depending on
+// the kernel version, the base isn't necessarily 0.
+fn chip_base() -> Result<u32> {
+    for entry in fs::read_dir(PATH_SYSFS_GPIO)? {
+        let path = entry?.path();
+
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.starts_with("gpiochip") => (),
+            _ => continue,
+        }
+
+        let label = fs::read_to_string(path.join("label")).unwrap_or_default();
+        if CHIP_LABELS.contains(&label.trim()) {
+            return fs::read_to_string(path.join("base"))?
+                .trim()
+                .parse()
+                .map_err(|_| Error::Io(io::Error::from(io::ErrorKind::InvalidData)));
+        }
+    }
+
+    Err(Error::Io(io::Error::from(io::ErrorKind::NotFound)))
+}
+
+// Unexports the pin if it was previously exported through the sysfs GPIO interface,
+// which prevents any line requests through the gpiochip character device. Returns
+// true if the pin was exported.
+pub fn unexport(pin: u8) -> Result<bool> {
+    if !Path::new(PATH_SYSFS_GPIO).exists() {
+        return Ok(false);
+    }
+
+    let gpio = chip_base()? + u32::from(pin);
+    if !Path::new(&format!("{}/gpio{}", PATH_SYSFS_GPIO, gpio)).exists() {
+        return Ok(false);
+    }
+
+    let path = format!("{}/unexport", PATH_SYSFS_GPIO);
+    match fs::write(&path, gpio.to_string()) {
+        Ok(()) => Ok(true),
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            Err(Error::PermissionDenied(path))
+        }
+        Err(e) => Err(Error::Io(e)),
+    }
+}
@@ -6,11 +6,32 @@
 //! This module is only included when either the `hal` or `hal-unproven` feature
 //! flag is enabled.
 
+use std::thread;
 use std::time::Duration;
 #[cfg(feature = "embedded-hal-0")]
 use std::time::Instant;
 
+use crate::time;
+
+// Delays shorter than SPIN_THRESHOLD are busy-waited, because thread::sleep
+// is too coarse. Longer delays sleep until SLEEP_MARGIN before the deadline,
+// and busy-wait for the remainder.
+const SPIN_THRESHOLD: Duration = Duration::from_millis(1);
+const SLEEP_MARGIN: Duration = Duration::from_micros(100);
+
 /// Implements the `embedded-hal` `DelayMs` and `DelayNs` traits.
+///
+/// The delay is measured using the monotonic system clock (`CLOCK_MONOTONIC`), which
+/// is also used by [`time::Instant`].
+///
+/// Delays shorter than 1 ms are busy-waited, which keeps the current thread on the
+/// CPU, but offers a much higher accuracy than `thread::sleep`. Longer delays put the
+/// thread to sleep until 100 µs before the deadline, and busy-wait for the remainder
+/// to compensate for the scheduler's wake-up latency. Busy-waiting is handled by
+/// [`time::sleep_busy`].
+///
+/// [`time::Instant`]: ../time/struct.Instant.html
+/// [`time::sleep_busy`]: ../time/fn.sleep_busy.html
 #[derive(Debug, Default)]
 pub struct Delay;
 
@@ -19,6 +40,16 @@ impl Delay {
     pub fn new() -> Delay {
         Delay {}
     }
+
+    fn delay(&mut self, duration: Duration) {
+        let start = time::Instant::now();
+
+        if duration >= SPIN_THRESHOLD {
+            thread::sleep(duration - SLEEP_MARGIN);
+        }
+
+        time::sleep_busy(duration.saturating_sub(start.elapsed()));
+    }
 }
 
 #[cfg(feature = "embedded-hal-0")]
@@ -71,15 +102,15 @@ impl embedded_hal_0::blocking::delay::DelayUs<u16> for Delay {
 #[cfg(feature = "embedded-hal")]
 impl embedded_hal::delay::DelayNs for Delay {
     fn delay_ns(&mut self, ns: u32) {
-        spin_sleep::sleep(Duration::from_nanos(ns.into()));
+        self.delay(Duration::from_nanos(ns.into()));
     }
 
     fn delay_us(&mut self, us: u32) {
-        spin_sleep::sleep(Duration::from_micros(us.into()));
+        self.delay(Duration::from_micros(us.into()));
     }
 
     fn delay_ms(&mut self, ms: u32) {
-        spin_sleep::sleep(Duration::from_millis(ms.into()));
+        self.delay(Duration::from_millis(ms.into()));
     }
 }
 
@@ -79,6 +79,19 @@
 //! clock frequency in hertz (Hz). Remember to reboot
 //! the Raspberry Pi afterwards.
 //!
+//! ## Mock bus
+//!
+//! When the `mock` feature is enabled, and the `RPPAL_MOCK` environment variable is
+//! set to a non-empty value, [`I2c::new`] and [`I2c::with_bus`] open an in-memory
+//! loopback bus instead of `/dev/i2c-*`. Every slave address on the mock bus behaves
+//! like a device with 256 8-bit registers. The first byte of each write selects a
+//! register, and any remaining bytes are stored starting at that register. Reads
+//! return the stored bytes starting at the last selected register, so any data
+//! that's written through plain I2C transfers or SMBus protocols can be read back.
+//!
+//! [`I2c::new`]: struct.I2c.html#method.new
+//! [`I2c::with_bus`]: struct.I2c.html#method.with_bus
+//!
 //! ## Not supported
 //!
 //! Some I2C and SMBus features aren't fully supported by the `i2cdev` interface, the underlying driver or
@@ -106,9 +119,16 @@
 //! Transactions return an `io::ErrorKind::TimedOut` error when their duration
 //! exceeds the timeout value. You can change the timeout using [`set_timeout`].
 //!
+//! ### Bus blocked
+//!
+//! If a slave device was interrupted in the middle of a transfer, it may keep holding
+//! SDA low, which causes any further transactions to fail. [`recover_bus`] attempts
+//! to free the bus by generating additional clock pulses.
+//!
 //! [`new`]: struct.I2c.html#method.new
 //! [`with_bus`]: struct.I2c.html#method.with_bus
 //! [`set_timeout`]: struct.I2c.html#method.set_timeout
+//! [`recover_bus`]: struct.I2c.html#method.recover_bus
 
 #![allow(dead_code)]
 
@@ -118,11 +138,14 @@ use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::{Read, Write};
 use std::marker::PhantomData;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::result;
+use std::thread;
+use std::time::Duration;
 
 use libc::c_ulong;
 
+use crate::gpio::{self, Gpio, IoPin, Level, Mode};
 use crate::system;
 use crate::system::{DeviceInfo, Model};
 
@@ -133,14 +156,29 @@ use crate::system::{DeviceInfo, Model};
 ))]
 mod hal;
 mod ioctl;
+#[cfg(feature = "mock")]
+mod mock;
+mod shared;
 
-pub use self::ioctl::Capabilities;
+pub use self::ioctl::{Capabilities, Message};
+pub use self::shared::{I2cDevice, SharedI2c};
+
+// I2C_TIMEOUT is specified in units of 10ms
+const TIMEOUT_UNIT_NANOS: u128 = 10_000_000;
+
+// Number of clock pulses needed to let a slave device finish sending a byte and
+// its ACK/NACK bit
+const RECOVERY_CLOCK_PULSES: u8 = 9;
+// Half of a 100 kHz clock period
+const RECOVERY_HALF_PERIOD: Duration = Duration::from_micros(5);
 
 /// Errors that can occur when accessing the I2C peripheral.
 #[derive(Debug)]
 pub enum Error {
     /// I/O error.
     Io(io::Error),
+    /// GPIO error.
+    Gpio(gpio::Error),
     /// Invalid slave address.
     ///
     /// I2C supports 7-bit and 10-bit addresses. Several 7-bit addresses
@@ -150,6 +188,14 @@ pub enum Error {
     ///
     /// The underlying drivers don't support the selected I2C feature or SMBus protocol.
     FeatureNotSupported,
+    /// SMBus Packet Error Code mismatch.
+    ///
+    /// The PEC byte received from the slave device doesn't match the
+    /// calculated CRC-8 value, which indicates the incoming data may
+    /// have been corrupted. You might want to retry the transaction.
+    InvalidPec,
+    /// Invalid or out of range value.
+    InvalidValue,
     /// Unknown model.
     ///
     /// The Raspberry Pi model or SoC can't be identified. Support for
@@ -167,8 +213,11 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::Gpio(ref err) => write!(f, "GPIO error: {}", err),
             Error::InvalidSlaveAddress(address) => write!(f, "Invalid slave address: {}", address),
             Error::FeatureNotSupported => write!(f, "I2C/SMBus feature not supported"),
+            Error::InvalidPec => write!(f, "SMBus Packet Error Code mismatch"),
+            Error::InvalidValue => write!(f, "Invalid or out of range value"),
             Error::UnknownModel => write!(f, "Unknown Raspberry Pi model"),
         }
     }
@@ -178,7 +227,18 @@ impl error::Error for Error {}
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Error {
-        Error::Io(err)
+        // The kernel reports PEC mismatches as EBADMSG
+        if err.raw_os_error() == Some(libc::EBADMSG) {
+            Error::InvalidPec
+        } else {
+            Error::Io(err)
+        }
+    }
+}
+
+impl From<gpio::Error> for Error {
+    fn from(err: gpio::Error) -> Error {
+        Error::Gpio(err)
     }
 }
 
@@ -235,10 +295,18 @@ impl I2c {
     ///
     /// [here]: index.html#i2c-buses
     pub fn new() -> Result<I2c> {
+        #[cfg(feature = "mock")]
+        if crate::mock::is_requested() {
+            return I2c::with_bus(1);
+        }
+
         match DeviceInfo::new()?.model() {
             // Pi B Rev 1 uses I2C0
             Model::RaspberryPiBRev1 => I2c::with_bus(0),
-            Model::RaspberryPi4B | Model::RaspberryPi400 | Model::RaspberryPi5 => {
+            Model::RaspberryPi4B
+            | Model::RaspberryPi400
+            | Model::RaspberryPi5
+            | Model::RaspberryPi500 => {
                 // Pi 4B/400 could have I2C3 enabled on pins 3 and 5
                 I2c::with_bus(1).or_else(|_| I2c::with_bus(3))
             }
@@ -261,10 +329,13 @@ impl I2c {
     pub fn with_bus(bus: u8) -> Result<I2c> {
         // bus is a u8, because any 8-bit bus ID could potentially
         // be configured for bit banging I2C using i2c-gpio.
-        let i2cdev = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(format!("/dev/i2c-{}", bus))?;
+        let i2cdev = match I2c::open_mock()? {
+            Some(i2cdev) => i2cdev,
+            None => OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(format!("/dev/i2c-{}", bus))?,
+        };
 
         let capabilities = ioctl::funcs(i2cdev.as_raw_fd())?;
 
@@ -288,10 +359,27 @@ impl I2c {
         })
     }
 
+    // Opens a loopback bus if the mock backend is explicitly requested through the
+    // RPPAL_MOCK environment variable.
+    #[cfg(feature = "mock")]
+    fn open_mock() -> Result<Option<File>> {
+        if !crate::mock::is_requested() {
+            return Ok(None);
+        }
+
+        Ok(Some(mock::open()?))
+    }
+
+    #[cfg(not(feature = "mock"))]
+    fn open_mock() -> Result<Option<File>> {
+        Ok(None)
+    }
+
     /// Returns information on the functionality supported by the underlying drivers.
     ///
     /// The returned [`Capabilities`] instance lists the available
-    /// I2C and SMBus features.
+    /// I2C and SMBus features. The functionality flags are retrieved once,
+    /// when `I2c` is constructed.
     ///
     /// [`Capabilities`]: struct.Capabilities.html
     pub fn capabilities(&self) -> Capabilities {
@@ -346,22 +434,177 @@ impl I2c {
         Ok(())
     }
 
-    /// Sets the maximum duration of a transaction in milliseconds (ms).
+    /// Enables 10-bit addressing, and sets a 10-bit slave address.
     ///
-    /// Transactions that take longer than `timeout` return an
-    /// `io::ErrorKind::TimedOut` error.
+    /// `set_slave_address_10bit` is a convenience method that combines [`set_addr_10bit`]
+    /// and [`set_slave_address`]. Subsequent transfers use the two-byte 10-bit address
+    /// frame until 10-bit addressing is disabled again with [`set_addr_10bit`].
+    ///
+    /// 10-bit addressing currently isn't supported on the Raspberry Pi.
+    /// `set_slave_address_10bit` returns `Err(`[`Error::FeatureNotSupported`]`)` unless
+    /// underlying driver support is detected. Addresses above `0x03FF` return
+    /// `Err(`[`Error::InvalidSlaveAddress`]`)`.
+    ///
+    /// [`set_addr_10bit`]: #method.set_addr_10bit
+    /// [`set_slave_address`]: #method.set_slave_address
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    /// [`Error::InvalidSlaveAddress`]: enum.Error.html#variant.InvalidSlaveAddress
+    pub fn set_slave_address_10bit(&mut self, slave_address: u16) -> Result<()> {
+        if !self.capabilities().addr_10bit() {
+            return Err(Error::FeatureNotSupported);
+        }
+
+        if slave_address > 0x03FF {
+            return Err(Error::InvalidSlaveAddress(slave_address));
+        }
+
+        if !self.addr_10bit {
+            self.set_addr_10bit(true)?;
+        }
+
+        self.set_slave_address(slave_address)
+    }
+
+    /// Probes the 7-bit addresses `0x03..=0x77`, and returns the addresses of all slave
+    /// devices that acknowledged the probe.
+    ///
+    /// Similar to `i2cdetect`, `scan` probes each address with a zero-length write
+    /// (SMBus Quick Command) when it's supported by the underlying drivers, and with
+    /// a 1-byte read otherwise. Addresses in the ranges `0x30..=0x37` and `0x50..=0x5F`
+    /// are always probed with a 1-byte read, because a zero-length write could corrupt
+    /// the contents of some EEPROMs.
+    ///
+    /// Addresses that don't acknowledge the probe are skipped. Addresses that are
+    /// currently claimed by a kernel driver can't be probed, and are skipped as well.
+    ///
+    /// The slave address and 10-bit addressing configuration are restored before
+    /// `scan` returns.
+    pub fn scan(&mut self) -> Result<Vec<u16>> {
+        let fd = self.i2cdev.as_raw_fd();
+        let quick = self.capabilities().smbus_quick_command();
+        let address = self.address;
+
+        if self.addr_10bit {
+            ioctl::set_addr_10bit(fd, 0)?;
+        }
+
+        let mut result = Ok(Vec::new());
+        for probe_address in 0x03..=0x77u16 {
+            match self.probe(probe_address, quick) {
+                Ok(true) => {
+                    if let Ok(ref mut addresses) = result {
+                        addresses.push(probe_address);
+                    }
+                }
+                Ok(false) => (),
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        // Restore the previous configuration, even if probing failed. Both settings are
+        // restored before returning the first error that occurred.
+        let restored_addr_10bit = if self.addr_10bit {
+            ioctl::set_addr_10bit(fd, 1).map_err(Error::from)
+        } else {
+            Ok(())
+        };
+
+        // i2cdev rejects address 0, which is only used when no slave address was set
+        let restored_address = if address != 0 {
+            self.set_slave_address(address)
+        } else {
+            self.address = 0;
+            Ok(())
+        };
+
+        let addresses = result?;
+        restored_addr_10bit?;
+        restored_address?;
+
+        Ok(addresses)
+    }
+
+    // Returns true if a slave device acknowledged its address
+    fn probe(&mut self, address: u16, quick: bool) -> Result<bool> {
+        match self.set_slave_address(address) {
+            Ok(()) => (),
+            // Address is in use by a kernel driver
+            Err(Error::Io(ref e)) if e.raw_os_error() == Some(libc::EBUSY) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+
+        let probe =
+            if quick && !(0x30..=0x37).contains(&address) && !(0x50..=0x5F).contains(&address) {
+                ioctl::smbus_quick_command(self.i2cdev.as_raw_fd(), false)
+            } else {
+                let mut buffer = [0u8; 1];
+                self.read_i2cdev(&mut buffer).map(|_| ())
+            };
+
+        match probe {
+            Ok(()) => Ok(true),
+            // No ACK. Depending on the driver, this is reported as ENXIO, EREMOTEIO or EIO.
+            Err(ref e)
+                if matches!(
+                    e.raw_os_error(),
+                    Some(libc::ENXIO) | Some(libc::EREMOTEIO) | Some(libc::EIO)
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+
+    /// Sets the maximum duration of a transaction.
     ///
-    /// `timeout` has a resolution of 10ms.
-    pub fn set_timeout(&self, timeout: u32) -> Result<()> {
+    /// Transactions that take longer than `timeout` return an
+    /// `io::ErrorKind::TimedOut` error. This includes any time the slave device
+    /// spends stretching the clock.
+    ///
+    /// `timeout` has a resolution of 10ms, and is rounded up to the next multiple
+    /// of 10ms. A `timeout` that can't be represented by the underlying ioctl
+    /// returns an `io::ErrorKind::InvalidInput` error.
+    pub fn set_timeout(&self, timeout: Duration) -> Result<()> {
+        // Timeout is specified in units of 10ms
+        let units = (timeout.as_nanos() + TIMEOUT_UNIT_NANOS - 1) / TIMEOUT_UNIT_NANOS;
+        let units = match c_ulong::try_from(units) {
+            Ok(units) => units,
+            Err(_) => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "timeout out of range",
+                )))
+            }
+        };
+
         // Contrary to the i2cdev documentation, this seems to
         // be used as a timeout for (part of?) the I2C transaction.
-        ioctl::set_timeout(self.i2cdev.as_raw_fd(), timeout as c_ulong)?;
+        ioctl::set_timeout(self.i2cdev.as_raw_fd(), units)?;
 
         Ok(())
     }
 
-    fn set_retries(&self, retries: u32) -> Result<()> {
-        // Set to private. While i2cdev implements retries, the underlying drivers don't.
+    /// Sets the number of times the kernel retries a transaction after a failed attempt.
+    ///
+    /// The I2C core only retries a transaction when the bus driver reports the
+    /// failure as a lost arbitration (`EAGAIN`). Retries stop once the total time spent
+    /// on all attempts exceeds the duration configured with [`set_timeout`], even if
+    /// `retries` hasn't been reached yet.
+    ///
+    /// The `i2c-bcm2835` driver used on most Raspberry Pi models reports NACKs as
+    /// `EREMOTEIO`, and other bus errors as `EIO`, neither of which is retried. On those
+    /// models, `set_retries` has no effect, and failed transactions need to be retried
+    /// manually.
+    ///
+    /// If `set_retries` isn't called, the retry count configured by the bus driver is
+    /// left unchanged, which is 0 for most drivers.
+    ///
+    /// [`set_timeout`]: #method.set_timeout
+    pub fn set_retries(&self, retries: u8) -> Result<()> {
         ioctl::set_retries(self.i2cdev.as_raw_fd(), retries as c_ulong)?;
 
         Ok(())
@@ -395,7 +638,7 @@ impl I2c {
     ///
     /// Returns how many bytes were read.
     pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
-        Ok(self.i2cdev.read(buffer)?)
+        Ok(self.read_i2cdev(buffer)?)
     }
 
     /// Sends the outgoing data contained in `buffer` to the slave device.
@@ -404,7 +647,33 @@ impl I2c {
     ///
     /// Returns how many bytes were written.
     pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
-        Ok(self.i2cdev.write(buffer)?)
+        Ok(self.write_i2cdev(buffer)?)
+    }
+
+    // Reads from i2cdev, or from the loopback bus if the mock backend is used
+    fn read_i2cdev(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        #[cfg(feature = "mock")]
+        if mock::BUSES
+            .with(self.i2cdev.as_raw_fd(), |bus| bus.slave().read(buffer))
+            .is_some()
+        {
+            return Ok(buffer.len());
+        }
+
+        self.i2cdev.read(buffer)
+    }
+
+    // Writes to i2cdev, or to the loopback bus if the mock backend is used
+    fn write_i2cdev(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        #[cfg(feature = "mock")]
+        if mock::BUSES
+            .with(self.i2cdev.as_raw_fd(), |bus| bus.slave().write(buffer))
+            .is_some()
+        {
+            return Ok(buffer.len());
+        }
+
+        self.i2cdev.write(buffer)
     }
 
     /// Sends the outgoing data contained in `write_buffer` to the slave device, and
@@ -415,13 +684,20 @@ impl I2c {
     /// START is sent instead.
     ///
     /// `write_read` reads as many bytes as can fit in `read_buffer`. The maximum
-    /// number of bytes in either `write_buffer` or `read_buffer` can't exceed 8192.
+    /// number of bytes in either `write_buffer` or `read_buffer` can't exceed 8192,
+    /// which is the limit imposed by `i2cdev` for a single message. Unlike
+    /// [`block_read`], `write_read` isn't limited to 32 bytes, which makes it suitable
+    /// for reading an entire EEPROM page, with the memory address in `write_buffer`.
+    /// Larger buffers return an `io::ErrorKind::InvalidInput` error, rather than
+    /// being truncated. If either buffer is empty, only the other operation is
+    /// performed.
     ///
     /// Sequence: START → Address + Write Bit → Outgoing Bytes → Repeated START →
     /// Address + Read Bit → Incoming Bytes → STOP
     ///
     /// [`write`]: #method.write
     /// [`read`]: #method.read
+    /// [`block_read`]: #method.block_read
     pub fn write_read(&self, write_buffer: &[u8], read_buffer: &mut [u8]) -> Result<()> {
         ioctl::i2c_write_read(
             self.i2cdev.as_raw_fd(),
@@ -434,6 +710,171 @@ impl I2c {
         Ok(())
     }
 
+    /// Sets the slave address to `slave_address`, and reads a single byte from
+    /// register `register`.
+    ///
+    /// `read_reg` is a convenience method that combines [`set_slave_address`] and
+    /// [`write_read`]. The register address is sent, followed by a repeated START and
+    /// the read operation, which prevents another master from accessing the device in
+    /// between, and avoids the STOP condition that some devices don't accept before
+    /// a register read.
+    ///
+    /// The slave address remains set for any subsequent transfers.
+    ///
+    /// Sequence: START → Address + Write Bit → Register → Repeated START →
+    /// Address + Read Bit → Incoming Byte → STOP
+    ///
+    /// [`set_slave_address`]: #method.set_slave_address
+    /// [`write_read`]: #method.write_read
+    pub fn read_reg(&mut self, slave_address: u16, register: u8) -> Result<u8> {
+        let mut buffer = [0u8; 1];
+        self.read_regs(slave_address, register, &mut buffer)?;
+
+        Ok(buffer[0])
+    }
+
+    /// Sets the slave address to `slave_address`, and fills `buffer` with the
+    /// contents of consecutive registers, starting at register `register`.
+    ///
+    /// Most devices automatically increment the register address after each byte
+    /// that's read. `read_regs` can read up to 8192 bytes.
+    ///
+    /// The slave address remains set for any subsequent transfers.
+    ///
+    /// Sequence: START → Address + Write Bit → Register → Repeated START →
+    /// Address + Read Bit → Incoming Bytes → STOP
+    pub fn read_regs(&mut self, slave_address: u16, register: u8, buffer: &mut [u8]) -> Result<()> {
+        self.set_slave_address(slave_address)?;
+
+        self.write_read(&[register], buffer)
+    }
+
+    /// Sets the slave address to `slave_address`, and writes `value` to register
+    /// `register`.
+    ///
+    /// The slave address remains set for any subsequent transfers.
+    ///
+    /// Sequence: START → Address + Write Bit → Register → Outgoing Byte → STOP
+    pub fn write_reg(&mut self, slave_address: u16, register: u8, value: u8) -> Result<()> {
+        self.set_slave_address(slave_address)?;
+
+        self.write_read(&[register, value], &mut [])
+    }
+
+    /// Attempts to free a bus that's blocked by a slave device holding SDA low, and
+    /// returns `true` if SDA is released afterwards.
+    ///
+    /// A slave device that was interrupted in the middle of a transfer, for instance
+    /// due to a reset of the Raspberry Pi, may keep driving SDA low while it waits for
+    /// additional clock pulses, which prevents any further transfers.
+    ///
+    /// `recover_bus` temporarily switches the SDA and SCL pins from their I2C function
+    /// to GPIO, and pulses SCL up to 9 times at 100 kHz until the slave device releases
+    /// SDA. It then generates a STOP condition, and restores the original pin modes.
+    /// SCL and SDA are only driven low, and rely on the bus pull-up resistors to return
+    /// high.
+    ///
+    /// `recover_bus` only supports the hardware I2C buses on their default pins, as
+    /// listed [here]. Returns [`Error::FeatureNotSupported`] for any other bus, or if
+    /// the pins aren't currently configured for an alternate function. Returns
+    /// [`Error::Gpio`] if the pins can't be accessed, for instance because they're
+    /// already in use elsewhere in your application.
+    ///
+    /// [here]: index.html#i2c-buses
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    /// [`Error::Gpio`]: enum.Error.html#variant.Gpio
+    pub fn recover_bus(&mut self) -> Result<bool> {
+        let (sda, scl) = match self.bus {
+            0 => (0, 1),
+            1 => (2, 3),
+            3 => (4, 5),
+            4 => (8, 9),
+            5 => (12, 13),
+            6 => (22, 23),
+            _ => return Err(Error::FeatureNotSupported),
+        };
+
+        let gpio = Gpio::new()?;
+        let sda = gpio.get(sda)?;
+        let scl = gpio.get(scl)?;
+
+        if !is_alt_mode(sda.mode()) || !is_alt_mode(scl.mode()) {
+            return Err(Error::FeatureNotSupported);
+        }
+
+        // The original modes are restored when the IoPins go out of scope
+        let mut sda = sda.into_io(Mode::Input);
+        let mut scl = scl.into_io(Mode::Input);
+
+        // Both lines are driven low by switching to output mode, and released by
+        // switching back to input mode
+        sda.set_low();
+        scl.set_low();
+
+        for _ in 0..RECOVERY_CLOCK_PULSES {
+            if sda.read() == Level::High {
+                break;
+            }
+
+            pulse_line(&mut scl, Mode::Output);
+            pulse_line(&mut scl, Mode::Input);
+        }
+
+        // STOP condition: SDA goes high while SCL is high
+        pulse_line(&mut scl, Mode::Output);
+        pulse_line(&mut sda, Mode::Output);
+        pulse_line(&mut scl, Mode::Input);
+        pulse_line(&mut sda, Mode::Input);
+
+        Ok(sda.read() == Level::High)
+    }
+
+    /// Performs a transaction consisting of an arbitrary sequence of read and write
+    /// [`Message`]s, while holding the bus for the entire transaction.
+    ///
+    /// Consecutive messages are separated by a repeated START, unless a STOP
+    /// was requested through [`Message::with_stop`]. The transaction always ends
+    /// with a STOP.
+    ///
+    /// Empty messages are sent as zero-length messages, which only transfer the slave
+    /// address and the read/write bit. A transaction consisting of a single empty
+    /// write message can be used to check whether a slave device acknowledges its
+    /// address, in which case an error is returned if no device responds. Some bus
+    /// drivers don't support zero-length messages, and return an error instead. If
+    /// `messages` is empty, `transaction` returns `Ok(())` without accessing the bus.
+    ///
+    /// A transaction can contain up to 42 messages, and each message can contain up
+    /// to 8192 bytes. Larger transactions return an `io::ErrorKind::InvalidInput`
+    /// error.
+    ///
+    /// Returns [`Error::FeatureNotSupported`] if a STOP was requested in between two
+    /// messages, and the underlying drivers don't support protocol mangling, as
+    /// indicated by [`Capabilities::protocol_mangling`].
+    ///
+    /// Sequence: START → Address + Read/Write Bit → Incoming/Outgoing Bytes →
+    /// Repeated START or STOP/START → ... → STOP
+    ///
+    /// [`Message`]: struct.Message.html
+    /// [`Message::with_stop`]: struct.Message.html#method.with_stop
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    /// [`Capabilities::protocol_mangling`]: struct.Capabilities.html#method.protocol_mangling
+    pub fn transaction(&self, messages: &mut [Message<'_>]) -> Result<()> {
+        if let Some((_, messages)) = messages.split_last() {
+            if messages.iter().any(|message| message.stop()) && !self.funcs.protocol_mangling() {
+                return Err(Error::FeatureNotSupported);
+            }
+        }
+
+        ioctl::i2c_transfer(
+            self.i2cdev.as_raw_fd(),
+            self.address,
+            self.addr_10bit,
+            messages,
+        )?;
+
+        Ok(())
+    }
+
     /// Sends an 8-bit `command`, and then fills a multi-byte `buffer` with
     /// incoming data.
     ///
@@ -444,10 +885,15 @@ impl I2c {
     /// The difference between `block_read` and [`smbus_block_read`] is that the
     /// latter also expects a byte count from the slave device.
     ///
+    /// To read more than 32 bytes, or to send a command or register address that's
+    /// longer than a single byte, use [`write_read`] instead, which isn't bound by the
+    /// SMBus block size, and can read up to 8192 bytes in a single transaction.
+    ///
     /// Sequence: START → Address + Write Bit → Command → Repeated START
     /// → Address + Read Bit → Incoming Bytes → STOP
     ///
     /// [`smbus_block_read`]: #method.smbus_block_read
+    /// [`write_read`]: #method.write_read
     pub fn block_read(&self, command: u8, buffer: &mut [u8]) -> Result<()> {
         ioctl::i2c_block_read(self.i2cdev.as_raw_fd(), command, buffer)?;
 
@@ -457,7 +903,8 @@ impl I2c {
     /// Sends an 8-bit `command` followed by a multi-byte `buffer`.
     ///
     /// `block_write` can write a maximum of 32 bytes. Any additional data contained
-    /// in `buffer` is ignored.
+    /// in `buffer` is ignored. To write more than 32 bytes, use [`write`] instead,
+    /// with the command prepended to the outgoing data.
     ///
     /// Although `block_write` isn't part of the SMBus protocol, it uses the
     /// SMBus functionality to offer this commonly used I2C transaction format. The
@@ -467,6 +914,7 @@ impl I2c {
     /// Sequence: START → Address + Write Bit → Command → Outgoing Bytes → STOP
     ///
     /// [`smbus_block_write`]: #method.smbus_block_write
+    /// [`write`]: #method.write
     pub fn block_write(&self, command: u8, buffer: &[u8]) -> Result<()> {
         ioctl::i2c_block_write(self.i2cdev.as_raw_fd(), command, buffer)?;
 
@@ -671,6 +1119,40 @@ impl I2c {
         Ok(())
     }
 
+    /// Sends an 8-bit `command` and an 8-bit byte count along with a multi-byte `buffer`,
+    /// and then receives an 8-bit byte count along with a multi-byte response in a single
+    /// transaction.
+    ///
+    /// `smbus_block_process_call` returns `Err(`[`Error::FeatureNotSupported`]`)` unless
+    /// underlying driver support is detected.
+    ///
+    /// `smbus_block_process_call` can write and read a maximum of 32 bytes. Returns
+    /// `Err(`[`Error::InvalidValue`]`)` if `buffer` contains more than 32 bytes.
+    ///
+    /// Sequence: START → Address + Write Bit → Command → Outgoing Byte Count →
+    /// Outgoing Bytes → Repeated START → Address + Read Bit → Incoming Byte Count →
+    /// Incoming Bytes → STOP
+    ///
+    /// Returns the incoming bytes.
+    ///
+    /// [`Error::FeatureNotSupported`]: enum.Error.html#variant.FeatureNotSupported
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn smbus_block_process_call(&self, command: u8, buffer: &[u8]) -> Result<Vec<u8>> {
+        if buffer.len() > 32 {
+            return Err(Error::InvalidValue);
+        }
+
+        if !self.capabilities().smbus_block_process_call() {
+            return Err(Error::FeatureNotSupported);
+        }
+
+        Ok(ioctl::smbus_block_process_call(
+            self.i2cdev.as_raw_fd(),
+            command,
+            buffer,
+        )?)
+    }
+
     /// Enables or disables SMBus Packet Error Checking.
     ///
     /// Packet Error Checking inserts a CRC-8 Packet Error Code (PEC) byte before each STOP
@@ -678,7 +1160,14 @@ impl I2c {
     ///
     /// The PEC is calculated on all message bytes except the START, STOP, ACK and NACK bits.
     ///
+    /// When Packet Error Checking is enabled, the `smbus_*` methods append the PEC byte
+    /// to outgoing data, and verify the PEC byte of incoming data. A mismatch returns
+    /// `Err(`[`Error::InvalidPec`]`)`. The PEC byte is handled by the kernel and isn't
+    /// included in any of the buffers or values.
+    ///
     /// By default, `pec` is set to `false`.
+    ///
+    /// [`Error::InvalidPec`]: enum.Error.html#variant.InvalidPec
     pub fn set_smbus_pec(&self, pec: bool) -> Result<()> {
         ioctl::set_pec(self.i2cdev.as_raw_fd(), pec as c_ulong)?;
 
@@ -689,3 +1178,29 @@ impl I2c {
 // Send is safe for I2c, but we're marked !Send because of the dummy pointer that's
 // needed to force !Sync.
 unsafe impl Send for I2c {}
+
+#[cfg(feature = "mock")]
+impl Drop for I2c {
+    fn drop(&mut self) {
+        mock::BUSES.remove(self.i2cdev.as_raw_fd());
+    }
+}
+
+/// Returns the raw file descriptor of the underlying `i2cdev` device.
+impl AsRawFd for I2c {
+    fn as_raw_fd(&self) -> RawFd {
+        self.i2cdev.as_raw_fd()
+    }
+}
+
+// Pins that are switched to one of the alternate functions are assumed to be
+// connected to the I2C peripheral
+fn is_alt_mode(mode: Mode) -> bool {
+    !matches!(mode, Mode::Input | Mode::Output)
+}
+
+// Changes the mode of a bus recovery line, and waits for half a clock period
+fn pulse_line(pin: &mut IoPin, mode: Mode) {
+    pin.set_mode(mode);
+    thread::sleep(RECOVERY_HALF_PERIOD);
+}
@@ -1,4 +1,6 @@
-use super::{Error, I2c};
+use std::os::unix::io::AsRawFd;
+
+use super::{ioctl, Error, I2c};
 
 #[cfg(feature = "embedded-hal-0")]
 impl embedded_hal_0::blocking::i2c::Write for I2c {
@@ -40,18 +42,98 @@ impl embedded_hal::i2c::ErrorType for I2c {
 #[cfg(feature = "embedded-hal")]
 impl embedded_hal::i2c::Error for Error {
     fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        use embedded_hal::i2c::{ErrorKind, NoAcknowledgeSource};
+
         if let Error::Io(e) = self {
+            match e.raw_os_error() {
+                Some(libc::ENXIO) | Some(libc::EREMOTEIO) => {
+                    return ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown)
+                }
+                Some(libc::EAGAIN) => return ErrorKind::ArbitrationLoss,
+                Some(libc::EIO) | Some(libc::EPROTO) => return ErrorKind::Bus,
+                _ => (),
+            }
+
             use std::io::ErrorKind::*;
 
             match e.kind() {
-                /* ResourceBusy | */ InvalidData => embedded_hal::i2c::ErrorKind::Bus,
-                WouldBlock => embedded_hal::i2c::ErrorKind::ArbitrationLoss,
-                _ => embedded_hal::i2c::ErrorKind::Other,
+                /* ResourceBusy | */ InvalidData => ErrorKind::Bus,
+                WouldBlock => ErrorKind::ArbitrationLoss,
+                _ => ErrorKind::Other,
             }
         } else {
-            embedded_hal::i2c::ErrorKind::Other
+            ErrorKind::Other
+        }
+    }
+}
+
+// Adjacent operations of the same type are merged, because they shouldn't be
+// separated by a repeated START.
+#[cfg(feature = "embedded-hal")]
+fn transaction(
+    i2c: &I2c,
+    address: u16,
+    addr_10bit: bool,
+    operations: &mut [embedded_hal::i2c::Operation<'_>],
+) -> Result<(), Error> {
+    use embedded_hal::i2c::Operation;
+
+    // Consecutive operations of the same type are grouped as (is_read, start, end)
+    let mut groups: Vec<(bool, usize, usize)> = Vec::new();
+    for (index, op) in operations.iter().enumerate() {
+        let is_read = matches!(op, Operation::Read(_));
+
+        match groups.last_mut() {
+            Some((group_read, _, end)) if *group_read == is_read => *end = index + 1,
+            _ => groups.push((is_read, index, index + 1)),
+        }
+    }
+
+    let mut buffers: Vec<Vec<u8>> = groups
+        .iter()
+        .map(|&(_, start, end)| {
+            let mut buffer = Vec::new();
+            for op in &operations[start..end] {
+                match op {
+                    Operation::Read(read) => buffer.resize(buffer.len() + read.len(), 0),
+                    Operation::Write(write) => buffer.extend_from_slice(write),
+                }
+            }
+
+            buffer
+        })
+        .collect();
+
+    let mut segments: Vec<ioctl::Message<'_>> = groups
+        .iter()
+        .zip(buffers.iter_mut())
+        .map(|(&(is_read, _, _), buffer)| {
+            if is_read {
+                ioctl::Message::read(buffer)
+            } else {
+                ioctl::Message::write(buffer)
+            }
+        })
+        .collect();
+
+    ioctl::i2c_transfer(i2c.i2cdev.as_raw_fd(), address, addr_10bit, &mut segments)?;
+
+    // Copy the incoming data back to the original read buffers
+    for (&(is_read, start, end), buffer) in groups.iter().zip(buffers.iter()) {
+        if !is_read {
+            continue;
+        }
+
+        let mut offset = 0;
+        for op in &mut operations[start..end] {
+            if let Operation::Read(read) = op {
+                read.copy_from_slice(&buffer[offset..offset + read.len()]);
+                offset += read.len();
+            }
         }
     }
+
+    Ok(())
 }
 
 #[cfg(feature = "embedded-hal")]
@@ -59,20 +141,23 @@ impl embedded_hal::i2c::I2c for I2c {
     fn transaction(
         &mut self,
         address: u8,
-        operations: &mut [embedded_hal::i2c::Operation],
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
     ) -> Result<(), Self::Error> {
-        self.set_slave_address(u16::from(address))?;
-        for op in operations {
-            match op {
-                embedded_hal::i2c::Operation::Read(buff) => {
-                    self.read(buff)?;
-                }
-                embedded_hal::i2c::Operation::Write(buff) => {
-                    self.write(buff)?;
-                }
-            }
+        transaction(self, u16::from(address), false, operations)
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::i2c::I2c<embedded_hal::i2c::TenBitAddress> for I2c {
+    fn transaction(
+        &mut self,
+        address: u16,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if !self.funcs.addr_10bit() {
+            return Err(Error::FeatureNotSupported);
         }
 
-        Ok(())
+        transaction(self, address, true, operations)
     }
 }
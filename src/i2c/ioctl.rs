@@ -5,6 +5,9 @@ use std::result;
 
 use libc::{self, c_int, c_ulong, ioctl};
 
+#[cfg(feature = "mock")]
+use super::mock;
+
 #[cfg(target_env = "gnu")]
 type IoctlLong = libc::c_ulong;
 #[cfg(target_env = "musl")]
@@ -37,6 +40,26 @@ const FUNC_SMBUS_READ_I2C_BLOCK: c_ulong = 0x0400_0000;
 const FUNC_SMBUS_WRITE_I2C_BLOCK: c_ulong = 0x0800_0000;
 const FUNC_SMBUS_HOST_NOTIFY: c_ulong = 0x1000_0000;
 
+// Capabilities of the loopback bus used by the mock backend
+#[cfg(feature = "mock")]
+const FUNCS_MOCK: c_ulong = FUNC_I2C
+    | FUNC_10BIT_ADDR
+    | FUNC_PROTOCOL_MANGLING
+    | FUNC_SMBUS_PEC
+    | FUNC_SMBUS_BLOCK_PROC_CALL
+    | FUNC_SMBUS_QUICK
+    | FUNC_SMBUS_READ_BYTE
+    | FUNC_SMBUS_WRITE_BYTE
+    | FUNC_SMBUS_READ_BYTE_DATA
+    | FUNC_SMBUS_WRITE_BYTE_DATA
+    | FUNC_SMBUS_READ_WORD_DATA
+    | FUNC_SMBUS_WRITE_WORD_DATA
+    | FUNC_SMBUS_PROC_CALL
+    | FUNC_SMBUS_READ_BLOCK_DATA
+    | FUNC_SMBUS_WRITE_BLOCK_DATA
+    | FUNC_SMBUS_READ_I2C_BLOCK
+    | FUNC_SMBUS_WRITE_I2C_BLOCK;
+
 /// Lists the features supported by the underlying drivers.
 #[derive(PartialEq, Eq, Copy, Clone)]
 pub struct Capabilities {
@@ -52,7 +75,13 @@ impl Capabilities {
         Capabilities { funcs }
     }
 
-    pub(crate) fn i2c(self) -> bool {
+    /// Indicates whether plain I2C transfers are supported.
+    ///
+    /// Plain I2C transfers are used by [`I2c::write_read`] and the `embedded-hal`
+    /// transaction implementations.
+    ///
+    /// [`I2c::write_read`]: struct.I2c.html#method.write_read
+    pub fn i2c(self) -> bool {
         (self.funcs & FUNC_I2C) > 0
     }
 
@@ -76,12 +105,12 @@ impl Capabilities {
     }
 
     /// Indicates whether protocol mangling is supported.
-    pub(crate) fn protocol_mangling(self) -> bool {
+    pub fn protocol_mangling(self) -> bool {
         (self.funcs & FUNC_PROTOCOL_MANGLING) > 0
     }
 
     /// Indicates whether the NOSTART flag is supported.
-    pub(crate) fn nostart(self) -> bool {
+    pub fn nostart(self) -> bool {
         (self.funcs & FUNC_NOSTART) > 0
     }
 
@@ -154,7 +183,10 @@ impl Capabilities {
 impl fmt::Debug for Capabilities {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Capabilities")
+            .field("i2c", &self.i2c())
             .field("addr_10bit", &self.addr_10bit())
+            .field("protocol_mangling", &self.protocol_mangling())
+            .field("nostart", &self.nostart())
             .field("i2c_block_read", &self.i2c_block_read())
             .field("i2c_block_write", &self.i2c_block_write())
             .field("smbus_quick_command", &self.smbus_quick_command())
@@ -190,8 +222,10 @@ const REQ_SMBUS: IoctlLong = 0x0720; // SMBus: Transfer data
 
 const RDWR_FLAG_RD: u16 = 0x0001; // Read operation
 const RDWR_FLAG_TEN: u16 = 0x0010; // 10-bit slave address
+const RDWR_FLAG_STOP: u16 = 0x8000; // Send a STOP after this segment (requires protocol mangling)
 
 const RDWR_MSG_MAX: usize = 42; // Maximum messages per RDWR operation
+const RDWR_BUFFER_MAX: usize = 8192; // Maximum bytes per RDWR segment
 const SMBUS_BLOCK_MAX: usize = 32; // Maximum bytes per block transfer
 
 // SMBus read or write request
@@ -210,6 +244,7 @@ enum SmbusSize {
     WordData = 3,
     ProcCall = 4,
     BlockData = 5,
+    BlockProcCall = 7,
     I2cBlockData = 8,
 }
 
@@ -288,15 +323,27 @@ fn smbus_request(
     size: SmbusSize,
     data: Option<&mut SmbusBuffer>,
 ) -> Result<()> {
+    let data: *mut SmbusBuffer = if let Some(buffer) = data {
+        buffer
+    } else {
+        ptr::null_mut()
+    };
+
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| unsafe {
+            mock_smbus_request(bus.slave(), read_write, command, size, data.as_mut())
+        })
+        .is_some()
+    {
+        return Ok(());
+    }
+
     let mut request = SmbusRequest {
         read_write: read_write as u8,
         command,
         size: size as u32,
-        data: if let Some(buffer) = data {
-            buffer
-        } else {
-            ptr::null_mut()
-        },
+        data,
     };
 
     parse_retval!(unsafe { ioctl(fd, REQ_SMBUS, &mut request) })?;
@@ -304,6 +351,70 @@ fn smbus_request(
     Ok(())
 }
 
+// Emulates an SMBus request on the mock backend's loopback bus, by sending the same
+// bytes to the device that would be sent over the bus on real hardware
+#[cfg(feature = "mock")]
+fn mock_smbus_request(
+    device: &mut mock::Device,
+    read_write: SmbusReadWrite,
+    command: u8,
+    size: SmbusSize,
+    data: Option<&mut SmbusBuffer>,
+) {
+    let mut empty = SmbusBuffer::new();
+    let data = &mut data.unwrap_or(&mut empty).data;
+
+    match (size, read_write) {
+        (SmbusSize::Quick, _) => (),
+        (SmbusSize::Byte, SmbusReadWrite::Read) => device.read(&mut data[..1]),
+        (SmbusSize::Byte, SmbusReadWrite::Write) => device.write(&[command]),
+        (SmbusSize::ByteData, SmbusReadWrite::Read) => {
+            device.write(&[command]);
+            device.read(&mut data[..1]);
+        }
+        (SmbusSize::ByteData, SmbusReadWrite::Write) => {
+            device.write(&[command, data[0]]);
+        }
+        (SmbusSize::WordData, SmbusReadWrite::Read) => {
+            device.write(&[command]);
+            device.read(&mut data[..2]);
+        }
+        (SmbusSize::WordData, SmbusReadWrite::Write) => {
+            device.write(&[command, data[0], data[1]]);
+        }
+        (SmbusSize::ProcCall, _) => {
+            device.write(&[command, data[0], data[1]]);
+            device.write(&[command]);
+            device.read(&mut data[..2]);
+        }
+        (SmbusSize::BlockData, SmbusReadWrite::Read) => {
+            device.write(&[command]);
+            device.read(&mut data[..=SMBUS_BLOCK_MAX]);
+            data[0] = data[0].min(SMBUS_BLOCK_MAX as u8);
+        }
+        (SmbusSize::BlockData, SmbusReadWrite::Write) => {
+            let len = data[0] as usize;
+            device.write(&[&[command], &data[..=len]].concat());
+        }
+        (SmbusSize::BlockProcCall, _) => {
+            let len = data[0] as usize;
+            device.write(&[&[command], &data[..=len]].concat());
+            device.write(&[command]);
+            device.read(&mut data[..=SMBUS_BLOCK_MAX]);
+            data[0] = data[0].min(SMBUS_BLOCK_MAX as u8);
+        }
+        (SmbusSize::I2cBlockData, SmbusReadWrite::Read) => {
+            let len = data[0] as usize;
+            device.write(&[command]);
+            device.read(&mut data[1..=len]);
+        }
+        (SmbusSize::I2cBlockData, SmbusReadWrite::Write) => {
+            let len = data[0] as usize;
+            device.write(&[&[command], &data[1..=len]].concat());
+        }
+    }
+}
+
 pub fn smbus_quick_command(fd: c_int, value: bool) -> Result<()> {
     // Quick Command uses the read_write field, instead of the data buffer
     smbus_request(
@@ -439,6 +550,26 @@ pub fn smbus_block_write(fd: c_int, command: u8, value: &[u8]) -> Result<()> {
     )
 }
 
+pub fn smbus_block_process_call(fd: i32, command: u8, value: &[u8]) -> Result<Vec<u8>> {
+    let mut buffer = SmbusBuffer::with_buffer(value);
+    smbus_request(
+        fd,
+        SmbusReadWrite::Write,
+        command,
+        SmbusSize::BlockProcCall,
+        Some(&mut buffer),
+    )?;
+
+    // Verify the length in case we're receiving corrupted data
+    let incoming_length = if buffer.data[0] as usize > SMBUS_BLOCK_MAX {
+        SMBUS_BLOCK_MAX
+    } else {
+        buffer.data[0] as usize
+    };
+
+    Ok(buffer.data[1..=incoming_length].to_vec())
+}
+
 pub fn i2c_block_read(fd: c_int, command: u8, value: &mut [u8]) -> Result<()> {
     let mut buffer = SmbusBuffer::new();
     buffer.data[0] = if value.len() > SMBUS_BLOCK_MAX {
@@ -495,6 +626,30 @@ struct RdwrRequest {
     nmsgs: u32,
 }
 
+// Emulates an RDWR request on the mock backend's loopback bus
+#[cfg(feature = "mock")]
+fn mock_rdwr_request(bus: &mut mock::Bus, segments: &[RdwrSegment]) {
+    for segment in segments {
+        let device = bus.device(segment.addr, segment.flags & RDWR_FLAG_TEN > 0);
+
+        // The segments point to the buffers that were borrowed by the caller, the
+        // same way they're accessed by the kernel on real hardware
+        unsafe {
+            if segment.flags & RDWR_FLAG_RD > 0 {
+                device.read(std::slice::from_raw_parts_mut(
+                    segment.data as *mut u8,
+                    segment.len as usize,
+                ));
+            } else {
+                device.write(std::slice::from_raw_parts(
+                    segment.data as *const u8,
+                    segment.len as usize,
+                ));
+            }
+        }
+    }
+}
+
 pub fn i2c_write_read(
     fd: c_int,
     address: u16,
@@ -502,33 +657,185 @@ pub fn i2c_write_read(
     write_buffer: &[u8],
     read_buffer: &mut [u8],
 ) -> Result<()> {
-    // 0 length buffers may cause issues
-    if write_buffer.is_empty() || read_buffer.is_empty() {
-        return Ok(());
+    if write_buffer.len() > RDWR_BUFFER_MAX || read_buffer.len() > RDWR_BUFFER_MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "buffer length exceeds RDWR segment limit",
+        ));
     }
 
+    let flags = if addr_10bit { RDWR_FLAG_TEN } else { 0 };
+
     let segment_write = RdwrSegment {
         addr: address,
-        flags: if addr_10bit { RDWR_FLAG_TEN } else { 0 },
+        flags,
         len: write_buffer.len() as u16,
         data: write_buffer.as_ptr() as usize,
     };
 
     let segment_read = RdwrSegment {
         addr: address,
-        flags: if addr_10bit {
-            RDWR_FLAG_RD | RDWR_FLAG_TEN
-        } else {
-            RDWR_FLAG_RD
-        },
+        flags: flags | RDWR_FLAG_RD,
         len: read_buffer.len() as u16,
         data: read_buffer.as_mut_ptr() as usize,
     };
 
-    let mut segments: [RdwrSegment; 2] = [segment_write, segment_read];
+    // 0 length segments may cause issues, so they're left out
+    let mut segments: [RdwrSegment; 2] = match (write_buffer.is_empty(), read_buffer.is_empty()) {
+        (true, true) => return Ok(()),
+        (false, false) => [segment_write, segment_read],
+        (false, true) => [segment_write, segment_write],
+        (true, false) => [segment_read, segment_read],
+    };
+
+    let nmsgs = if write_buffer.is_empty() || read_buffer.is_empty() {
+        1
+    } else {
+        2
+    };
+
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| mock_rdwr_request(bus, &segments[..nmsgs]))
+        .is_some()
+    {
+        return Ok(());
+    }
+
     let mut request = RdwrRequest {
-        segments: &mut segments,
-        nmsgs: 2,
+        segments: &mut segments[..nmsgs],
+        nmsgs: nmsgs as u32,
+    };
+
+    parse_retval!(unsafe { ioctl(fd, REQ_RDWR, &mut request) })?;
+
+    Ok(())
+}
+
+// Incoming or outgoing data for a single RDWR segment
+#[derive(Debug)]
+enum RdwrBuffer<'a> {
+    Read(&'a mut [u8]),
+    Write(&'a [u8]),
+}
+
+/// A single message of an I2C transaction.
+///
+/// A `Message` either fills a buffer with incoming data, or sends the outgoing
+/// data contained in a buffer. Consecutive messages in a transaction are
+/// separated by a repeated START, unless a STOP is requested through
+/// [`with_stop`].
+///
+/// [`with_stop`]: #method.with_stop
+#[derive(Debug)]
+pub struct Message<'a> {
+    buffer: RdwrBuffer<'a>,
+    stop: bool,
+}
+
+impl<'a> Message<'a> {
+    /// Constructs a new `Message` that fills `buffer` with incoming data.
+    pub fn read(buffer: &'a mut [u8]) -> Message<'a> {
+        Message {
+            buffer: RdwrBuffer::Read(buffer),
+            stop: false,
+        }
+    }
+
+    /// Constructs a new `Message` that sends the outgoing data contained in `buffer`.
+    pub fn write(buffer: &'a [u8]) -> Message<'a> {
+        Message {
+            buffer: RdwrBuffer::Write(buffer),
+            stop: false,
+        }
+    }
+
+    /// Sends a STOP condition after this message, instead of a repeated START.
+    ///
+    /// The STOP condition is always sent after the last message of a transaction.
+    pub fn with_stop(mut self) -> Message<'a> {
+        self.stop = true;
+        self
+    }
+
+    /// Returns `true` if this message fills a buffer with incoming data.
+    pub fn is_read(&self) -> bool {
+        matches!(self.buffer, RdwrBuffer::Read(_))
+    }
+
+    /// Returns `true` if a STOP condition is sent after this message.
+    pub fn stop(&self) -> bool {
+        self.stop
+    }
+}
+
+// Transfers multiple segments separated by a repeated START, or a STOP if requested,
+// with a single STOP at the end
+pub fn i2c_transfer(
+    fd: i32,
+    address: u16,
+    addr_10bit: bool,
+    messages: &mut [Message<'_>],
+) -> Result<()> {
+    let flags = if addr_10bit { RDWR_FLAG_TEN } else { 0 };
+
+    let mut segments: Vec<RdwrSegment> = Vec::with_capacity(messages.len());
+    for message in messages.iter_mut() {
+        let (flags, len, data) = match message.buffer {
+            RdwrBuffer::Read(ref mut buffer) => (
+                flags | RDWR_FLAG_RD,
+                buffer.len(),
+                buffer.as_mut_ptr() as usize,
+            ),
+            RdwrBuffer::Write(buffer) => (flags, buffer.len(), buffer.as_ptr() as usize),
+        };
+
+        if len > RDWR_BUFFER_MAX {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "buffer length exceeds RDWR segment limit",
+            ));
+        }
+
+        // 0 length segments are sent as is, so an empty write can be used to check
+        // whether a slave device acknowledges its address
+        segments.push(RdwrSegment {
+            addr: address,
+            flags: if message.stop {
+                flags | RDWR_FLAG_STOP
+            } else {
+                flags
+            },
+            len: len as u16,
+            data,
+        });
+    }
+
+    // The final STOP is always sent, and doesn't require protocol mangling support
+    if let Some(segment) = segments.last_mut() {
+        segment.flags &= !RDWR_FLAG_STOP;
+    } else {
+        return Ok(());
+    }
+
+    if segments.len() > RDWR_MSG_MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "number of segments exceeds RDWR message limit",
+        ));
+    }
+
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| mock_rdwr_request(bus, &segments))
+        .is_some()
+    {
+        return Ok(());
+    }
+
+    let mut request = RdwrRequest {
+        segments: &mut segments[..],
+        nmsgs: segments.len() as u32,
     };
 
     parse_retval!(unsafe { ioctl(fd, REQ_RDWR, &mut request) })?;
@@ -537,37 +844,62 @@ pub fn i2c_write_read(
 }
 
 pub fn set_slave_address(fd: c_int, value: c_ulong) -> Result<()> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| bus.set_slave_address(value as u16))
+        .is_some()
+    {
+        return Ok(());
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_SLAVE, value) })?;
 
     Ok(())
 }
 
 pub fn set_addr_10bit(fd: c_int, value: c_ulong) -> Result<()> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| bus.set_addr_10bit(value != 0))
+        .is_some()
+    {
+        return Ok(());
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_TENBIT, value) })?;
 
     Ok(())
 }
 
 pub fn set_pec(fd: c_int, value: c_ulong) -> Result<()> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES.with(fd, |_| ()).is_some() {
+        return Ok(());
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_PEC, value) })?;
 
     Ok(())
 }
 
 pub fn set_timeout(fd: c_int, value: c_ulong) -> Result<()> {
-    // Timeout is specified in units of 10ms
-    let timeout: c_ulong = if value > 0 && value < 10 {
-        1
-    } else {
-        value / 10
-    };
+    #[cfg(feature = "mock")]
+    if mock::BUSES.with(fd, |_| ()).is_some() {
+        return Ok(());
+    }
 
-    parse_retval!(unsafe { ioctl(fd, REQ_TIMEOUT, timeout) })?;
+    // Timeout is specified in units of 10ms
+    parse_retval!(unsafe { ioctl(fd, REQ_TIMEOUT, value) })?;
 
     Ok(())
 }
 
 pub fn set_retries(fd: c_int, value: c_ulong) -> Result<()> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES.with(fd, |_| ()).is_some() {
+        return Ok(());
+    }
+
     // Number of retries on arbitration loss
     parse_retval!(unsafe { ioctl(fd, REQ_RETRIES, value) })?;
 
@@ -575,6 +907,11 @@ pub fn set_retries(fd: c_int, value: c_ulong) -> Result<()> {
 }
 
 pub fn funcs(fd: c_int) -> Result<Capabilities> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES.with(fd, |_| ()).is_some() {
+        return Ok(Capabilities::new(FUNCS_MOCK));
+    }
+
     let mut funcs: c_ulong = 0;
 
     parse_retval!(unsafe { ioctl(fd, REQ_FUNCS, &mut funcs) })?;
@@ -0,0 +1,104 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+use crate::mock::Registry;
+
+// Number of registers emulated for each slave address
+const REGISTERS: usize = 256;
+
+// Loopback I2C buses, used when the mock feature is enabled and the
+// RPPAL_MOCK environment variable is set.
+pub static BUSES: Registry<Bus> = Registry::new();
+
+// Emulated slave device. The first byte of every write selects a register, and
+// any remaining bytes are stored starting at that register. Reads return the
+// stored bytes starting at the last selected register, so anything written
+// can be read back.
+#[derive(Debug)]
+pub struct Device {
+    registers: [u8; REGISTERS],
+    register: u8,
+}
+
+impl Device {
+    fn new() -> Device {
+        Device {
+            registers: [0u8; REGISTERS],
+            register: 0,
+        }
+    }
+
+    pub fn write(&mut self, buffer: &[u8]) {
+        if let Some((&register, data)) = buffer.split_first() {
+            self.register = register;
+
+            for (offset, &value) in data.iter().enumerate() {
+                self.registers[(register as usize + offset) % REGISTERS] = value;
+            }
+        }
+    }
+
+    pub fn read(&mut self, buffer: &mut [u8]) {
+        for (offset, value) in buffer.iter_mut().enumerate() {
+            *value = self.registers[(self.register as usize + offset) % REGISTERS];
+        }
+    }
+}
+
+// Emulated I2C bus, where every slave address is acknowledged by a Device.
+#[derive(Debug)]
+pub struct Bus {
+    address: u16,
+    addr_10bit: bool,
+    devices: Vec<((u16, bool), Device)>,
+}
+
+impl Bus {
+    fn new() -> Bus {
+        Bus {
+            address: 0,
+            addr_10bit: false,
+            devices: Vec::new(),
+        }
+    }
+
+    pub fn set_slave_address(&mut self, address: u16) {
+        self.address = address;
+    }
+
+    pub fn set_addr_10bit(&mut self, addr_10bit: bool) {
+        self.addr_10bit = addr_10bit;
+    }
+
+    pub fn device(&mut self, address: u16, addr_10bit: bool) -> &mut Device {
+        let key = (address, addr_10bit);
+
+        let index = match self.devices.iter().position(|(k, _)| *k == key) {
+            Some(index) => index,
+            None => {
+                self.devices.push((key, Device::new()));
+                self.devices.len() - 1
+            }
+        };
+
+        &mut self.devices[index].1
+    }
+
+    // Returns the device at the slave address configured through REQ_SLAVE
+    pub fn slave(&mut self) -> &mut Device {
+        self.device(self.address, self.addr_10bit)
+    }
+}
+
+// Opens /dev/null to get a file descriptor for a new loopback bus.
+pub fn open() -> io::Result<File> {
+    let i2cdev = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")?;
+
+    BUSES.insert(i2cdev.as_raw_fd(), Bus::new());
+
+    Ok(i2cdev)
+}
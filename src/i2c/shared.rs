@@ -0,0 +1,169 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::{I2c, Result};
+
+/// Shared I2C bus.
+///
+/// `SharedI2c` wraps an [`I2c`] instance in a mutex, so the bus can be shared between
+/// multiple threads and slave devices. Each slave device is addressed through its own
+/// [`I2cDevice`] handle, retrieved by calling [`acquire`].
+///
+/// `SharedI2c` can be cloned cheaply. All clones refer to the same underlying bus.
+///
+/// [`I2c`]: struct.I2c.html
+/// [`I2cDevice`]: struct.I2cDevice.html
+/// [`acquire`]: #method.acquire
+#[derive(Debug, Clone)]
+pub struct SharedI2c {
+    bus: Arc<Mutex<I2c>>,
+}
+
+impl SharedI2c {
+    /// Constructs a new `SharedI2c`.
+    pub fn new(i2c: I2c) -> SharedI2c {
+        SharedI2c {
+            bus: Arc::new(Mutex::new(i2c)),
+        }
+    }
+
+    /// Returns an [`I2cDevice`] handle for the slave device at `slave_address`.
+    ///
+    /// The slave address isn't validated until the first transaction.
+    ///
+    /// [`I2cDevice`]: struct.I2cDevice.html
+    pub fn acquire(&self, slave_address: u16) -> I2cDevice {
+        I2cDevice {
+            bus: self.bus.clone(),
+            address: slave_address,
+        }
+    }
+}
+
+// A panic while holding the lock doesn't leave I2c in an inconsistent state,
+// because the slave address is set again at the start of each transaction.
+fn lock(bus: &Mutex<I2c>) -> MutexGuard<'_, I2c> {
+    bus.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Slave device on a shared I2C bus.
+///
+/// `I2cDevice` is constructed by calling [`SharedI2c::acquire`]. Each operation locks
+/// the bus, sets the slave address, and releases the bus when the operation completes,
+/// which prevents another thread from addressing a different slave device in between.
+///
+/// Use [`transaction`] to perform multiple operations while holding the lock.
+///
+/// [`SharedI2c::acquire`]: struct.SharedI2c.html#method.acquire
+/// [`transaction`]: #method.transaction
+#[derive(Debug, Clone)]
+pub struct I2cDevice {
+    bus: Arc<Mutex<I2c>>,
+    address: u16,
+}
+
+impl I2cDevice {
+    /// Returns the slave address.
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Locks the bus, sets the slave address, and calls `f` with the underlying [`I2c`].
+    ///
+    /// The bus stays locked until `f` returns. Changing the slave address inside `f`
+    /// doesn't affect other `I2cDevice` handles.
+    ///
+    /// [`I2c`]: struct.I2c.html
+    pub fn transaction<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut I2c) -> Result<T>,
+    {
+        let mut i2c = lock(&self.bus);
+
+        i2c.set_slave_address(self.address)?;
+
+        f(&mut i2c)
+    }
+
+    /// Receives incoming data from the slave device and writes it to `buffer`.
+    ///
+    /// See [`I2c::read`] for more details.
+    ///
+    /// [`I2c::read`]: struct.I2c.html#method.read
+    pub fn read(&self, buffer: &mut [u8]) -> Result<usize> {
+        self.transaction(|i2c| i2c.read(buffer))
+    }
+
+    /// Sends the outgoing data contained in `buffer` to the slave device.
+    ///
+    /// See [`I2c::write`] for more details.
+    ///
+    /// [`I2c::write`]: struct.I2c.html#method.write
+    pub fn write(&self, buffer: &[u8]) -> Result<usize> {
+        self.transaction(|i2c| i2c.write(buffer))
+    }
+
+    /// Sends the outgoing data contained in `write_buffer` to the slave device, and
+    /// then fills `read_buffer` with incoming data.
+    ///
+    /// See [`I2c::write_read`] for more details.
+    ///
+    /// [`I2c::write_read`]: struct.I2c.html#method.write_read
+    pub fn write_read(&self, write_buffer: &[u8], read_buffer: &mut [u8]) -> Result<()> {
+        self.transaction(|i2c| i2c.write_read(write_buffer, read_buffer))
+    }
+
+    /// Sends an 8-bit `command`, and then fills a multi-byte `buffer` with
+    /// incoming data.
+    ///
+    /// See [`I2c::block_read`] for more details.
+    ///
+    /// [`I2c::block_read`]: struct.I2c.html#method.block_read
+    pub fn block_read(&self, command: u8, buffer: &mut [u8]) -> Result<()> {
+        self.transaction(|i2c| i2c.block_read(command, buffer))
+    }
+
+    /// Sends an 8-bit `command` followed by a multi-byte `buffer`.
+    ///
+    /// See [`I2c::block_write`] for more details.
+    ///
+    /// [`I2c::block_write`]: struct.I2c.html#method.block_write
+    pub fn block_write(&self, command: u8, buffer: &[u8]) -> Result<()> {
+        self.transaction(|i2c| i2c.block_write(command, buffer))
+    }
+
+    /// Sends an 8-bit `command`, and receives an 8-bit value.
+    ///
+    /// See [`I2c::smbus_read_byte`] for more details.
+    ///
+    /// [`I2c::smbus_read_byte`]: struct.I2c.html#method.smbus_read_byte
+    pub fn smbus_read_byte(&self, command: u8) -> Result<u8> {
+        self.transaction(|i2c| i2c.smbus_read_byte(command))
+    }
+
+    /// Sends an 8-bit `command` and an 8-bit `value`.
+    ///
+    /// See [`I2c::smbus_write_byte`] for more details.
+    ///
+    /// [`I2c::smbus_write_byte`]: struct.I2c.html#method.smbus_write_byte
+    pub fn smbus_write_byte(&self, command: u8, value: u8) -> Result<()> {
+        self.transaction(|i2c| i2c.smbus_write_byte(command, value))
+    }
+
+    /// Sends an 8-bit `command`, and receives a 16-bit value.
+    ///
+    /// See [`I2c::smbus_read_word`] for more details.
+    ///
+    /// [`I2c::smbus_read_word`]: struct.I2c.html#method.smbus_read_word
+    pub fn smbus_read_word(&self, command: u8) -> Result<u16> {
+        self.transaction(|i2c| i2c.smbus_read_word(command))
+    }
+
+    /// Sends an 8-bit `command` and a 16-bit `value`.
+    ///
+    /// See [`I2c::smbus_write_word`] for more details.
+    ///
+    /// [`I2c::smbus_write_word`]: struct.I2c.html#method.smbus_write_word
+    pub fn smbus_write_word(&self, command: u8, value: u16) -> Result<()> {
+        self.transaction(|i2c| i2c.smbus_write_word(command, value))
+    }
+}
@@ -8,9 +8,20 @@
 //!
 //! RPPAL requires Raspberry Pi OS or any similar, recent, Linux distribution.
 //! Both `gnu` and `musl` libc targets are supported. RPPAL is compatible with the
-//! Raspberry Pi A, A+, B, B+, 2B, 3A+, 3B, 3B+, 4B, 5, CM, CM 3, CM 3+, CM 4, 400,
-//! Zero, Zero W and Zero 2 W. Backwards compatibility for minor revisions isn't
+//! Raspberry Pi A, A+, B, B+, 2B, 3A+, 3B, 3B+, 4B, 5, CM, CM 3, CM 3+, CM 4, CM 5,
+//! 400, 500, Zero, Zero W and Zero 2 W. Backwards compatibility for minor revisions isn't
 //! guaranteed until v1.0.0.
+//!
+//! [`I2c`], [`Spi`] and [`Uart`] implement `AsRawFd`. The file descriptor is borrowed,
+//! and remains owned by the peripheral instance. It can be registered with an external
+//! event loop, or used to issue device-specific `ioctl()` requests that aren't exposed
+//! by RPPAL. Don't close the file descriptor, and keep in mind that changing any settings
+//! or reading and writing data through the file descriptor may interfere with RPPAL's
+//! own calls.
+//!
+//! [`I2c`]: i2c/struct.I2c.html
+//! [`Spi`]: spi/struct.Spi.html
+//! [`Uart`]: uart/struct.Uart.html
 
 // Used by rustdoc to link other crates to rppal's docs
 #![doc(html_root_url = "https://docs.rs/rppal/0.16.1")]
@@ -26,7 +37,10 @@ pub mod gpio;
 ))]
 pub mod hal;
 pub mod i2c;
+#[cfg(feature = "mock")]
+mod mock;
 pub mod pwm;
 pub mod spi;
 pub mod system;
+pub mod time;
 pub mod uart;
@@ -36,3 +36,30 @@ macro_rules! init_array {
         unsafe { mem::transmute::<_, [_; $len]>(data) }
     }};
 }
+
+// Emits a log record when the log feature is enabled, and expands to nothing otherwise
+macro_rules! log_trace {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)+);
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)+);
+    };
+}
+
+// Unlike log_trace and log_debug, the arguments are still evaluated when the log
+// feature is disabled, so values that are only used for logging aren't reported
+// as unused
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::warn!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        let _ = format_args!($($arg)+);
+    };
+}
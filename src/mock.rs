@@ -0,0 +1,76 @@
+// Shared helpers for the in-memory mock backends, which are used when the mock feature
+// is enabled and the RPPAL_MOCK environment variable is set.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::os::unix::io::RawFd;
+use std::sync::{Mutex, MutexGuard, Once};
+
+// Environment variable that enables the mock backends
+const ENV_VAR: &str = "RPPAL_MOCK";
+
+// Returns true if the mock backends are requested through the RPPAL_MOCK environment variable.
+pub fn is_requested() -> bool {
+    std::env::var_os(ENV_VAR).map_or(false, |value| !value.is_empty())
+}
+
+// Emulated devices and the file descriptors they belong to
+type Devices<T> = Mutex<Vec<(RawFd, T)>>;
+
+// Keeps track of the emulated devices that belong to open file descriptors. The
+// I2C and SPI mocks open /dev/null or /dev/zero to get a valid file descriptor,
+// and intercept any ioctl() requests for that descriptor.
+pub struct Registry<T> {
+    once: Once,
+    // Replace this when std::sync::SyncLazy is stabilized. https://github.com/rust-lang/rust/issues/74465
+    devices: UnsafeCell<MaybeUninit<Devices<T>>>,
+}
+
+// devices is only accessed through the Mutex, after it's initialized by once.
+unsafe impl<T: Send> Sync for Registry<T> {}
+
+impl<T> Registry<T> {
+    pub const fn new() -> Registry<T> {
+        Registry {
+            once: Once::new(),
+            devices: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    fn devices(&self) -> MutexGuard<'_, Vec<(RawFd, T)>> {
+        // call_once is thread-safe, guaranteed to be called only once, and memory writes
+        // performed by the closure can be observed by other threads after execution completes.
+        unsafe {
+            self.once.call_once(|| {
+                (*self.devices.get()).write(Mutex::new(Vec::new()));
+            });
+
+            // devices will always be initialized at this point. The emulated devices can't
+            // be left in an inconsistent state, so a poisoned lock is recovered.
+            (*self.devices.get())
+                .assume_init_ref()
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+        }
+    }
+
+    pub fn insert(&self, fd: RawFd, device: T) {
+        let mut devices = self.devices();
+
+        devices.retain(|(device_fd, _)| *device_fd != fd);
+        devices.push((fd, device));
+    }
+
+    pub fn remove(&self, fd: RawFd) {
+        self.devices().retain(|(device_fd, _)| *device_fd != fd);
+    }
+
+    // Calls f with the emulated device that belongs to fd. Returns None if fd
+    // doesn't belong to an emulated device.
+    pub fn with<R>(&self, fd: RawFd, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.devices()
+            .iter_mut()
+            .find(|(device_fd, _)| *device_fd == fd)
+            .map(|(_, device)| f(device))
+    }
+}
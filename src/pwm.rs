@@ -13,6 +13,16 @@
 //! and configuring PWM on other GPIO pins than the default ones can be found in
 //! `/boot/overlays/README`.
 //!
+//! The following GPIO pins on the 40-pin header can be connected to a hardware PWM channel:
+//!
+//! * BCM283x and BCM2711: PWM0 on BCM GPIO 12 (physical pin 32) and BCM GPIO 18 (physical
+//!   pin 12), PWM1 on BCM GPIO 13 (physical pin 33) and BCM GPIO 19 (physical pin 35).
+//! * Raspberry Pi 5: PWM0 channel 0 on BCM GPIO 12, channel 1 on BCM GPIO 13, channel 2 on
+//!   BCM GPIO 14 and 18, and channel 3 on BCM GPIO 15 and 19.
+//!
+//! Software-based PWM can't be enabled on these pins through [`OutputPin::set_pwm`] while
+//! they're configured for their hardware PWM function.
+//!
 //! The Raspberry Pi's analog audio output uses both PWM channels. Playing audio and
 //! simultaneously accessing a PWM channel may cause issues.
 //!
@@ -59,6 +69,7 @@
 //!
 //! [patch]: https://github.com/raspberrypi/linux/issues/1983
 //! [`new`]: struct.Pwm.html#method.new
+//! [`OutputPin::set_pwm`]: ../gpio/struct.OutputPin.html#method.set_pwm
 
 use std::error;
 use std::fmt;
@@ -66,6 +77,7 @@ use std::io;
 use std::result;
 use std::time::Duration;
 
+mod group;
 #[cfg(any(
     feature = "embedded-hal-0",
     feature = "embedded-hal",
@@ -74,8 +86,12 @@ use std::time::Duration;
 mod hal;
 #[cfg(feature = "hal-unproven")]
 mod hal_unproven;
+mod servo;
 mod sysfs;
 
+pub use self::group::PwmGroup;
+pub use self::servo::Servo;
+
 const NANOS_PER_SEC: f64 = 1_000_000_000.0;
 
 /// Errors that can occur when accessing the PWM peripheral.
@@ -83,12 +99,15 @@ const NANOS_PER_SEC: f64 = 1_000_000_000.0;
 pub enum Error {
     /// I/O error.
     Io(io::Error),
+    /// Invalid or out of range value.
+    InvalidValue,
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::InvalidValue => write!(f, "Invalid or out of range value"),
         }
     }
 }
@@ -199,8 +218,9 @@ impl Pwm {
     /// `enabled` enables PWM on the selected channel. If `enabled` is set to `false`,
     /// the channel will remain disabled until [`enable`] is called.
     ///
-    /// This method will fail if `period` is shorter than `pulse_width`.
+    /// Returns [`Error::InvalidValue`] if `period` is shorter than `pulse_width`.
     ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
     /// [`Normal`]: enum.Polarity.html#variant.Normal
     /// [`Inverse`]: enum.Polarity.html#variant.Inverse
     /// [`enable`]: #method.enable
@@ -223,11 +243,7 @@ impl Pwm {
         // "enable" is still set to 1, even though the channel isn't enabled.
         let _ = pwm.disable();
 
-        // Set pulse width to 0 first in case the new period is shorter than the current pulse width
-        let _ = sysfs::set_pulse_width(channel as u8, 0);
-
-        pwm.set_period(period)?;
-        pwm.set_pulse_width(pulse_width)?;
+        pwm.set_period_pulse(period, pulse_width)?;
         pwm.set_polarity(polarity)?;
         if enabled {
             pwm.enable()?;
@@ -295,6 +311,12 @@ impl Pwm {
     }
 
     /// Returns the period.
+    ///
+    /// The value is read back from the `pwm` sysfs interface on each call, rather than
+    /// cached. It reflects the configuration the kernel accepted, which includes any
+    /// changes made outside of `Pwm`. Depending on the PWM clock, the hardware may round
+    /// the period and pulse width to the nearest multiple of its clock period, which the
+    /// sysfs interface doesn't report.
     pub fn period(&self) -> Result<Duration> {
         Ok(Duration::from_nanos(sysfs::period(self.channel as u8)?))
     }
@@ -305,16 +327,17 @@ impl Pwm {
     ///
     /// This method will fail if `period` is shorter than the current pulse width.
     pub fn set_period(&self, period: Duration) -> Result<()> {
-        sysfs::set_period(
-            self.channel as u8,
-            u64::from(period.subsec_nanos())
-                .saturating_add(period.as_secs().saturating_mul(NANOS_PER_SEC as u64)),
-        )?;
+        sysfs::set_period(self.channel as u8, duration_to_nanos(period))?;
 
         Ok(())
     }
 
     /// Returns the pulse width.
+    ///
+    /// Similar to [`period`], the pulse width is read back from the `pwm` sysfs interface
+    /// on each call.
+    ///
+    /// [`period`]: #method.period
     pub fn pulse_width(&self) -> Result<Duration> {
         Ok(Duration::from_nanos(sysfs::pulse_width(
             self.channel as u8,
@@ -328,11 +351,63 @@ impl Pwm {
     ///
     /// This method will fail if `pulse_width` is longer than the current period.
     pub fn set_pulse_width(&self, pulse_width: Duration) -> Result<()> {
-        sysfs::set_pulse_width(
-            self.channel as u8,
-            u64::from(pulse_width.subsec_nanos())
-                .saturating_add(pulse_width.as_secs().saturating_mul(NANOS_PER_SEC as u64)),
-        )?;
+        sysfs::set_pulse_width(self.channel as u8, duration_to_nanos(pulse_width))?;
+
+        Ok(())
+    }
+
+    /// Sets the period and pulse width.
+    ///
+    /// `period` indicates the time it takes for the PWM channel to complete one cycle.
+    ///
+    /// `pulse_width` indicates the amount of time the PWM channel is active during a
+    /// single period.
+    ///
+    /// The sysfs interface rejects a pulse width that's longer than the current period.
+    /// `set_period_pulse` writes both values in an order that ensures the pulse width
+    /// never exceeds the period during the update, even when the new period is shorter
+    /// than the current pulse width.
+    ///
+    /// The `pwm` sysfs interface doesn't offer a combined update, and the kernel applies
+    /// each write immediately, so the PWM signal may briefly use the new period with the
+    /// old pulse width, or vice versa. To keep such intermediate states to a minimum, only
+    /// the values that differ from the current configuration are written. Changing only
+    /// the pulse width of a running channel, for instance to adjust a servo or motor,
+    /// results in a single write, without any intermediate state.
+    ///
+    /// Returns [`Error::InvalidValue`] if `period` is shorter than `pulse_width`.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_period_pulse(&self, period: Duration, pulse_width: Duration) -> Result<()> {
+        if pulse_width > period {
+            return Err(Error::InvalidValue);
+        }
+
+        self.write_period_pulse(duration_to_nanos(period), duration_to_nanos(pulse_width))
+    }
+
+    // Writes the period and pulse width in nanoseconds, skipping any unchanged values,
+    // in an order that keeps the pulse width from exceeding the period
+    fn write_period_pulse(&self, period: u64, pulse_width: u64) -> Result<()> {
+        let current_period = sysfs::period(self.channel as u8)?;
+        let current_pulse_width = sysfs::pulse_width(self.channel as u8)?;
+
+        if period == current_period {
+            if pulse_width != current_pulse_width {
+                sysfs::set_pulse_width(self.channel as u8, pulse_width)?;
+            }
+        } else if period < current_pulse_width {
+            // The new pulse width is shorter than the new period, and therefore also
+            // shorter than the current period, so it's safe to write it first.
+            sysfs::set_pulse_width(self.channel as u8, pulse_width)?;
+            sysfs::set_period(self.channel as u8, period)?;
+        } else {
+            sysfs::set_period(self.channel as u8, period)?;
+
+            if pulse_width != current_pulse_width {
+                sysfs::set_pulse_width(self.channel as u8, pulse_width)?;
+            }
+        }
 
         Ok(())
     }
@@ -340,7 +415,10 @@ impl Pwm {
     /// Returns the frequency.
     ///
     /// `frequency` is a convenience method that calculates the frequency in hertz (Hz)
-    /// based on the configured period.
+    /// based on the configured period. Use [`actual_frequency`] to take the resolution
+    /// of the PWM clock into account.
+    ///
+    /// [`actual_frequency`]: #method.actual_frequency
     pub fn frequency(&self) -> Result<f64> {
         let period = sysfs::period(self.channel as u8)? as f64;
 
@@ -351,6 +429,36 @@ impl Pwm {
         })
     }
 
+    /// Returns the frequency the PWM hardware actually produces.
+    ///
+    /// The PWM peripheral can only generate periods that are a whole number of PWM
+    /// clock cycles, so the configured period is rounded to the nearest multiple of
+    /// the clock period by the driver. Unlike [`frequency`], which is calculated
+    /// based on the configured period, `actual_frequency` applies the same rounding,
+    /// using the PWM clock rate assigned by the `pwm` or `pwm-2chan` device tree
+    /// overlay (100 MHz by default).
+    ///
+    /// If the PWM clock rate can't be determined, `actual_frequency` returns the same
+    /// value as [`frequency`].
+    ///
+    /// [`frequency`]: #method.frequency
+    pub fn actual_frequency(&self) -> Result<f64> {
+        let period = sysfs::period(self.channel as u8)?;
+
+        let clock_rate = match sysfs::clock_rate() {
+            Ok(clock_rate) if clock_rate > 0 => clock_rate as f64,
+            _ => return self.frequency(),
+        };
+
+        let cycles = ((period as f64) * clock_rate / NANOS_PER_SEC).round();
+
+        Ok(if cycles == 0.0 {
+            0.0
+        } else {
+            clock_rate / cycles
+        })
+    }
+
     /// Sets the frequency and duty cycle.
     ///
     /// `set_frequency` is a convenience method that converts `frequency` to a period,
@@ -359,10 +467,13 @@ impl Pwm {
     /// `frequency` is specified in hertz (Hz).
     ///
     /// `duty_cycle` is specified as a floating point value between `0.0` (0%) and `1.0` (100%).
+    ///
+    /// Similar to [`set_period_pulse`], the period and pulse width are written in an order
+    /// that ensures the pulse width never exceeds the period, and any unchanged values are
+    /// skipped, which avoids glitches while the channel is enabled.
+    ///
+    /// [`set_period_pulse`]: #method.set_period_pulse
     pub fn set_frequency(&self, frequency: f64, duty_cycle: f64) -> Result<()> {
-        // Set duty cycle to 0 first in case the new period is shorter than the current duty cycle
-        let _ = sysfs::set_pulse_width(self.channel as u8, 0);
-
         // Convert to nanoseconds
         let period = if frequency == 0.0 {
             0.0
@@ -371,17 +482,14 @@ impl Pwm {
         };
         let pulse_width = period * duty_cycle.clamp(0.0, 1.0);
 
-        sysfs::set_period(self.channel as u8, period as u64)?;
-        sysfs::set_pulse_width(self.channel as u8, pulse_width as u64)?;
-
-        Ok(())
+        self.write_period_pulse(period as u64, pulse_width as u64)
     }
 
     /// Returns the duty cycle.
     ///
     /// `duty_cycle` is a convenience method that calculates the duty cycle as a
     /// floating point value between `0.0` (0%) and `1.0` (100%) based on the configured
-    /// period and pulse width.
+    /// period and pulse width, which are read back from the `pwm` sysfs interface.
     pub fn duty_cycle(&self) -> Result<f64> {
         let period = sysfs::period(self.channel as u8)? as f64;
         let pulse_width = sysfs::pulse_width(self.channel as u8)? as f64;
@@ -418,12 +526,31 @@ impl Pwm {
     /// `polarity` configures the active logic level as either high
     /// ([`Normal`]) or low ([`Inverse`]).
     ///
+    /// The sysfs interface only allows the polarity to be changed while the PWM
+    /// channel is disabled. If the channel is currently enabled, `set_polarity`
+    /// briefly disables it, and enables it again after the polarity has been changed.
+    ///
     /// [`Normal`]: enum.Polarity.html#variant.Normal
     /// [`Inverse`]: enum.Polarity.html#variant.Inverse
     pub fn set_polarity(&self, polarity: Polarity) -> Result<()> {
-        sysfs::set_polarity(self.channel as u8, polarity)?;
+        if sysfs::polarity(self.channel as u8)? == polarity {
+            return Ok(());
+        }
 
-        Ok(())
+        let enabled = self.is_enabled()?;
+        if enabled {
+            self.disable()?;
+        }
+
+        let result = sysfs::set_polarity(self.channel as u8, polarity);
+
+        // Re-enable the channel, even if changing the polarity failed. In that case,
+        // the polarity error is returned instead of any error caused by re-enabling.
+        let reenabled = if enabled { self.enable() } else { Ok(()) };
+
+        result?;
+
+        reenabled
     }
 
     /// Returns `true` if the PWM channel is enabled.
@@ -465,6 +592,11 @@ impl Pwm {
     }
 }
 
+fn duration_to_nanos(duration: Duration) -> u64 {
+    u64::from(duration.subsec_nanos())
+        .saturating_add(duration.as_secs().saturating_mul(NANOS_PER_SEC as u64))
+}
+
 impl Drop for Pwm {
     fn drop(&mut self) {
         if self.reset_on_drop {
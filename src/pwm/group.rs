@@ -0,0 +1,67 @@
+use super::{sysfs, Pwm, Result};
+
+/// Group of PWM channels that are enabled and disabled together.
+///
+/// Enabling multiple [`Pwm`] channels one after the other introduces a delay between the
+/// start of each channel's first period. `PwmGroup` minimizes this delay by opening the
+/// relevant sysfs attributes for all channels in advance, and then writing them back-to-back.
+///
+/// The sysfs interface doesn't expose a way to start multiple channels simultaneously, so some
+/// delay remains. Expect a skew of a few to a few dozen microseconds between channels, depending
+/// on system load. Because the channels are driven by the same clock, the phase offset stays
+/// constant as long as both channels use the same period.
+///
+/// [`Pwm`]: struct.Pwm.html
+#[derive(Debug)]
+pub struct PwmGroup {
+    pwms: Vec<Pwm>,
+}
+
+impl PwmGroup {
+    /// Constructs a new `PwmGroup` containing the specified channels.
+    ///
+    /// Each [`Pwm`] should be configured before it's added to the group. The current
+    /// state of each channel isn't changed.
+    ///
+    /// [`Pwm`]: struct.Pwm.html
+    pub fn new(pwms: Vec<Pwm>) -> PwmGroup {
+        PwmGroup { pwms }
+    }
+
+    /// Returns a slice containing all channels in the group.
+    pub fn pwms(&self) -> &[Pwm] {
+        &self.pwms
+    }
+
+    /// Consumes the `PwmGroup`, and returns the individual channels.
+    pub fn into_inner(self) -> Vec<Pwm> {
+        self.pwms
+    }
+
+    /// Enables all channels in the group, with minimal delay in between.
+    ///
+    /// Any channels that are already enabled are briefly disabled first, so all
+    /// channels start their first period at approximately the same time.
+    pub fn enable_all(&self) -> Result<()> {
+        self.disable_all()?;
+        self.write_all(true)
+    }
+
+    /// Disables all channels in the group, with minimal delay in between.
+    pub fn disable_all(&self) -> Result<()> {
+        self.write_all(false)
+    }
+
+    fn write_all(&self, enabled: bool) -> Result<()> {
+        let mut files = Vec::with_capacity(self.pwms.len());
+        for pwm in &self.pwms {
+            files.push(sysfs::open_enabled(pwm.channel as u8)?);
+        }
+
+        for file in &mut files {
+            sysfs::write_enabled(file, enabled)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use super::{Channel, Error, Polarity, Pwm, Result};
+
+const DEFAULT_PERIOD: Duration = Duration::from_millis(20);
+const DEFAULT_MIN_PULSE_WIDTH: Duration = Duration::from_micros(1000);
+const DEFAULT_MAX_PULSE_WIDTH: Duration = Duration::from_micros(2000);
+const DEFAULT_ANGLE_RANGE: f64 = 180.0;
+
+/// Hobby servo controlled by a hardware PWM channel.
+///
+/// `Servo` converts an angle to a pulse width within a configurable range, and uses
+/// [`Pwm::set_pulse_width`] to update the PWM signal. The angle is specified in degrees,
+/// from `0.0` up to and including the angle range, which corresponds to the minimum and
+/// maximum pulse width respectively.
+///
+/// Most servos expect a pulse every 20 ms, with a pulse width between 1000 µs and 2000 µs
+/// for a 180° angle range, which are the default values used by [`new`]. Servos that use a
+/// non-standard range can be calibrated with [`with_range`] or [`set_pulse_width_range`].
+///
+/// The PWM channel remains disabled until the first call to [`set_angle`] or
+/// [`set_pulse_width`]. The PWM channel is disabled when `Servo` goes out of scope.
+///
+/// [`Pwm::set_pulse_width`]: struct.Pwm.html#method.set_pulse_width
+/// [`new`]: #method.new
+/// [`with_range`]: #method.with_range
+/// [`set_pulse_width_range`]: #method.set_pulse_width_range
+/// [`set_angle`]: #method.set_angle
+/// [`set_pulse_width`]: #method.set_pulse_width
+#[derive(Debug)]
+pub struct Servo {
+    pwm: Pwm,
+    min_pulse_width: Duration,
+    max_pulse_width: Duration,
+    angle_range: f64,
+}
+
+impl Servo {
+    /// Constructs a new `Servo` with a period of 20 ms, a pulse width range of
+    /// 1000 µs to 2000 µs, and an angle range of 180°.
+    pub fn new(channel: Channel) -> Result<Servo> {
+        Servo::with_range(
+            channel,
+            DEFAULT_PERIOD,
+            DEFAULT_MIN_PULSE_WIDTH,
+            DEFAULT_MAX_PULSE_WIDTH,
+            DEFAULT_ANGLE_RANGE,
+        )
+    }
+
+    /// Constructs a new `Servo` using the specified settings.
+    ///
+    /// `period` indicates the time between the start of two consecutive pulses.
+    ///
+    /// `min_pulse_width` and `max_pulse_width` indicate the pulse widths that correspond
+    /// to an angle of `0.0` and `angle_range` degrees respectively.
+    ///
+    /// Returns [`Error::InvalidValue`] if `min_pulse_width` is longer than `max_pulse_width`,
+    /// `max_pulse_width` is longer than `period`, or `angle_range` isn't a positive value.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn with_range(
+        channel: Channel,
+        period: Duration,
+        min_pulse_width: Duration,
+        max_pulse_width: Duration,
+        angle_range: f64,
+    ) -> Result<Servo> {
+        if min_pulse_width > max_pulse_width || max_pulse_width > period || angle_range <= 0.0 {
+            return Err(Error::InvalidValue);
+        }
+
+        let pwm = Pwm::with_period(channel, period, min_pulse_width, Polarity::Normal, false)?;
+
+        Ok(Servo {
+            pwm,
+            min_pulse_width,
+            max_pulse_width,
+            angle_range,
+        })
+    }
+
+    /// Returns the minimum pulse width, which corresponds to an angle of `0.0` degrees.
+    pub fn min_pulse_width(&self) -> Duration {
+        self.min_pulse_width
+    }
+
+    /// Returns the maximum pulse width, which corresponds to the maximum angle.
+    pub fn max_pulse_width(&self) -> Duration {
+        self.max_pulse_width
+    }
+
+    /// Sets the pulse width range, which can be used to calibrate a servo that uses
+    /// non-standard pulse widths.
+    ///
+    /// The current pulse width isn't changed until the next call to [`set_angle`] or
+    /// [`set_pulse_width`].
+    ///
+    /// Returns [`Error::InvalidValue`] if `min_pulse_width` is longer than `max_pulse_width`,
+    /// or `max_pulse_width` is longer than the period.
+    ///
+    /// [`set_angle`]: #method.set_angle
+    /// [`set_pulse_width`]: #method.set_pulse_width
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_pulse_width_range(
+        &mut self,
+        min_pulse_width: Duration,
+        max_pulse_width: Duration,
+    ) -> Result<()> {
+        if min_pulse_width > max_pulse_width || max_pulse_width > self.pwm.period()? {
+            return Err(Error::InvalidValue);
+        }
+
+        self.min_pulse_width = min_pulse_width;
+        self.max_pulse_width = max_pulse_width;
+
+        Ok(())
+    }
+
+    /// Returns the angle range in degrees.
+    pub fn angle_range(&self) -> f64 {
+        self.angle_range
+    }
+
+    /// Sets the angle range in degrees, which corresponds to the maximum pulse width.
+    ///
+    /// Returns [`Error::InvalidValue`] if `angle_range` isn't a positive value.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_angle_range(&mut self, angle_range: f64) -> Result<()> {
+        if angle_range <= 0.0 {
+            return Err(Error::InvalidValue);
+        }
+
+        self.angle_range = angle_range;
+
+        Ok(())
+    }
+
+    /// Returns the current angle in degrees, based on the configured pulse width.
+    pub fn angle(&self) -> Result<f64> {
+        let pulse_width = self.pwm.pulse_width()?;
+        let range = (self.max_pulse_width - self.min_pulse_width).as_secs_f64();
+
+        if range == 0.0 || pulse_width <= self.min_pulse_width {
+            return Ok(0.0);
+        }
+
+        let angle = (pulse_width - self.min_pulse_width).as_secs_f64() / range * self.angle_range;
+
+        Ok(angle.min(self.angle_range))
+    }
+
+    /// Sets the angle in degrees, and enables the PWM channel if it's disabled.
+    ///
+    /// Returns [`Error::InvalidValue`] if `angle` is outside of the range `0.0` to
+    /// [`angle_range`].
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    /// [`angle_range`]: #method.angle_range
+    pub fn set_angle(&self, angle: f64) -> Result<()> {
+        if !(0.0..=self.angle_range).contains(&angle) {
+            return Err(Error::InvalidValue);
+        }
+
+        let range = self.max_pulse_width - self.min_pulse_width;
+
+        self.set_pulse_width(
+            self.min_pulse_width + range.mul_f64(angle / self.angle_range).min(range),
+        )
+    }
+
+    /// Returns the current pulse width.
+    pub fn pulse_width(&self) -> Result<Duration> {
+        self.pwm.pulse_width()
+    }
+
+    /// Sets the pulse width, and enables the PWM channel if it's disabled.
+    ///
+    /// Returns [`Error::InvalidValue`] if `pulse_width` is outside of the configured
+    /// pulse width range.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_pulse_width(&self, pulse_width: Duration) -> Result<()> {
+        if pulse_width < self.min_pulse_width || pulse_width > self.max_pulse_width {
+            return Err(Error::InvalidValue);
+        }
+
+        self.pwm.set_pulse_width(pulse_width)?;
+
+        if !self.pwm.is_enabled()? {
+            self.pwm.enable()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the period.
+    pub fn period(&self) -> Result<Duration> {
+        self.pwm.period()
+    }
+
+    /// Returns `true` if the PWM channel is enabled.
+    pub fn is_enabled(&self) -> Result<bool> {
+        self.pwm.is_enabled()
+    }
+
+    /// Disables the PWM channel.
+    ///
+    /// Most servos stop holding their position when they no longer receive any pulses.
+    /// The PWM channel is enabled again on the next call to [`set_angle`] or
+    /// [`set_pulse_width`].
+    ///
+    /// [`set_angle`]: #method.set_angle
+    /// [`set_pulse_width`]: #method.set_pulse_width
+    pub fn disable(&self) -> Result<()> {
+        self.pwm.disable()
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::ffi::CString;
 use std::fs;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
 use std::io;
 use std::io::Write;
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
@@ -169,6 +169,23 @@ pub fn period(channel: u8) -> Result<u64> {
     }
 }
 
+// Returns the PWM clock rate in hertz (Hz), as assigned by the device tree overlay
+pub fn clock_rate() -> Result<u64> {
+    let rate = fs::read("/sys/class/pwm/pwmchip0/device/of_node/assigned-clock-rates")?;
+
+    // Device tree properties are stored as big-endian 32-bit cells
+    if rate.len() < 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid clock rate property",
+        ));
+    }
+
+    Ok(u64::from(u32::from_be_bytes([
+        rate[0], rate[1], rate[2], rate[3],
+    ])))
+}
+
 pub fn set_period(channel: u8, period: u64) -> Result<()> {
     File::create(format!("/sys/class/pwm/pwmchip0/pwm{}/period", channel))?
         .write_fmt(format_args!("{}", period))?;
@@ -244,3 +261,24 @@ pub fn set_enabled(channel: u8, enabled: bool) -> Result<()> {
 
     Ok(())
 }
+
+// Opens the enable attribute, so multiple channels can be enabled with minimal delay in between
+pub fn open_enabled(channel: u8) -> Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .open(format!("/sys/class/pwm/pwmchip0/pwm{}/enable", channel))
+}
+
+pub fn write_enabled(file: &mut File, enabled: bool) -> Result<()> {
+    file.write_all(if enabled { b"1" } else { b"0" })
+        .map_err(|e| {
+            if e.kind() == io::ErrorKind::InvalidInput {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Make sure you have set either a period or frequency before enabling PWM",
+                )
+            } else {
+                e
+            }
+        })
+}
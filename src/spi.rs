@@ -10,7 +10,10 @@
 //! The Raspberry Pi's GPIO header exposes several SPI buses. SPI0 is available
 //! on all Raspberry Pi models. SPI1 is available on models with a 40-pin
 //! header. SPI2 is only available on the Compute and Compute 3. SPI3 through SPI6
-//! are only available on the Raspberry Pi 4 B, 400 and 5.
+//! are only available on the Raspberry Pi 4 B, 400, Compute Module 4, and Raspberry
+//! Pi 5. Each bus is only available on the fixed set of alternate function pins
+//! listed below. On the Compute Module 4, those pins are only accessible if the
+//! carrier board breaks them out.
 //!
 //! ### SPI0
 //!
@@ -105,16 +108,59 @@
 //! can increase this limit to a maximum of 65536 bytes by appending
 //! `spidev.bufsiz=65536` to the single line of parameters in `/boot/cmdline.txt`.
 //! Remember to reboot the Raspberry Pi afterwards. The current value of bufsiz
-//! can be checked with `cat /sys/module/spidev/parameters/bufsiz`.
+//! can be checked with `cat /sys/module/spidev/parameters/bufsiz`, or by calling
+//! [`Spi::max_transfer_size`].
+//!
+//! [`Spi::write`] and [`Spi::transfer`] automatically split buffers that exceed bufsiz
+//! into multiple transfers, while Slave Select stays active until all data has been
+//! transferred. Other methods return an error for oversized buffers.
+//!
+//! [`Spi::max_transfer_size`]: struct.Spi.html#method.max_transfer_size
+//! [`Spi::write`]: struct.Spi.html#method.write
+//! [`Spi::transfer`]: struct.Spi.html#method.transfer
+//!
+//! ## DMA
+//!
+//! The `spi-bcm2835` driver used for SPI0 and SPI3 through SPI6 automatically hands
+//! transfers of at least 96 bytes to the DMA controller, provided DMA channels are
+//! assigned to the bus in the device tree, which is the default configuration for SPI0.
+//! The calling thread sleeps while the DMA controller streams the data to and from
+//! the SPI FIFOs, and smaller transfers are handled by the CPU. SPI1 and SPI2 don't
+//! support DMA.
+//!
+//! [`Spi::transfer_dma`] can be used to transfer buffers that exceed bufsiz, while
+//! still benefiting from the driver's DMA support.
+//!
+//! ## LoSSI
+//!
+//! Some display controllers use a 9-bit serial protocol, where a data/command bit
+//! precedes each byte. The BCM283x SPI peripheral supports this natively as LoSSI
+//! (Low Speed Serial Interface) mode, but the `spi-bcm2835` driver only accepts 8-bit
+//! words and doesn't expose it. [`Spi::write_lossi`] instead packs each 9-bit word into
+//! a continuous stream of 8-bit words, which is clocked out without gaps while Slave
+//! Select is active, so these displays can be used without a separate D/C pin.
+//!
+//! [`Spi::write_lossi`]: struct.Spi.html#method.write_lossi
+//!
+//! ## Mock bus
+//!
+//! When the `mock` feature is enabled, and the `RPPAL_MOCK` environment variable is
+//! set to a non-empty value, [`Spi::new`] opens an in-memory loopback bus instead of
+//! `/dev/spidevB.S`. The mock bus behaves as if MOSI is connected directly to MISO,
+//! so full-duplex transfers receive the outgoing data, and reads without any
+//! outgoing data receive zero-value bytes. Mode, bits per word and clock speed
+//! settings are stored, and can be read back.
+//!
+//! [`Spi::new`]: struct.Spi.html#method.new
 //!
 //! ## Not supported
 //!
 //! Some features exposed by the generic `spidev` interface aren't fully
 //! supported by the underlying driver or the BCM283x SoC: `SPI_LSB_FIRST` (LSB
-//! first bit order), `SPI_3WIRE` (bidirectional mode), `SPI_LOOP` (loopback mode),
+//! first bit order), `SPI_LOOP` (loopback mode),
 //! `SPI_NO_CS` (no Slave Select), `SPI_READY` (slave ready signal),
 //! `SPI_TX_DUAL`/`SPI_RX_DUAL` (dual SPI), `SPI_TX_QUAD`/`SPI_RX_QUAD` (quad SPI),
-//! and any number of bits per word other than 8.
+//! and any number of bits per word other than 8 on models prior to the Raspberry Pi 5.
 //!
 //! If your slave device requires `SPI_LSB_FIRST`, you can use the
 //! [`reverse_bits`] function instead to reverse the bit order in software.
@@ -124,7 +170,8 @@
 //!
 //! `SPI_NO_CS` can be implemented by connecting the Slave Select pin on your
 //! slave device to any other available GPIO pin on the Pi, and manually
-//! changing it to high and low as needed.
+//! changing it to high and low as needed. [`SoftSlaveSelect`] can handle this
+//! automatically for every transfer.
 //!
 //! [`Ss0`]: enum.SlaveSelect.html
 //! [`Ss1`]: enum.SlaveSelect.html
@@ -132,14 +179,17 @@
 //! [`Mode1`]: enum.Mode.html
 //! [`Mode3`]: enum.Mode.html
 //! [`reverse_bits`]: fn.reverse_bits.html
+//! [`SoftSlaveSelect`]: struct.SoftSlaveSelect.html
+//! [`Spi::transfer_dma`]: struct.Spi.html#method.transfer_dma
 
+use std::cell::Cell;
 use std::error;
 use std::fmt;
-use std::fs::{File, OpenOptions};
+use std::fs::{self, File, OpenOptions};
 use std::io;
 use std::io::{Read, Write};
 use std::marker::PhantomData;
-use std::os::unix::io::AsRawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::result;
 
 #[cfg(any(
@@ -149,9 +199,15 @@ use std::result;
 ))]
 mod hal;
 mod ioctl;
+#[cfg(feature = "mock")]
+mod mock;
 mod segment;
+mod soft_ss;
+mod ws2812;
 
 pub use self::segment::Segment;
+pub use self::soft_ss::SoftSlaveSelect;
+pub use self::ws2812::Ws2812;
 #[cfg(any(
     feature = "embedded-hal-0",
     feature = "embedded-hal",
@@ -159,6 +215,11 @@ pub use self::segment::Segment;
 ))]
 pub use hal::SimpleHalSpiDevice;
 
+const PATH_BUFSIZ: &str = "/sys/module/spidev/parameters/bufsiz";
+const BUFSIZ_DEFAULT: usize = 4096;
+// Transfers shorter than this are handled by the CPU instead of DMA (see spi-bcm2835.c)
+const DMA_MIN_LENGTH: usize = 96;
+
 /// Errors that can occur when accessing the SPI peripheral.
 #[derive(Debug)]
 pub enum Error {
@@ -166,8 +227,9 @@ pub enum Error {
     Io(io::Error),
     /// The specified number of bits per word is not supported.
     ///
-    /// The Raspberry Pi currently only supports 8 bit words. Any other value
-    /// will trigger this error.
+    /// The BCM283x and BCM2711 SoCs only support 8 bit words. The RP1 on the
+    /// Raspberry Pi 5 supports 4 to 32 bits per word. Any other value will
+    /// trigger this error.
     BitsPerWordNotSupported(u8),
     /// The specified bit order is not supported.
     ///
@@ -232,10 +294,37 @@ pub fn reverse_bits(buffer: &mut [u8]) {
     }
 }
 
+// Packs each byte in buffer into a 9-bit LoSSI word preceded by the D/C bit
+fn lossi_pack(command: bool, buffer: &[u8]) -> Vec<u8> {
+    let dc_bit = u16::from(!command) << 8;
+    let mut packed = Vec::with_capacity((buffer.len() * 9 + 7) / 8);
+    let mut bits: u32 = 0;
+    let mut len = 0;
+
+    for &byte in buffer {
+        bits = (bits << 9) | u32::from(dc_bit | u16::from(byte));
+        len += 9;
+
+        while len >= 8 {
+            len -= 8;
+            packed.push((bits >> len) as u8);
+        }
+
+        bits &= (1 << len) - 1;
+    }
+
+    if len > 0 {
+        packed.push((bits << (8 - len)) as u8);
+    }
+
+    packed
+}
+
 /// SPI buses.
 ///
-/// The Raspberry Pi exposes up to five SPI buses, depending on the model and
-/// your `/boot/config.txt` configuration. More information can be found [here].
+/// The Raspberry Pi exposes up to seven SPI buses, depending on the model and
+/// your `/boot/config.txt` configuration. Each bus is accessed through
+/// `/dev/spidevB.S`, where B is the bus number. More information can be found [here].
 ///
 /// [here]: index.html
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -424,6 +513,13 @@ pub struct Spi {
     // Stores the last read value. Used for embedded_hal::spi::FullDuplex.
     #[cfg(any(feature = "embedded-hal-0", feature = "embedded-hal-nb"))]
     last_read: Option<u8>,
+    // Default delay and Slave Select change for read, write and transfer
+    delay: Cell<u16>,
+    ss_change: Cell<bool>,
+    ss_setup_delay: Cell<u16>,
+    // Maximum number of bytes spidev accepts in a single transfer, read once when the
+    // bus is opened
+    bufsiz: usize,
     // The not_sync field is a workaround to force !Sync. Spi isn't safe for
     // Sync because of ioctl() and the underlying drivers. This avoids needing
     // #![feature(optin_builtin_traits)] to manually add impl !Sync for Spi.
@@ -444,17 +540,19 @@ impl Spi {
         // The following options currently aren't supported by spidev in Raspbian Stretch on the Pi:
         //
         // LSB_FIRST - ioctl() returns EINVAL when set
-        // 3WIRE - neither MOSI nor MISO show any outgoing data in half-duplex mode
         // LOOP - ioctl() returns EINVAL when set
         // NO_CS - SS is still set to active (tried both file write() and ioctl())
         // READY - ioctl() returns EINVAL when set
         // TX_DUAL/TX_QUAD/RX_DUAL/RX_QUAD - Not supported by BCM283x
         // bits per word - any value other than 0 or 8 returns EINVAL when set
 
-        let spidev = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(format!("/dev/spidev{}.{}", bus as u8, slave_select as u8))?;
+        let spidev = match Spi::open_mock()? {
+            Some(spidev) => spidev,
+            None => OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(format!("/dev/spidev{}.{}", bus as u8, slave_select as u8))?,
+        };
 
         // Reset all mode flags
         if let Err(e) = ioctl::set_mode32(spidev.as_raw_fd(), mode as u32) {
@@ -469,6 +567,10 @@ impl Spi {
             spidev,
             #[cfg(any(feature = "embedded-hal-0", feature = "embedded-hal-nb"))]
             last_read: None,
+            delay: Cell::new(0),
+            ss_change: Cell::new(false),
+            ss_setup_delay: Cell::new(0),
+            bufsiz: bufsiz(),
             not_sync: PhantomData,
         };
 
@@ -479,7 +581,26 @@ impl Spi {
         Ok(spi)
     }
 
+    // Opens a loopback bus if the mock backend is explicitly requested through the
+    // RPPAL_MOCK environment variable.
+    #[cfg(feature = "mock")]
+    fn open_mock() -> Result<Option<File>> {
+        if !crate::mock::is_requested() {
+            return Ok(None);
+        }
+
+        Ok(Some(mock::open()?))
+    }
+
+    #[cfg(not(feature = "mock"))]
+    fn open_mock() -> Result<Option<File>> {
+        Ok(None)
+    }
+
     /// Gets the bit order.
+    ///
+    /// `bit_order` reads the `SPI_LSB_FIRST` mode bit back from the driver through the
+    /// `SPI_IOC_RD_LSB_FIRST` ioctl, so it reflects the setting that was actually applied.
     pub fn bit_order(&self) -> Result<BitOrder> {
         let mut bit_order: u8 = 0;
         ioctl::lsb_first(self.spidev.as_raw_fd(), &mut bit_order)?;
@@ -492,7 +613,12 @@ impl Spi {
 
     /// Sets the order in which bits are shifted out and in.
     ///
-    /// The Raspberry Pi currently only supports the [`MsbFirst`] bit order. If you
+    /// `set_bit_order` configures the `SPI_LSB_FIRST` mode bit. Drivers that don't support
+    /// the requested bit order return `Err(`[`Error::BitOrderNotSupported`]`)`.
+    ///
+    /// The Raspberry Pi currently only supports the [`MsbFirst`] bit order. Neither the
+    /// SPI controllers on the BCM283x and BCM2711 SoCs nor the RP1 on the Raspberry Pi 5
+    /// can shift out the least-significant bit first in hardware. If you
     /// need the [`LsbFirst`] bit order, you can use the [`reverse_bits`] function
     /// instead to reverse the bit order in software by converting your write
     /// buffer before sending it to the slave device, and your read buffer after
@@ -503,6 +629,7 @@ impl Spi {
     /// [`MsbFirst`]: enum.BitOrder.html
     /// [`LsbFirst`]: enum.BitOrder.html
     /// [`reverse_bits`]: fn.reverse_bits.html
+    /// [`Error::BitOrderNotSupported`]: enum.Error.html#variant.BitOrderNotSupported
     pub fn set_bit_order(&self, bit_order: BitOrder) -> Result<()> {
         match ioctl::set_lsb_first(self.spidev.as_raw_fd(), bit_order as u8) {
             Ok(_) => Ok(()),
@@ -514,6 +641,9 @@ impl Spi {
     }
 
     /// Gets the number of bits per word.
+    ///
+    /// `bits_per_word` reads the value back from the driver through the
+    /// `SPI_IOC_RD_BITS_PER_WORD` ioctl.
     pub fn bits_per_word(&self) -> Result<u8> {
         let mut bits_per_word: u8 = 0;
         ioctl::bits_per_word(self.spidev.as_raw_fd(), &mut bits_per_word)?;
@@ -523,9 +653,22 @@ impl Spi {
 
     /// Sets the number of bits per word.
     ///
-    /// The Raspberry Pi currently only supports 8 bit words.
+    /// The BCM283x and BCM2711 SoCs only support 8 bit words. The RP1 on the
+    /// Raspberry Pi 5 supports 4 to 32 bits per word. Unsupported values return
+    /// `Err(`[`Error::BitsPerWordNotSupported`]`)`. Use [`bits_per_word`] to confirm
+    /// the value that was accepted by the driver.
+    ///
+    /// `spidev` pads each word to the next power-of-two number of bytes, using
+    /// the native (little-endian) byte order. Words of up to 8 bits occupy a single
+    /// byte, 9 to 16 bit words occupy 2 bytes, and 17 to 32 bit words occupy 4 bytes.
+    /// The unused most-significant bits are ignored when sending, and set to 0 when
+    /// receiving. The length of any read or write buffer should be a multiple of the
+    /// padded word size.
     ///
     /// By default, `bits_per_word` is set to 8.
+    ///
+    /// [`Error::BitsPerWordNotSupported`]: enum.Error.html#variant.BitsPerWordNotSupported
+    /// [`bits_per_word`]: #method.bits_per_word
     pub fn set_bits_per_word(&self, bits_per_word: u8) -> Result<()> {
         match ioctl::set_bits_per_word(self.spidev.as_raw_fd(), bits_per_word) {
             Ok(_) => Ok(()),
@@ -547,6 +690,14 @@ impl Spi {
     /// Sets the clock frequency in hertz (Hz).
     ///
     /// The SPI driver will automatically round down to the closest valid frequency.
+    ///
+    /// When slave devices with different maximum clock frequencies share the same bus,
+    /// a custom clock frequency can be set for individual transfers through
+    /// [`Segment::set_clock_speed`] and [`transfer_segments`], which doesn't change the
+    /// clock frequency configured for `Spi`, and is applied atomically with the transfer.
+    ///
+    /// [`Segment::set_clock_speed`]: struct.Segment.html#method.set_clock_speed
+    /// [`transfer_segments`]: #method.transfer_segments
     pub fn set_clock_speed(&self, clock_speed: u32) -> Result<()> {
         match ioctl::set_clock_speed(self.spidev.as_raw_fd(), clock_speed) {
             Ok(_) => Ok(()),
@@ -558,6 +709,12 @@ impl Spi {
     }
 
     /// Gets the SPI mode.
+    ///
+    /// `mode` reads the clock polarity and phase bits back from the driver through
+    /// the `SPI_IOC_RD_MODE` ioctl, so it reflects the mode that was actually applied.
+    /// [`mode_flags`] returns all mode bits.
+    ///
+    /// [`mode_flags`]: #method.mode_flags
     pub fn mode(&self) -> Result<Mode> {
         let mut mode: u8 = 0;
         ioctl::mode(self.spidev.as_raw_fd(), &mut mode)?;
@@ -570,10 +727,31 @@ impl Spi {
         })
     }
 
+    /// Returns the raw `spidev` mode flags.
+    ///
+    /// `mode_flags` reads the 32-bit mode value back from the driver through the
+    /// `SPI_IOC_RD_MODE32` ioctl. Besides the clock polarity and phase (`0x03`), this
+    /// includes flags such as `SPI_CS_HIGH` (`0x04`), `SPI_LSB_FIRST` (`0x08`),
+    /// `SPI_3WIRE` (`0x10`), `SPI_LOOP` (`0x20`) and `SPI_NO_CS` (`0x40`). The flags
+    /// can be compared against the expected configuration to confirm which mode bits
+    /// the driver accepted.
+    pub fn mode_flags(&self) -> Result<u32> {
+        let mut mode: u32 = 0;
+        ioctl::mode32(self.spidev.as_raw_fd(), &mut mode)?;
+
+        Ok(mode)
+    }
+
     /// Sets the SPI mode.
     ///
     /// The SPI mode indicates the serial clock polarity and phase. Some modes
     /// may not be available depending on the SPI bus that's used.
+    ///
+    /// Only the clock polarity and phase bits are changed. Any other mode flags,
+    /// such as the Slave Select polarity configured by [`set_ss_polarity`], are
+    /// left unchanged.
+    ///
+    /// [`set_ss_polarity`]: #method.set_ss_polarity
     pub fn set_mode(&self, mode: Mode) -> Result<()> {
         let mut new_mode: u8 = 0;
         ioctl::mode(self.spidev.as_raw_fd(), &mut new_mode)?;
@@ -591,6 +769,9 @@ impl Spi {
     }
 
     /// Gets the Slave Select polarity.
+    ///
+    /// `ss_polarity` reads the `SPI_CS_HIGH` mode bit back from the driver through the
+    /// `SPI_IOC_RD_MODE` ioctl.
     pub fn ss_polarity(&self) -> Result<Polarity> {
         let mut mode: u8 = 0;
         ioctl::mode(self.spidev.as_raw_fd(), &mut mode)?;
@@ -604,7 +785,17 @@ impl Spi {
 
     /// Sets Slave Select polarity.
     ///
+    /// When set to `ActiveHigh`, the hardware Slave Select pin idles low, and is
+    /// set high during a transfer.
+    ///
+    /// `set_ss_polarity` only changes the Slave Select polarity bit. The clock polarity
+    /// and phase configured by [`set_mode`] are left unchanged, and vice versa, so both
+    /// methods can be called in any order, as long as they're called before the
+    /// transfer that relies on them.
+    ///
     /// By default, the Slave Select polarity is set to `ActiveLow`.
+    ///
+    /// [`set_mode`]: #method.set_mode
     pub fn set_ss_polarity(&self, polarity: Polarity) -> Result<()> {
         let mut new_mode: u8 = 0;
         ioctl::mode(self.spidev.as_raw_fd(), &mut new_mode)?;
@@ -624,6 +815,194 @@ impl Spi {
         }
     }
 
+    /// Returns `true` if 3-wire (bidirectional) mode is enabled.
+    pub fn is_3wire(&self) -> Result<bool> {
+        let mut mode: u8 = 0;
+        ioctl::mode(self.spidev.as_raw_fd(), &mut mode)?;
+
+        Ok((mode & ioctl::MODE_3WIRE) != 0)
+    }
+
+    /// Enables or disables 3-wire (bidirectional) mode.
+    ///
+    /// In 3-wire mode, a single data line is shared for outgoing and incoming data.
+    /// On the Raspberry Pi, MOSI is used as the shared data line. Because
+    /// data can only travel in one direction at a time, full-duplex transfers aren't
+    /// possible. Use [`transfer_half_duplex`] to send data and read the response within
+    /// a single Slave Select assertion.
+    ///
+    /// Only the 3-wire mode bit is changed. Any previously configured mode or
+    /// Slave Select polarity is left unchanged.
+    ///
+    /// By default, 3-wire mode is disabled.
+    ///
+    /// [`transfer_half_duplex`]: #method.transfer_half_duplex
+    pub fn set_3wire(&self, enabled: bool) -> Result<()> {
+        let mut new_mode: u8 = 0;
+        ioctl::mode(self.spidev.as_raw_fd(), &mut new_mode)?;
+
+        if enabled {
+            new_mode |= ioctl::MODE_3WIRE;
+        } else {
+            new_mode &= !ioctl::MODE_3WIRE;
+        }
+
+        ioctl::set_mode(self.spidev.as_raw_fd(), new_mode)?;
+
+        Ok(())
+    }
+
+    /// Returns the delay in microseconds (µs) that's added at the end of each transfer.
+    pub fn delay(&self) -> u16 {
+        self.delay.get()
+    }
+
+    /// Sets a delay in microseconds (µs) that's added at the end of each transfer,
+    /// before the (optional) Slave Select change.
+    ///
+    /// The delay is applied to [`read`], [`write`], [`transfer`], the final part of
+    /// [`transfer_half_duplex`] and [`transfer_dma`]. Segments passed to
+    /// [`transfer_segments`] use their own settings configured through [`Segment::set_delay`].
+    ///
+    /// Because the delay is added before Slave Select is set to inactive, it can be used
+    /// as a Slave Select hold time for slave devices that need extra time between the
+    /// last clock edge and the end of the transaction. A setup time can be configured
+    /// through [`set_ss_setup_delay`].
+    ///
+    /// By default, `delay` is set to `0`.
+    ///
+    /// [`set_ss_setup_delay`]: #method.set_ss_setup_delay
+    /// [`read`]: #method.read
+    /// [`write`]: #method.write
+    /// [`transfer`]: #method.transfer
+    /// [`transfer_half_duplex`]: #method.transfer_half_duplex
+    /// [`transfer_dma`]: #method.transfer_dma
+    /// [`transfer_segments`]: #method.transfer_segments
+    /// [`Segment::set_delay`]: struct.Segment.html#method.set_delay
+    pub fn set_delay(&self, delay: u16) {
+        self.delay.set(delay);
+    }
+
+    /// Returns `true` if Slave Select stays active after each transfer.
+    pub fn ss_change(&self) -> bool {
+        self.ss_change.get()
+    }
+
+    /// Changes Slave Select's behavior at the end of each transfer.
+    ///
+    /// If `ss_change` is set to `true`, Slave Select stays active after a transfer ends,
+    /// until the next transfer starts. This allows multiple back-to-back calls to be
+    /// treated as a single transaction by the slave device. Slave Select is only set
+    /// to inactive after a transfer with `ss_change` set to `false`.
+    ///
+    /// `ss_change` is applied to [`read`], [`write`], [`transfer`], [`transfer_half_duplex`]
+    /// and [`transfer_dma`]. Segments passed to [`transfer_segments`] use their own settings
+    /// configured through [`Segment::set_ss_change`].
+    ///
+    /// By default, `ss_change` is set to `false`.
+    ///
+    /// [`read`]: #method.read
+    /// [`write`]: #method.write
+    /// [`transfer`]: #method.transfer
+    /// [`transfer_half_duplex`]: #method.transfer_half_duplex
+    /// [`transfer_dma`]: #method.transfer_dma
+    /// [`transfer_segments`]: #method.transfer_segments
+    /// [`Segment::set_ss_change`]: struct.Segment.html#method.set_ss_change
+    pub fn set_ss_change(&self, ss_change: bool) {
+        self.ss_change.set(ss_change);
+    }
+
+    /// Returns the delay in microseconds (µs) that's added between setting Slave Select
+    /// to active and the first clock edge of each transfer.
+    pub fn ss_setup_delay(&self) -> u16 {
+        self.ss_setup_delay.get()
+    }
+
+    /// Sets a delay in microseconds (µs) that's added between setting Slave Select to
+    /// active and the first clock edge of each transfer.
+    ///
+    /// Some slave devices need more time after Slave Select is set to active than the
+    /// SPI controller provides, especially at higher clock speeds. `spidev` doesn't
+    /// offer a Slave Select setup time setting, so the delay is inserted as an empty
+    /// segment at the start of each transfer, which keeps Slave Select active while
+    /// the delay elapses. The hold time at the end of each transfer can be configured
+    /// through [`set_delay`].
+    ///
+    /// The delay is applied to [`read`], [`write`], [`transfer`], [`transfer_half_duplex`]
+    /// and the first part of [`transfer_dma`]. Segments passed to [`transfer_segments`]
+    /// can include a setup delay by starting with a segment constructed through
+    /// [`Segment::with_delay`].
+    ///
+    /// By default, `ss_setup_delay` is set to `0`.
+    ///
+    /// [`set_delay`]: #method.set_delay
+    /// [`read`]: #method.read
+    /// [`write`]: #method.write
+    /// [`transfer`]: #method.transfer
+    /// [`transfer_half_duplex`]: #method.transfer_half_duplex
+    /// [`transfer_dma`]: #method.transfer_dma
+    /// [`transfer_segments`]: #method.transfer_segments
+    /// [`Segment::with_delay`]: struct.Segment.html#method.with_delay
+    pub fn set_ss_setup_delay(&self, ss_setup_delay: u16) {
+        self.ss_setup_delay.set(ss_setup_delay);
+    }
+
+    /// Returns the maximum number of bytes `spidev` accepts in a single transfer.
+    ///
+    /// The limit is read from `/sys/module/spidev/parameters/bufsiz` when `Spi` is
+    /// constructed, and defaults to 4096 bytes if the file can't be read. More information
+    /// can be found [here].
+    ///
+    /// [here]: index.html#buffer-size-limits
+    pub fn max_transfer_size(&self) -> usize {
+        self.bufsiz
+    }
+
+    // Applies the default delay and Slave Select change to the last segment of a transfer
+    fn with_settings<'a, 'b>(&self, mut segment: Segment<'a, 'b>) -> Segment<'a, 'b> {
+        segment.set_delay(self.delay.get());
+        segment.set_ss_change(self.ss_change.get());
+
+        segment
+    }
+
+    // Setting ss_change on the last segment of a transfer keeps Slave Select
+    // active until the next transfer, which is used to join the chunks of
+    // transfer_dma into a single transaction
+    fn chunk_settings<'a, 'b>(&self, segment: Segment<'a, 'b>, last: bool) -> Segment<'a, 'b> {
+        if last {
+            self.with_settings(segment)
+        } else {
+            let mut segment = segment;
+            segment.set_ss_change(true);
+
+            segment
+        }
+    }
+
+    // read() and write() on the spidev file don't support any transfer settings
+    fn has_settings(&self) -> bool {
+        self.delay.get() > 0 || self.ss_change.get() || self.ss_setup_delay.get() > 0
+    }
+
+    // Prepends an empty segment that only adds a delay when a Slave Select setup
+    // delay is configured
+    fn transfer_with_setup(&self, segments: &[Segment<'_, '_>]) -> Result<()> {
+        let ss_setup_delay = self.ss_setup_delay.get();
+
+        if ss_setup_delay == 0 {
+            ioctl::transfer(self.spidev.as_raw_fd(), segments)?;
+        } else {
+            let mut with_setup = Vec::with_capacity(segments.len() + 1);
+            with_setup.push(Segment::with_delay(ss_setup_delay));
+            with_setup.extend_from_slice(segments);
+
+            ioctl::transfer(self.spidev.as_raw_fd(), &with_setup)?;
+        }
+
+        Ok(())
+    }
+
     /// Receives incoming data from the slave device and writes it to `buffer`.
     ///
     /// The SPI protocol doesn't indicate how much incoming data is waiting,
@@ -638,6 +1017,13 @@ impl Spi {
     ///
     /// Returns how many bytes were read.
     pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        if self.has_settings() && !buffer.is_empty() {
+            let len = buffer.len();
+            self.transfer_with_setup(&[self.with_settings(Segment::with_read(buffer))])?;
+
+            return Ok(len);
+        }
+
         Ok(self.spidev.read(buffer)?)
     }
 
@@ -648,11 +1034,52 @@ impl Spi {
     /// Slave Select is set to active at the start of the write, and inactive
     /// when the write completes.
     ///
+    /// Buffers that exceed [`max_transfer_size`] are split into multiple transfers.
+    ///
     /// Returns how many bytes were written.
+    ///
+    /// [`max_transfer_size`]: #method.max_transfer_size
     pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        if buffer.len() > self.bufsiz {
+            return self.transfer_dma(buffer, None);
+        }
+
+        if self.has_settings() && !buffer.is_empty() {
+            self.transfer_with_setup(&[self.with_settings(Segment::with_write(buffer))])?;
+
+            return Ok(buffer.len());
+        }
+
         Ok(self.spidev.write(buffer)?)
     }
 
+    /// Sends the outgoing data contained in `buffer` to the slave device as 9-bit
+    /// LoSSI words.
+    ///
+    /// Each byte is preceded by a data/command bit, which is set to 0 if `command` is
+    /// `true`, or 1 if `command` is `false`. The 9-bit words are packed into 8-bit words
+    /// before they're sent, most-significant bit first. If the total number of bits
+    /// isn't a multiple of 8, the last 8-bit word is padded with zeros. Most display
+    /// controllers ignore the incomplete word when Slave Select is set to inactive.
+    ///
+    /// `write_lossi` expects the bit order to be set to [`BitOrder::MsbFirst`] and the
+    /// number of bits per word to be set to 8. The packed data is sent using [`write`],
+    /// and every 8 bytes in `buffer` occupy 9 bytes on the bus.
+    ///
+    /// Returns how many bytes from `buffer` were written.
+    ///
+    /// [`BitOrder::MsbFirst`]: enum.BitOrder.html#variant.MsbFirst
+    /// [`write`]: #method.write
+    pub fn write_lossi(&mut self, command: bool, buffer: &[u8]) -> Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        self.write(&lossi_pack(command, buffer))?;
+
+        Ok(buffer.len())
+    }
+
     /// Sends and receives data at the same time.
     ///
     /// SPI is a full-duplex protocol that shifts out bits to the slave device
@@ -666,15 +1093,68 @@ impl Spi {
     /// Slave Select is set to active at the start of the transfer, and inactive
     /// when the transfer completes.
     ///
+    /// Buffers that exceed [`max_transfer_size`] are split into multiple transfers.
+    ///
     /// Returns how many bytes were transferred.
+    ///
+    /// [`max_transfer_size`]: #method.max_transfer_size
     pub fn transfer(&self, read_buffer: &mut [u8], write_buffer: &[u8]) -> Result<usize> {
-        let segment = Segment::new(read_buffer, write_buffer);
+        if read_buffer.len().min(write_buffer.len()) > self.bufsiz {
+            return self.transfer_dma(write_buffer, Some(read_buffer));
+        }
+
+        let segment = self.with_settings(Segment::new(read_buffer, write_buffer));
 
-        ioctl::transfer(self.spidev.as_raw_fd(), &[segment])?;
+        self.transfer_with_setup(&[segment])?;
 
         Ok(segment.len())
     }
 
+    /// Verifies the transfer path by sending a test pattern, and checking whether it's
+    /// received back unchanged.
+    ///
+    /// `loopback_test` requires an external loopback, with MOSI connected directly to
+    /// MISO. None of the Raspberry Pi's SPI drivers support the `SPI_LOOP` mode bit, which
+    /// would otherwise configure the loopback internally. Any slave devices should be
+    /// disconnected, as Slave Select is asserted for the duration of the test.
+    ///
+    /// The test pattern contains all 256 possible byte values in a single
+    /// full-duplex transfer, using the currently configured clock speed and mode.
+    ///
+    /// Returns `Ok(true)` if every byte was received back unchanged.
+    pub fn loopback_test(&self) -> Result<bool> {
+        let write_buffer: Vec<u8> = (0..=u8::MAX).collect();
+        let mut read_buffer = vec![0u8; write_buffer.len()];
+
+        let len = self.transfer(&mut read_buffer, &write_buffer)?;
+
+        Ok(len == write_buffer.len() && read_buffer == write_buffer)
+    }
+
+    /// Sends the outgoing data contained in `write_buffer`, and then fills
+    /// `read_buffer` with incoming data.
+    ///
+    /// Slave Select is set to active at the start of the write, and stays active
+    /// until the read completes. This allows data to be read back in 3-wire mode,
+    /// once the slave device has switched the shared data line's direction.
+    ///
+    /// If either buffer is empty, only the other operation is performed.
+    pub fn transfer_half_duplex(&self, write_buffer: &[u8], read_buffer: &mut [u8]) -> Result<()> {
+        match (write_buffer.is_empty(), read_buffer.is_empty()) {
+            (true, true) => Ok(()),
+            (false, true) => {
+                self.transfer_with_setup(&[self.with_settings(Segment::with_write(write_buffer))])
+            }
+            (true, false) => {
+                self.transfer_with_setup(&[self.with_settings(Segment::with_read(read_buffer))])
+            }
+            (false, false) => self.transfer_with_setup(&[
+                Segment::with_write(write_buffer),
+                self.with_settings(Segment::with_read(read_buffer)),
+            ]),
+        }
+    }
+
     /// Transfers multiple half-duplex or full-duplex segments.
     ///
     /// `transfer_segments` transfers multiple segments in a single call. Each
@@ -685,6 +1165,9 @@ impl Spi {
     /// By default, Slave Select stays active until all segments have been
     /// transferred. You can change this behavior using [`Segment::set_ss_change`].
     ///
+    /// A single call can transfer up to 511 segments. Passing more segments returns
+    /// an `io::ErrorKind::InvalidInput` error.
+    ///
     /// [`Segment`]: struct.Segment.html
     /// [`Segment::set_ss_change`]: struct.Segment.html#method.set_ss_change
     pub fn transfer_segments(&self, segments: &[Segment<'_, '_>]) -> Result<()> {
@@ -692,12 +1175,115 @@ impl Spi {
 
         Ok(())
     }
+
+    /// Sends the outgoing data contained in `write_buffer`, and optionally stores
+    /// the incoming data in `read_buffer`, using the driver's DMA support for large
+    /// transfers.
+    ///
+    /// `transfer_dma` is intended for large buffers, such as full frames for SPI
+    /// displays. Buffers that exceed the `spidev` bufsiz limit are split into multiple
+    /// transfers, while Slave Select stays active until all data has been transferred.
+    /// Each transfer of at least 96 bytes is handled by the DMA controller if the bus
+    /// supports it, which frees up the CPU while the calling thread waits for the transfer
+    /// to complete. Smaller buffers are transferred in a single call, similar to [`write`]
+    /// and [`transfer`].
+    ///
+    /// If `read_buffer` is specified, `transfer_dma` will only transfer as many bytes as
+    /// the shortest of the two buffers contains.
+    ///
+    /// More information on DMA support can be found [here].
+    ///
+    /// Returns how many bytes were transferred.
+    ///
+    /// [`write`]: #method.write
+    /// [`transfer`]: #method.transfer
+    /// [here]: index.html#dma
+    pub fn transfer_dma(
+        &self,
+        write_buffer: &[u8],
+        read_buffer: Option<&mut [u8]>,
+    ) -> Result<usize> {
+        let fd = self.spidev.as_raw_fd();
+
+        let len = match read_buffer {
+            Some(ref read_buffer) => read_buffer.len().min(write_buffer.len()),
+            None => write_buffer.len(),
+        };
+
+        if len == 0 {
+            return Ok(0);
+        }
+
+        // Small transfers don't benefit from splitting, and aren't handled by DMA
+        let chunk_size = if len < DMA_MIN_LENGTH {
+            len
+        } else {
+            self.bufsiz
+        };
+        let num_chunks = (len + chunk_size - 1) / chunk_size;
+
+        match read_buffer {
+            Some(read_buffer) => {
+                for (index, (read_chunk, write_chunk)) in read_buffer[..len]
+                    .chunks_mut(chunk_size)
+                    .zip(write_buffer[..len].chunks(chunk_size))
+                    .enumerate()
+                {
+                    let segment = Segment::new(read_chunk, write_chunk);
+                    let segment = self.chunk_settings(segment, index + 1 == num_chunks);
+
+                    if index == 0 {
+                        self.transfer_with_setup(&[segment])?;
+                    } else {
+                        ioctl::transfer(fd, &[segment])?;
+                    }
+                }
+            }
+            None => {
+                for (index, write_chunk) in write_buffer.chunks(chunk_size).enumerate() {
+                    let segment = Segment::with_write(write_chunk);
+                    let segment = self.chunk_settings(segment, index + 1 == num_chunks);
+
+                    if index == 0 {
+                        self.transfer_with_setup(&[segment])?;
+                    } else {
+                        ioctl::transfer(fd, &[segment])?;
+                    }
+                }
+            }
+        }
+
+        Ok(len)
+    }
+}
+
+// Returns the maximum number of bytes spidev accepts in a single transfer
+fn bufsiz() -> usize {
+    fs::read_to_string(PATH_BUFSIZ)
+        .ok()
+        .and_then(|bufsiz| bufsiz.trim().parse().ok())
+        .filter(|&bufsiz| bufsiz > 0)
+        .unwrap_or(BUFSIZ_DEFAULT)
 }
 
 // Send is safe for Spi, but we're marked !Send because of the dummy pointer that's
 // needed to force !Sync.
 unsafe impl Send for Spi {}
 
+#[cfg(feature = "mock")]
+impl Drop for Spi {
+    fn drop(&mut self) {
+        mock::BUSES.remove(self.spidev.as_raw_fd());
+    }
+}
+
+/// Returns the raw file descriptor of the underlying `spidev` device.
+impl AsRawFd for Spi {
+    fn as_raw_fd(&self) -> RawFd {
+        self.spidev.as_raw_fd()
+    }
+}
+
 impl fmt::Debug for Spi {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Spi").field("spidev", &self.spidev).finish()
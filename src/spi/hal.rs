@@ -1,6 +1,6 @@
 use std::io;
 
-use super::{super::hal::Delay, Error, Spi};
+use super::{super::hal::Delay, Error, Segment, Spi};
 
 #[cfg(feature = "embedded-hal")]
 impl embedded_hal::spi::ErrorType for Spi {
@@ -27,7 +27,17 @@ impl embedded_hal::spi::SpiBus<u8> for Spi {
     }
 
     fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), Self::Error> {
-        Spi::transfer(self, read, write)?;
+        if read.len() == write.len() {
+            Spi::transfer(self, read, write)?;
+            return Ok(());
+        }
+
+        // The transfer length is the length of the longest buffer. Outgoing data
+        // is padded with zeroes, and any excess incoming data is discarded.
+        let (write_buffer, mut read_buffer) = padded_buffers(read.len(), write);
+        Spi::transfer(self, &mut read_buffer, &write_buffer)?;
+        read.copy_from_slice(&read_buffer[..read.len()]);
+
         Ok(())
     }
 
@@ -41,6 +51,93 @@ impl embedded_hal::spi::SpiBus<u8> for Spi {
     }
 }
 
+// Returns a zero-padded copy of write, and a read buffer with the same length
+#[cfg(feature = "embedded-hal")]
+fn padded_buffers(read_len: usize, write: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let len = read_len.max(write.len());
+
+    let mut write_buffer = write.to_vec();
+    write_buffer.resize(len, 0);
+
+    (write_buffer, vec![0u8; len])
+}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::spi::SpiDevice<u8> for Spi {
+    /// Performs all operations as a single transfer.
+    ///
+    /// The hardware Slave Select pin selected when `Spi` was constructed stays active
+    /// until all operations have been completed.
+    fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        use embedded_hal::spi::Operation;
+
+        // In-place transfers and transfers with unequal buffer lengths need
+        // separate write and read buffers.
+        let mut scratch: Vec<Option<(Vec<u8>, Vec<u8>)>> = operations
+            .iter()
+            .map(|op| match op {
+                Operation::Transfer(read, write) if read.len() != write.len() => {
+                    Some(padded_buffers(read.len(), write))
+                }
+                Operation::TransferInPlace(words) => Some((words.to_vec(), vec![0u8; words.len()])),
+                _ => None,
+            })
+            .collect();
+
+        let mut segments = Vec::with_capacity(operations.len());
+        for (op, scratch) in operations.iter_mut().zip(scratch.iter_mut()) {
+            if let Some((write, read)) = scratch {
+                segments.push(Segment::new(read, write));
+                continue;
+            }
+
+            match op {
+                Operation::Read(read) => segments.push(Segment::with_read(read)),
+                Operation::Write(write) => segments.push(Segment::with_write(write)),
+                Operation::Transfer(read, write) => segments.push(Segment::new(read, write)),
+                Operation::TransferInPlace(_) => (),
+                Operation::DelayNs(ns) => {
+                    // Delays are added as empty segments, which keep Slave Select active
+                    let mut us = (u64::from(*ns) + 999) / 1000;
+                    while us > 0 {
+                        let delay = us.min(u64::from(u16::MAX));
+                        segments.push(Segment::with_settings(
+                            None,
+                            None,
+                            0,
+                            delay as u16,
+                            0,
+                            false,
+                        ));
+                        us -= delay;
+                    }
+                }
+            }
+        }
+
+        self.transfer_segments(&segments)?;
+        drop(segments);
+
+        // Copy the incoming data back to the original buffers
+        for (op, scratch) in operations.iter_mut().zip(scratch.iter()) {
+            if let Some((_, read_buffer)) = scratch {
+                match op {
+                    Operation::Transfer(read, _) => {
+                        read.copy_from_slice(&read_buffer[..read.len()])
+                    }
+                    Operation::TransferInPlace(words) => words.copy_from_slice(read_buffer),
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(feature = "embedded-hal-0")]
 impl embedded_hal_0::blocking::spi::Transfer<u8> for Spi {
     type Error = Error;
@@ -100,7 +197,9 @@ impl embedded_hal_0::spi::FullDuplex<u8> for Spi {
 ///
 /// Slave-select is currently handled at the bus level.
 /// This no-op device implementation can be used to satisfy the trait.
-// TODO: The underlying crate::spi::Spi shall be split up to support proper slave-select handling here.
+///
+/// `Spi` also implements `SpiDevice` directly, which keeps the hardware Slave Select
+/// pin active for the duration of a transaction.
 pub struct SimpleHalSpiDevice<B> {
     bus: B,
 }
@@ -152,8 +251,8 @@ impl<B: embedded_hal::spi::SpiBus<u8>> embedded_hal::spi::SpiDevice<u8> for Simp
                         ))
                     })?;
                 }
-                embedded_hal::spi::Operation::DelayNs(us) => {
-                    embedded_hal::delay::DelayNs::delay_us(&mut Delay::new(), *us);
+                embedded_hal::spi::Operation::DelayNs(ns) => {
+                    embedded_hal::delay::DelayNs::delay_ns(&mut Delay::new(), *ns);
                 }
             }
         }
@@ -6,6 +6,8 @@ use std::result;
 
 use libc::{self, c_int, ioctl};
 
+#[cfg(feature = "mock")]
+use super::mock;
 use super::segment::Segment;
 
 #[cfg(target_env = "gnu")]
@@ -64,46 +66,145 @@ pub const MODE_RX_DUAL: u32 = 0x0400; // Receive on 2 incoming lines
 pub const MODE_RX_QUAD: u32 = 0x0800; // Receive on 4 incoming lines
 
 pub fn mode(fd: c_int, value: &mut u8) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| *value = bus.mode as u8)
+        .is_some()
+    {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_RD_MODE, value) })
 }
 
 pub fn set_mode(fd: c_int, value: u8) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| bus.mode = (bus.mode & !0xFF) | u32::from(value))
+        .is_some()
+    {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_WR_MODE, &value) })
 }
 
 pub fn lsb_first(fd: c_int, value: &mut u8) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| {
+            *value = (bus.mode & u32::from(MODE_LSB_FIRST) > 0) as u8
+        })
+        .is_some()
+    {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_RD_LSB_FIRST, value) })
 }
 
 pub fn set_lsb_first(fd: c_int, value: u8) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| {
+            if value > 0 {
+                bus.mode |= u32::from(MODE_LSB_FIRST);
+            } else {
+                bus.mode &= !u32::from(MODE_LSB_FIRST);
+            }
+        })
+        .is_some()
+    {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_WR_LSB_FIRST, &value) })
 }
 
 pub fn bits_per_word(fd: c_int, value: &mut u8) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| *value = bus.bits_per_word)
+        .is_some()
+    {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_RD_BITS_PER_WORD, value) })
 }
 
 pub fn set_bits_per_word(fd: c_int, value: u8) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| bus.bits_per_word = value)
+        .is_some()
+    {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_WR_BITS_PER_WORD, &value) })
 }
 
 pub fn clock_speed(fd: c_int, value: &mut u32) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| *value = bus.clock_speed)
+        .is_some()
+    {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_RD_MAX_SPEED_HZ, value) })
 }
 
 pub fn set_clock_speed(fd: c_int, value: u32) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |bus| bus.clock_speed = value)
+        .is_some()
+    {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_WR_MAX_SPEED_HZ, &value) })
 }
 
 pub fn mode32(fd: c_int, value: &mut u32) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES.with(fd, |bus| *value = bus.mode).is_some() {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_RD_MODE_32, value) })
 }
 
 pub fn set_mode32(fd: c_int, value: u32) -> Result<i32> {
+    #[cfg(feature = "mock")]
+    if mock::BUSES.with(fd, |bus| bus.mode = value).is_some() {
+        return Ok(0);
+    }
+
     parse_retval!(unsafe { ioctl(fd, REQ_WR_MODE_32, &value) })
 }
 
 pub fn transfer(fd: c_int, segments: &[Segment<'_, '_>]) -> Result<i32> {
+    // The total size of all segments is encoded in the request's 14-bit size field
+    if std::mem::size_of_val(segments) >= (1 << SIZEBITS) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "too many segments in a single transfer",
+        ));
+    }
+
+    // The mock backend emulates a loopback bus, with MOSI connected directly to MISO
+    #[cfg(feature = "mock")]
+    if mock::BUSES
+        .with(fd, |_| segments.iter().for_each(Segment::loopback))
+        .is_some()
+    {
+        return Ok(segments.iter().map(Segment::len).sum::<usize>() as i32);
+    }
+
     parse_retval!(unsafe {
         ioctl(
             fd,
@@ -0,0 +1,42 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+use crate::mock::Registry;
+
+// Loopback SPI buses, used when the mock feature is enabled and the
+// RPPAL_MOCK environment variable is set.
+pub static BUSES: Registry<Bus> = Registry::new();
+
+// Emulated SPI bus with MOSI connected directly to MISO. Mode, bits per word and
+// clock speed are stored as is, the same way spidev does.
+#[derive(Debug)]
+pub struct Bus {
+    pub mode: u32,
+    pub bits_per_word: u8,
+    pub clock_speed: u32,
+}
+
+impl Bus {
+    fn new() -> Bus {
+        Bus {
+            mode: 0,
+            bits_per_word: 8,
+            clock_speed: 0,
+        }
+    }
+}
+
+// Opens /dev/zero to get a file descriptor for a new loopback bus. Plain reads
+// return the zero-value bytes that are sent for every byte read, and plain
+// writes are discarded.
+pub fn open() -> io::Result<File> {
+    let spidev = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/zero")?;
+
+    BUSES.insert(spidev.as_raw_fd(), Bus::new());
+
+    Ok(spidev)
+}
@@ -35,8 +35,10 @@ pub struct Segment<'a, 'b> {
     tx_nbits: u8,
     // Number of incoming lines used for dual/quad SPI. Not supported on the Raspberry Pi. Default = 0.
     rx_nbits: u8,
+    // Delay between words within this segment. Default = 0.
+    word_delay_usecs: u8,
     // Padding. Set to 0 for forward compatibility.
-    pad: u16,
+    pad: u8,
     // Zero-sized variable used to link this struct to the read buffer lifetime.
     read_buffer_lifetime: marker::PhantomData<&'a mut [u8]>,
     // Zero-sized variable used to link this struct to the write buffer lifetime.
@@ -93,6 +95,20 @@ impl<'a, 'b> Segment<'a, 'b> {
         Segment::with_settings(None, Some(buffer), 0, 0, 0, false)
     }
 
+    /// Constructs a new empty `Segment` that only adds a delay in microseconds (µs).
+    ///
+    /// No data is transferred, but Slave Select remains active while the delay elapses.
+    /// When used as the first segment passed to [`Spi::transfer_segments`], it delays the
+    /// first clock edge after Slave Select is set to active, which provides a Slave Select
+    /// setup time for slave devices that need one. Similarly, [`set_delay`] on the last
+    /// segment provides a hold time before Slave Select is set to inactive.
+    ///
+    /// [`Spi::transfer_segments`]: struct.Spi.html#method.transfer_segments
+    /// [`set_delay`]: #method.set_delay
+    pub fn with_delay(delay: u16) -> Segment<'static, 'static> {
+        Segment::with_settings(None, None, 0, delay, 0, false)
+    }
+
     /// Constructs a new `Segment` with the specified settings.
     ///
     /// These settings override the values set for [`Spi`], and are only used
@@ -138,7 +154,7 @@ impl<'a, 'b> Segment<'a, 'b> {
             if (len > buffer.len() as u32) || tx_buf == 0 {
                 len = buffer.len() as u32;
             }
-            buffer.as_ptr() as u64
+            buffer.as_mut_ptr() as u64
         } else {
             0
         };
@@ -153,6 +169,7 @@ impl<'a, 'b> Segment<'a, 'b> {
             cs_change: ss_change as u8,
             tx_nbits: 0,
             rx_nbits: 0,
+            word_delay_usecs: 0,
             pad: 0,
             read_buffer_lifetime: marker::PhantomData,
             write_buffer_lifetime: marker::PhantomData,
@@ -175,6 +192,30 @@ impl<'a, 'b> Segment<'a, 'b> {
         self.len == 0
     }
 
+    // Copies the outgoing data to the read buffer, as if MOSI were connected directly
+    // to MISO. Without a write buffer, the read buffer is filled with the zero-value
+    // bytes that are sent for every byte read.
+    #[cfg(feature = "mock")]
+    pub(crate) fn loopback(&self) {
+        if self.rx_buf == 0 {
+            return;
+        }
+
+        // The lifetimes of the read and write buffers are tied to this segment
+        unsafe {
+            let read_buffer = std::slice::from_raw_parts_mut(self.rx_buf as *mut u8, self.len());
+
+            if self.tx_buf == 0 {
+                read_buffer.fill(0);
+            } else {
+                read_buffer.copy_from_slice(std::slice::from_raw_parts(
+                    self.tx_buf as *const u8,
+                    self.len(),
+                ));
+            }
+        }
+    }
+
     /// Gets the custom clock speed in hertz (Hz) for this segment.
     pub fn clock_speed(&self) -> u32 {
         self.speed_hz
@@ -207,6 +248,24 @@ impl<'a, 'b> Segment<'a, 'b> {
         self.delay_usecs = delay;
     }
 
+    /// Gets the delay in microseconds (µs) between words for this segment.
+    pub fn word_delay(&self) -> u8 {
+        self.word_delay_usecs
+    }
+
+    /// Sets a delay in microseconds (µs) between words for this segment.
+    ///
+    /// `set_word_delay` adds a delay after each word in this segment, except
+    /// for the last word. The delay is handled by the SPI driver. The `spi-bcm2835`
+    /// driver used on the Raspberry Pi doesn't implement word delays, so the delay is
+    /// ignored regardless of the kernel version. Other SPI drivers may apply it on
+    /// kernels that support the `word_delay_usecs` field.
+    ///
+    /// By default, `word_delay` is set to `0`.
+    pub fn set_word_delay(&mut self, word_delay: u8) {
+        self.word_delay_usecs = word_delay;
+    }
+
     /// Gets the number of bits per word for this segment.
     pub fn bits_per_word(&self) -> u8 {
         self.bits_per_word
@@ -255,6 +314,7 @@ impl<'a, 'b> fmt::Debug for Segment<'a, 'b> {
             .field("cs_change", &self.cs_change)
             .field("tx_nbits", &self.tx_nbits)
             .field("rx_nbits", &self.rx_nbits)
+            .field("word_delay_usecs", &self.word_delay_usecs)
             .field("pad", &self.pad)
             .finish()
     }
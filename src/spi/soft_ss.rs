@@ -0,0 +1,184 @@
+use std::thread;
+use std::time::Duration;
+
+use super::{Polarity, Result, Segment, Spi};
+use crate::gpio::OutputPin;
+
+/// SPI bus with a software-controlled Slave Select pin.
+///
+/// `SoftSlaveSelect` wraps an [`Spi`] instance, and uses any available GPIO pin as Slave
+/// Select. This is useful when more slave devices are connected than there are hardware
+/// Slave Select pins available, or when a slave device's Slave Select is wired to a
+/// different pin.
+///
+/// The Slave Select pin is set to active before each [`read`], [`write`], [`transfer`] or
+/// [`transfer_segments`], and stays active until the operation completes. Optional setup
+/// and hold delays are inserted after activating and before deactivating the pin, for
+/// slave devices that need additional time to latch the data.
+///
+/// The underlying [`Spi`] still controls its hardware Slave Select pin during each transfer.
+/// If that pin is connected to a different slave device, or you need it for other purposes,
+/// consider using an overlay without hardware Slave Select pins. For SPI0, this can be
+/// configured by adding `dtoverlay=spi0-0cs` to `/boot/config.txt`.
+///
+/// [`Spi`]: struct.Spi.html
+/// [`read`]: #method.read
+/// [`write`]: #method.write
+/// [`transfer`]: #method.transfer
+/// [`transfer_segments`]: #method.transfer_segments
+#[derive(Debug)]
+pub struct SoftSlaveSelect {
+    spi: Spi,
+    pin: OutputPin,
+    polarity: Polarity,
+    setup_delay: Duration,
+    hold_delay: Duration,
+}
+
+impl SoftSlaveSelect {
+    /// Constructs a new `SoftSlaveSelect`.
+    ///
+    /// `pin` is set to inactive, using the [`ActiveLow`] polarity by default.
+    ///
+    /// [`ActiveLow`]: enum.Polarity.html
+    pub fn new(spi: Spi, mut pin: OutputPin) -> SoftSlaveSelect {
+        pin.set_high();
+
+        SoftSlaveSelect {
+            spi,
+            pin,
+            polarity: Polarity::ActiveLow,
+            setup_delay: Duration::ZERO,
+            hold_delay: Duration::ZERO,
+        }
+    }
+
+    /// Returns a reference to the underlying [`Spi`] instance.
+    ///
+    /// The returned reference can be used to change the bus settings, such as the
+    /// clock speed or mode.
+    ///
+    /// [`Spi`]: struct.Spi.html
+    pub fn spi(&self) -> &Spi {
+        &self.spi
+    }
+
+    /// Consumes the `SoftSlaveSelect`, and returns the underlying [`Spi`] and [`OutputPin`].
+    ///
+    /// [`Spi`]: struct.Spi.html
+    /// [`OutputPin`]: ../gpio/struct.OutputPin.html
+    pub fn into_inner(self) -> (Spi, OutputPin) {
+        (self.spi, self.pin)
+    }
+
+    /// Returns the Slave Select polarity.
+    pub fn ss_polarity(&self) -> Polarity {
+        self.polarity
+    }
+
+    /// Sets the Slave Select polarity, and sets the pin to inactive.
+    ///
+    /// By default, the Slave Select polarity is set to `ActiveLow`.
+    pub fn set_ss_polarity(&mut self, polarity: Polarity) {
+        self.polarity = polarity;
+        self.deactivate();
+    }
+
+    /// Returns the delay between activating Slave Select and the start of a transfer.
+    pub fn setup_delay(&self) -> Duration {
+        self.setup_delay
+    }
+
+    /// Sets the delay between activating Slave Select and the start of a transfer.
+    ///
+    /// By default, `setup_delay` is set to 0.
+    pub fn set_setup_delay(&mut self, setup_delay: Duration) {
+        self.setup_delay = setup_delay;
+    }
+
+    /// Returns the delay between the end of a transfer and deactivating Slave Select.
+    pub fn hold_delay(&self) -> Duration {
+        self.hold_delay
+    }
+
+    /// Sets the delay between the end of a transfer and deactivating Slave Select.
+    ///
+    /// By default, `hold_delay` is set to 0.
+    pub fn set_hold_delay(&mut self, hold_delay: Duration) {
+        self.hold_delay = hold_delay;
+    }
+
+    fn activate(&mut self) {
+        match self.polarity {
+            Polarity::ActiveLow => self.pin.set_low(),
+            Polarity::ActiveHigh => self.pin.set_high(),
+        }
+    }
+
+    fn deactivate(&mut self) {
+        match self.polarity {
+            Polarity::ActiveLow => self.pin.set_high(),
+            Polarity::ActiveHigh => self.pin.set_low(),
+        }
+    }
+
+    // Keeps Slave Select active for the duration of f
+    fn selected<T>(&mut self, f: impl FnOnce(&mut Spi) -> Result<T>) -> Result<T> {
+        self.activate();
+
+        if self.setup_delay > Duration::ZERO {
+            thread::sleep(self.setup_delay);
+        }
+
+        let result = f(&mut self.spi);
+
+        if self.hold_delay > Duration::ZERO {
+            thread::sleep(self.hold_delay);
+        }
+
+        self.deactivate();
+
+        result
+    }
+
+    /// Receives incoming data from the slave device and writes it to `buffer`.
+    ///
+    /// See [`Spi::read`] for more details.
+    ///
+    /// [`Spi::read`]: struct.Spi.html#method.read
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        self.selected(|spi| spi.read(buffer))
+    }
+
+    /// Sends the outgoing data contained in `buffer` to the slave device.
+    ///
+    /// See [`Spi::write`] for more details.
+    ///
+    /// [`Spi::write`]: struct.Spi.html#method.write
+    pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        self.selected(|spi| spi.write(buffer))
+    }
+
+    /// Sends and receives data at the same time.
+    ///
+    /// See [`Spi::transfer`] for more details.
+    ///
+    /// [`Spi::transfer`]: struct.Spi.html#method.transfer
+    pub fn transfer(&mut self, read_buffer: &mut [u8], write_buffer: &[u8]) -> Result<usize> {
+        self.selected(|spi| spi.transfer(read_buffer, write_buffer))
+    }
+
+    /// Transfers multiple half-duplex or full-duplex segments.
+    ///
+    /// The software-controlled Slave Select pin stays active until all segments
+    /// have been transferred. [`Segment::set_ss_change`] only affects the hardware
+    /// Slave Select pin.
+    ///
+    /// See [`Spi::transfer_segments`] for more details.
+    ///
+    /// [`Segment::set_ss_change`]: struct.Segment.html#method.set_ss_change
+    /// [`Spi::transfer_segments`]: struct.Spi.html#method.transfer_segments
+    pub fn transfer_segments(&mut self, segments: &[Segment<'_, '_>]) -> Result<()> {
+        self.selected(|spi| spi.transfer_segments(segments))
+    }
+}
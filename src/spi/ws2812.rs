@@ -0,0 +1,104 @@
+use super::{Mode, Result, Spi};
+
+// Each WS2812 bit is encoded as 3 SPI bits, which results in a bit period of 1.25 µs
+// at 2.4 MHz. A 0 bit is sent as 100 (417 ns high), and a 1 bit as 110 (833 ns high).
+const CLOCK_SPEED: u32 = 2_400_000;
+const BITS_ZERO: u32 = 0b100;
+const BITS_ONE: u32 = 0b110;
+const BYTES_PER_LED: usize = 9;
+// The data line needs to stay low for at least 280 µs (50 µs for older WS2812 LEDs)
+// before the LEDs latch the new colors. 96 bytes take 320 µs at 2.4 MHz, and also
+// ensure each write is handed to the DMA controller, which avoids any gaps in the
+// signal while the CPU refills the SPI FIFO.
+const RESET_BYTES: usize = 96;
+
+/// WS2812 (NeoPixel) addressable LED strip driven through an SPI bus.
+///
+/// `Ws2812` wraps an [`Spi`] instance, and encodes each bit of the LED data as a 3-bit
+/// pattern that's clocked out on the MOSI pin at 2.4 MHz, which produces the 800 kHz
+/// signal WS2812 LEDs expect. Connect the data input of the first LED to MOSI. The SCLK
+/// and Slave Select pins aren't used. WS2812 LEDs powered by 5 V may need a level shifter
+/// to reliably detect the 3.3 V signal.
+///
+/// Each write is followed by a reset period of 320 µs, during which the LEDs latch the
+/// new colors.
+///
+/// Each LED occupies 9 bytes in the SPI transfer. With the default bufsiz of 4096 bytes,
+/// up to 444 LEDs can be updated in a single transfer. Longer strips are split into
+/// multiple transfers, and any delay between them may cause the LEDs to latch early,
+/// so a larger bufsiz is recommended, as described in the [buffer size limits] section.
+///
+/// [`Spi`]: struct.Spi.html
+/// [buffer size limits]: index.html#buffer-size-limits
+#[derive(Debug)]
+pub struct Ws2812 {
+    spi: Spi,
+    buffer: Vec<u8>,
+}
+
+impl Ws2812 {
+    /// Constructs a new `Ws2812`.
+    ///
+    /// `new` changes the clock speed of `spi` to 2.4 MHz, and the mode to [`Mode0`].
+    ///
+    /// [`Mode0`]: enum.Mode.html#variant.Mode0
+    pub fn new(spi: Spi) -> Result<Ws2812> {
+        spi.set_clock_speed(CLOCK_SPEED)?;
+        spi.set_mode(Mode::Mode0)?;
+
+        Ok(Ws2812 {
+            spi,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Returns a reference to the underlying [`Spi`] instance.
+    ///
+    /// [`Spi`]: struct.Spi.html
+    pub fn spi(&self) -> &Spi {
+        &self.spi
+    }
+
+    /// Consumes the `Ws2812`, and returns the underlying [`Spi`].
+    ///
+    /// [`Spi`]: struct.Spi.html
+    pub fn into_inner(self) -> Spi {
+        self.spi
+    }
+
+    /// Sends the specified colors to the LED strip.
+    ///
+    /// Each color is specified as a `(red, green, blue)` tuple, starting with the first
+    /// LED on the strip. Any LEDs beyond the number of specified colors keep their
+    /// previous color.
+    pub fn write(&mut self, colors: &[(u8, u8, u8)]) -> Result<()> {
+        self.buffer.clear();
+        self.buffer
+            .reserve(colors.len() * BYTES_PER_LED + RESET_BYTES);
+
+        for &(red, green, blue) in colors {
+            // WS2812 LEDs expect the color components in GRB order
+            for &component in &[green, blue, red] {
+                let bits = (0..8).rev().fold(0u32, |bits, bit| {
+                    let pattern = if component & (1 << bit) > 0 {
+                        BITS_ONE
+                    } else {
+                        BITS_ZERO
+                    };
+
+                    (bits << 3) | pattern
+                });
+
+                self.buffer
+                    .extend_from_slice(&[(bits >> 16) as u8, (bits >> 8) as u8, bits as u8]);
+            }
+        }
+
+        self.buffer
+            .resize(colors.len() * BYTES_PER_LED + RESET_BYTES, 0);
+
+        self.spi.write(&self.buffer)?;
+
+        Ok(())
+    }
+}
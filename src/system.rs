@@ -1,6 +1,7 @@
 //! Raspberry Pi system-related tools.
 //!
-//! Use [`DeviceInfo`] to identify the Raspberry Pi's model and SoC.
+//! Use [`DeviceInfo`] to identify the Raspberry Pi's model and SoC, and to monitor
+//! the SoC temperature and throttling state.
 //!
 //! [`DeviceInfo`]: struct.DeviceInfo.html
 
@@ -8,7 +9,8 @@ use std::error;
 use std::fmt;
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::result;
 
 // Peripheral base address
@@ -21,6 +23,17 @@ const PERIPHERAL_BASE_RP1: u32 = 0x4000_0000;
 const GPIO_OFFSET: u32 = 0x20_0000;
 const GPIO_OFFSET_RP1: u32 = 0x0d_0000;
 
+const PATH_TEMPERATURE: &str = "/sys/class/thermal/thermal_zone0/temp";
+const PATH_PLATFORM: &str = "/sys/devices/platform";
+
+// Throttling state flags reported by the firmware
+const THROTTLED_UNDER_VOLTAGE: u32 = 0x01;
+const THROTTLED_FREQUENCY_CAPPED: u32 = 0x02;
+const THROTTLED_THROTTLED: u32 = 0x04;
+const THROTTLED_SOFT_TEMPERATURE_LIMIT: u32 = 0x08;
+// Each flag is repeated 16 bits higher if the condition occurred since boot
+const THROTTLED_OCCURRED_SHIFT: u32 = 16;
+
 // Number of GPIO lines
 const GPIO_LINES_BCM283X: u8 = 54;
 const GPIO_LINES_BCM2711: u8 = 58;
@@ -45,18 +58,27 @@ pub enum Error {
     /// doesn't provide any of the common user-accessible system files
     /// that are used to identify the model and SoC.
     UnknownModel,
+    /// I/O error.
+    Io(io::Error),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
             Error::UnknownModel => write!(f, "Unknown Raspberry Pi model"),
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
         }
     }
 }
 
 impl error::Error for Error {}
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
 /// Result type returned from methods that can have `system::Error`s.
 pub type Result<T> = result::Result<T, Error>;
 
@@ -80,11 +102,14 @@ pub enum Model {
     RaspberryPi4B,
     RaspberryPi400,
     RaspberryPi5,
+    RaspberryPi500,
     RaspberryPiComputeModule,
     RaspberryPiComputeModule3,
     RaspberryPiComputeModule3Plus,
     RaspberryPiComputeModule4,
     RaspberryPiComputeModule4S,
+    RaspberryPiComputeModule5,
+    RaspberryPiComputeModule5Lite,
     RaspberryPiZero,
     RaspberryPiZeroW,
     RaspberryPiZero2W,
@@ -105,11 +130,16 @@ impl fmt::Display for Model {
             Model::RaspberryPi4B => write!(f, "Raspberry Pi 4 B"),
             Model::RaspberryPi400 => write!(f, "Raspberry Pi 400"),
             Model::RaspberryPi5 => write!(f, "Raspberry Pi 5"),
+            Model::RaspberryPi500 => write!(f, "Raspberry Pi 500"),
             Model::RaspberryPiComputeModule => write!(f, "Raspberry Pi Compute Module"),
             Model::RaspberryPiComputeModule3 => write!(f, "Raspberry Pi Compute Module 3"),
             Model::RaspberryPiComputeModule3Plus => write!(f, "Raspberry Pi Compute Module 3+"),
             Model::RaspberryPiComputeModule4 => write!(f, "Raspberry Pi Compute Module 4"),
             Model::RaspberryPiComputeModule4S => write!(f, "Raspberry Pi Compute Module 4S"),
+            Model::RaspberryPiComputeModule5 => write!(f, "Raspberry Pi Compute Module 5"),
+            Model::RaspberryPiComputeModule5Lite => {
+                write!(f, "Raspberry Pi Compute Module 5 Lite")
+            }
             Model::RaspberryPiZero => write!(f, "Raspberry Pi Zero"),
             Model::RaspberryPiZeroW => write!(f, "Raspberry Pi Zero W"),
             Model::RaspberryPiZero2W => write!(f, "Raspberry Pi Zero 2 W"),
@@ -208,6 +238,9 @@ fn parse_proc_cpuinfo() -> Result<Model> {
             0x14 => Model::RaspberryPiComputeModule4,
             0x15 => Model::RaspberryPiComputeModule4S,
             0x17 => Model::RaspberryPi5,
+            0x18 => Model::RaspberryPiComputeModule5,
+            0x19 => Model::RaspberryPi500,
+            0x1a => Model::RaspberryPiComputeModule5Lite,
             _ => return Err(Error::UnknownModel),
         }
     } else {
@@ -249,6 +282,8 @@ fn parse_base_compatible() -> Result<Model> {
             "raspberrypi,4-compute-module" => Model::RaspberryPiComputeModule4,
             "raspberrypi,4-compute-module-s" => Model::RaspberryPiComputeModule4S,
             "raspberrypi,5-model-b" => Model::RaspberryPi5,
+            "raspberrypi,500" => Model::RaspberryPi500,
+            "raspberrypi,5-compute-module" => Model::RaspberryPiComputeModule5,
             _ => continue,
         };
 
@@ -312,12 +347,140 @@ fn parse_base_model() -> Result<Model> {
         "Raspberry Pi Compute Module 4" => Model::RaspberryPiComputeModule4,
         "Raspberry Pi Compute Module 4S" => Model::RaspberryPiComputeModule4S,
         "Raspberry Pi 5 Model B" => Model::RaspberryPi5,
+        "Raspberry Pi 500" => Model::RaspberryPi500,
+        "Raspberry Pi Compute Module 5" => Model::RaspberryPiComputeModule5,
+        "Raspberry Pi Compute Module 5 Lite" => Model::RaspberryPiComputeModule5Lite,
         _ => return Err(Error::UnknownModel),
     };
 
     Ok(model)
 }
 
+/// Throttling state reported by the firmware.
+///
+/// `ThrottlingState` is retrieved through [`DeviceInfo::throttling_state`], and decodes
+/// the same bitmask that's returned by `vcgencmd get_throttled`. Each condition is
+/// reported twice: whether it's currently active, and whether it has occurred at any
+/// point since the Raspberry Pi was last booted.
+///
+/// [`DeviceInfo::throttling_state`]: struct.DeviceInfo.html#method.throttling_state
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub struct ThrottlingState {
+    flags: u32,
+}
+
+impl ThrottlingState {
+    fn active(self, flag: u32) -> bool {
+        (self.flags & flag) > 0
+    }
+
+    fn occurred(self, flag: u32) -> bool {
+        (self.flags & (flag << THROTTLED_OCCURRED_SHIFT)) > 0
+    }
+
+    /// Indicates whether the supply voltage is currently below 4.63 V.
+    pub fn under_voltage(self) -> bool {
+        self.active(THROTTLED_UNDER_VOLTAGE)
+    }
+
+    /// Indicates whether the supply voltage has dropped below 4.63 V since boot.
+    pub fn under_voltage_occurred(self) -> bool {
+        self.occurred(THROTTLED_UNDER_VOLTAGE)
+    }
+
+    /// Indicates whether the ARM core frequency is currently capped.
+    pub fn frequency_capped(self) -> bool {
+        self.active(THROTTLED_FREQUENCY_CAPPED)
+    }
+
+    /// Indicates whether the ARM core frequency has been capped since boot.
+    pub fn frequency_capped_occurred(self) -> bool {
+        self.occurred(THROTTLED_FREQUENCY_CAPPED)
+    }
+
+    /// Indicates whether the SoC is currently throttled.
+    pub fn throttled(self) -> bool {
+        self.active(THROTTLED_THROTTLED)
+    }
+
+    /// Indicates whether the SoC has been throttled since boot.
+    pub fn throttled_occurred(self) -> bool {
+        self.occurred(THROTTLED_THROTTLED)
+    }
+
+    /// Indicates whether the soft temperature limit is currently active.
+    pub fn soft_temperature_limit(self) -> bool {
+        self.active(THROTTLED_SOFT_TEMPERATURE_LIMIT)
+    }
+
+    /// Indicates whether the soft temperature limit has been active since boot.
+    pub fn soft_temperature_limit_occurred(self) -> bool {
+        self.occurred(THROTTLED_SOFT_TEMPERATURE_LIMIT)
+    }
+
+    /// Returns the raw bitmask reported by the firmware.
+    pub fn into_inner(self) -> u32 {
+        self.flags
+    }
+}
+
+impl fmt::Debug for ThrottlingState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThrottlingState")
+            .field("under_voltage", &self.under_voltage())
+            .field("under_voltage_occurred", &self.under_voltage_occurred())
+            .field("frequency_capped", &self.frequency_capped())
+            .field(
+                "frequency_capped_occurred",
+                &self.frequency_capped_occurred(),
+            )
+            .field("throttled", &self.throttled())
+            .field("throttled_occurred", &self.throttled_occurred())
+            .field("soft_temperature_limit", &self.soft_temperature_limit())
+            .field(
+                "soft_temperature_limit_occurred",
+                &self.soft_temperature_limit_occurred(),
+            )
+            .finish()
+    }
+}
+
+// Finds the get_throttled attribute exposed by the Raspberry Pi firmware driver. The
+// firmware node is located at soc/soc:firmware on most models, but its path on the
+// Raspberry Pi 5 includes the SoC's bus address.
+fn find_get_throttled() -> Option<PathBuf> {
+    let soc_dirs = fs::read_dir(PATH_PLATFORM).ok()?;
+
+    for soc_dir in soc_dirs.flatten() {
+        let firmware_dirs = match fs::read_dir(soc_dir.path()) {
+            Ok(firmware_dirs) => firmware_dirs,
+            Err(_) => continue,
+        };
+
+        for firmware_dir in firmware_dirs.flatten() {
+            let path = firmware_dir.path().join("get_throttled");
+            if firmware_dir
+                .file_name()
+                .to_string_lossy()
+                .ends_with(":firmware")
+                && path.exists()
+            {
+                return Some(path);
+            }
+        }
+    }
+
+    None
+}
+
+fn parse_hex(path: &Path) -> Result<u32> {
+    let value = fs::read_to_string(path)?;
+    let value = value.trim();
+
+    u32::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map_err(|_| Error::Io(io::Error::from(io::ErrorKind::InvalidData)))
+}
+
 /// Retrieves Raspberry Pi device information.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct DeviceInfo {
@@ -400,7 +563,10 @@ impl DeviceInfo {
                 gpio_lines: GPIO_LINES_BCM2711,
                 gpio_interface: GpioInterface::Bcm,
             }),
-            Model::RaspberryPi5 => Ok(DeviceInfo {
+            Model::RaspberryPi5
+            | Model::RaspberryPi500
+            | Model::RaspberryPiComputeModule5
+            | Model::RaspberryPiComputeModule5Lite => Ok(DeviceInfo {
                 model,
                 soc: SoC::Bcm2712,
                 peripheral_base: PERIPHERAL_BASE_RP1,
@@ -421,13 +587,53 @@ impl DeviceInfo {
         self.soc
     }
 
+    /// Returns the SoC temperature in degrees Celsius (°C).
+    ///
+    /// The temperature is read from `/sys/class/thermal/thermal_zone0/temp`, which
+    /// reports the same value as `vcgencmd measure_temp`.
+    pub fn temperature(&self) -> Result<f32> {
+        fs::read_to_string(PATH_TEMPERATURE)?
+            .trim()
+            .parse::<i32>()
+            .map(|millidegrees| millidegrees as f32 / 1000.0)
+            .map_err(|_| Error::Io(io::Error::from(io::ErrorKind::InvalidData)))
+    }
+
+    /// Returns the throttling state reported by the firmware.
+    ///
+    /// The state is read from the `get_throttled` attribute exposed by the Raspberry Pi
+    /// firmware driver, which contains the same bitmask returned by
+    /// `vcgencmd get_throttled`.
+    ///
+    /// Returns `Err(`[`Error::Io`]`)` with an `io::ErrorKind::NotFound` error if the
+    /// attribute isn't available, which is the case for older kernel versions.
+    ///
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn throttling_state(&self) -> Result<ThrottlingState> {
+        let path = find_get_throttled()
+            .ok_or_else(|| Error::Io(io::Error::from(io::ErrorKind::NotFound)))?;
+
+        Ok(ThrottlingState {
+            flags: parse_hex(&path)?,
+        })
+    }
+
     /// Returns the peripheral base memory address.
-    pub(crate) fn peripheral_base(&self) -> u32 {
+    ///
+    /// On the BCM283x and BCM2711, this is the physical address of the peripherals as seen
+    /// by the ARM cores, which can be used to memory-map peripheral registers through
+    /// `/dev/mem`. The peripheral offsets listed in the BCM2835 ARM Peripherals datasheet
+    /// are relative to bus address `0x7e000000`, which corresponds to this base address.
+    ///
+    /// On the BCM2712, the GPIO header is controlled by the RP1 I/O controller, and the
+    /// returned address is located in RP1's own address space, rather than the ARM's
+    /// physical address space.
+    pub fn peripheral_base(&self) -> u32 {
         self.peripheral_base
     }
 
     /// Returns the offset from the peripheral base memory address for the GPIO section.
-    pub(crate) fn gpio_offset(&self) -> u32 {
+    pub fn gpio_offset(&self) -> u32 {
         self.gpio_offset
     }
 
@@ -0,0 +1,233 @@
+//! Timestamps captured by the monotonic system clock.
+//!
+//! The kernel timestamps each GPIO interrupt trigger event using `CLOCK_MONOTONIC`.
+//! Those timestamps are exposed as [`Instant`]s, which can be compared to each
+//! other to calculate the time that elapsed between two events, or to the current
+//! time retrieved through [`Instant::now`].
+//!
+//! For timing-critical code, such as bit-banging a protocol with strict timing
+//! requirements, [`sleep_busy`] provides delays with a much higher accuracy than
+//! `thread::sleep`, at the cost of keeping the CPU busy.
+//!
+//! [`Instant`]: struct.Instant.html
+//! [`Instant::now`]: struct.Instant.html#method.now
+//! [`sleep_busy`]: fn.sleep_busy.html
+
+use std::hint;
+use std::ops::{Add, Sub};
+use std::time::{Duration, SystemTime};
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// A measurement of the monotonic system clock (`CLOCK_MONOTONIC`).
+///
+/// `Instant` is an opaque type. An `Instant` is only meaningful when compared to another
+/// `Instant` captured by the same clock since the system was last booted.
+///
+/// When the `serde` feature is enabled, `Instant` implements `Serialize` and `Deserialize`,
+/// using the same nanosecond count returned by [`into_inner`].
+///
+/// [`into_inner`]: #method.into_inner
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
+pub struct Instant {
+    nanos: u128,
+}
+
+impl Instant {
+    pub(crate) fn from_nanos(nanos: u128) -> Instant {
+        Instant { nanos }
+    }
+
+    /// Returns an `Instant` corresponding to the current time.
+    ///
+    /// `now` reads the monotonic system clock (`CLOCK_MONOTONIC`), which is the same
+    /// clock the kernel uses to timestamp GPIO interrupt trigger events. The returned
+    /// `Instant` can be compared to the timestamps passed to interrupt callbacks.
+    pub fn now() -> Instant {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+
+        unsafe {
+            libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts);
+        }
+
+        Instant::from_nanos(ts.tv_sec as u128 * NANOS_PER_SEC + ts.tv_nsec as u128)
+    }
+
+    /// Returns the number of nanoseconds elapsed since an unspecified
+    /// point in time, usually the last system boot.
+    pub fn into_inner(self) -> u128 {
+        self.nanos
+    }
+
+    /// Returns the amount of time elapsed from `earlier` to this `Instant`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `earlier` is later than this `Instant`. See [`checked_duration_since`]
+    /// for a version without panic.
+    ///
+    /// [`checked_duration_since`]: #method.checked_duration_since
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier)
+            .expect("supplied instant is later than self")
+    }
+
+    /// Returns the amount of time elapsed from `earlier` to this `Instant`, or `None`
+    /// if `earlier` is later than this `Instant`.
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        self.nanos.checked_sub(earlier.nanos).map(nanos_to_duration)
+    }
+
+    /// Returns the amount of time elapsed from `earlier` to this `Instant`, or zero
+    /// if `earlier` is later than this `Instant`.
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier)
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// Returns the signed number of nanoseconds elapsed from `other` to this `Instant`.
+    ///
+    /// Unlike [`duration_since`], `signed_duration_since` doesn't require `other` to be
+    /// the earlier `Instant`. The result is positive if `other` is earlier than this
+    /// `Instant`, and negative if `other` is later. This can be used to calculate the skew
+    /// between events captured on different pins, when the order in which they occurred
+    /// isn't known in advance.
+    ///
+    /// [`duration_since`]: #method.duration_since
+    pub fn signed_duration_since(&self, other: Instant) -> i128 {
+        if self.nanos >= other.nanos {
+            (self.nanos - other.nanos).min(i128::MAX as u128) as i128
+        } else {
+            -((other.nanos - self.nanos).min(i128::MAX as u128) as i128)
+        }
+    }
+
+    /// Returns the amount of time elapsed since this `Instant` was captured.
+    ///
+    /// Returns zero if this `Instant` is later than the current time.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().saturating_duration_since(*self)
+    }
+
+    /// Converts this `Instant` to the corresponding wall-clock time.
+    ///
+    /// `to_system_time` reads the monotonic system clock (`CLOCK_MONOTONIC`) and the real
+    /// time clock (`CLOCK_REALTIME`) at the time of the call, and applies the offset between
+    /// them to this `Instant`. This can be used to correlate interrupt trigger events with
+    /// timestamps in log files.
+    ///
+    /// The result is an approximation. Unlike the monotonic clock, the real time clock can
+    /// be adjusted at any time, for instance by NTP or a manual time change. Any adjustments
+    /// made between the moment this `Instant` was captured and the call to `to_system_time`
+    /// are included in the offset, which means `Instant`s converted at different times may
+    /// not be consistent with each other. Converting events shortly after they occur keeps
+    /// the error to a minimum.
+    pub fn to_system_time(&self) -> SystemTime {
+        let now = Instant::now();
+        let system_now = SystemTime::now();
+
+        if *self <= now {
+            system_now - now.duration_since(*self)
+        } else {
+            system_now + self.duration_since(now)
+        }
+    }
+
+    /// Returns `Some(t)` where `t` is this `Instant` plus `duration`, or `None` if
+    /// the result can't be represented.
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.nanos
+            .checked_add(duration.as_nanos())
+            .map(Instant::from_nanos)
+    }
+
+    /// Returns `Some(t)` where `t` is this `Instant` minus `duration`, or `None` if
+    /// the result would be earlier than the clock's starting point.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.nanos
+            .checked_sub(duration.as_nanos())
+            .map(Instant::from_nanos)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// Panics if the result can't be represented. See [`checked_add`] for a
+    /// version without panic.
+    ///
+    /// [`checked_add`]: struct.Instant.html#method.checked_add
+    fn add(self, rhs: Duration) -> Instant {
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    ///
+    /// Panics if the result would be earlier than the clock's starting point. See
+    /// [`checked_sub`] for a version without panic.
+    ///
+    /// [`checked_sub`]: struct.Instant.html#method.checked_sub
+    fn sub(self, rhs: Duration) -> Instant {
+        self.checked_sub(rhs)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Instant {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u128(self.nanos)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Instant {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Instant, D::Error> {
+        u128::deserialize(deserializer).map(Instant::from_nanos)
+    }
+}
+
+/// Blocks the current thread for at least the specified duration by busy-waiting.
+///
+/// `sleep_busy` repeatedly reads the monotonic system clock (`CLOCK_MONOTONIC`) until
+/// `duration` has elapsed, without yielding to the scheduler. This avoids the wake-up
+/// latency of `thread::sleep`, which is usually somewhere between 50 µs and several
+/// milliseconds, but keeps a CPU core fully occupied for the entire duration.
+///
+/// `sleep_busy` is only appropriate for very short, timing-critical delays. A userspace
+/// thread can't disable preemption, so the kernel may still interrupt the thread or
+/// schedule another task at any time, which extends the delay. Running the thread with a
+/// real-time scheduling policy and on an isolated CPU core reduces, but doesn't
+/// eliminate, the chance of that occurring.
+pub fn sleep_busy(duration: Duration) {
+    let start = Instant::now();
+
+    while Instant::now().saturating_duration_since(start) < duration {
+        hint::spin_loop();
+    }
+}
+
+fn nanos_to_duration(nanos: u128) -> Duration {
+    Duration::new(
+        (nanos / NANOS_PER_SEC) as u64,
+        (nanos % NANOS_PER_SEC) as u32,
+    )
+}
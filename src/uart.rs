@@ -108,6 +108,12 @@
 //! RTS on BCM GPIO 31 (physical pin 6) and CTS on BCM GPIO 30 (physical pin
 //! 5).
 //!
+//! RTS and CTS are switched to ALT3 for the PL011 (`ttyAMA0`), or ALT5 for
+//! the mini UART (`ttyS0`). Other UART peripherals, such as the additional
+//! PL011 UARTs on the Raspberry Pi 4 and 5, require RTS and CTS to be enabled
+//! through a device tree overlay, for instance by adding
+//! `dtoverlay=uart2,ctsrts` to `/boot/config.txt`.
+//!
 //! The RTS and CTS pins are reset to their original state when [`Uart`] goes
 //! out of scope. Note that `drop` methods aren't called when a process is
 //! abnormally terminated, for instance when a user presses <kbd>Ctrl</kbd> +
@@ -115,6 +121,14 @@
 //! from resetting the pins. You can catch those using crates such as
 //! [`simple_signal`].
 //!
+//! ## RS-485
+//!
+//! RS-485 transceivers need their driver to be enabled while data is
+//! transmitted, and disabled afterwards to release the bus. [`enable_rs485`]
+//! configures [`Uart`] to drive the transceiver's driver enable (DE) input
+//! using any available GPIO pin, which is set high during each write, and
+//! set low once all data has been transmitted.
+//!
 //! ## Troubleshooting
 //!
 //! ### Permission denied
@@ -129,6 +143,7 @@
 //! [`Uart`]: struct.Uart.html
 //! [`new`]: struct.Uart.html#method.new
 //! [`with_path`]: struct.Uart.html#method.with_path
+//! [`enable_rs485`]: struct.Uart.html#method.enable_rs485
 
 use std::error;
 use std::fmt;
@@ -139,12 +154,13 @@ use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::{AsRawFd, RawFd};
 use std::path::Path;
 use std::result;
+use std::thread;
 use std::time::Duration;
 
 use libc::{c_int, O_NOCTTY, O_NONBLOCK};
 use libc::{TIOCM_CAR, TIOCM_CTS, TIOCM_DSR, TIOCM_DTR, TIOCM_RNG, TIOCM_RTS};
 
-use crate::gpio::{self, Gpio, IoPin, Mode};
+use crate::gpio::{self, Gpio, IoPin, Mode, OutputPin};
 use crate::system::{self, DeviceInfo, Model};
 
 #[cfg(any(
@@ -176,6 +192,8 @@ pub enum Error {
     Gpio(gpio::Error),
     /// Invalid or unsupported value.
     InvalidValue,
+    /// Operation timed out.
+    TimedOut,
 }
 
 impl fmt::Display for Error {
@@ -184,6 +202,7 @@ impl fmt::Display for Error {
             Error::Io(ref err) => write!(f, "I/O error: {}", err),
             Error::Gpio(ref err) => write!(f, "GPIO error: {}", err),
             Error::InvalidValue => write!(f, "Invalid or unsupported value"),
+            Error::TimedOut => write!(f, "Operation timed out"),
         }
     }
 }
@@ -384,6 +403,70 @@ impl fmt::Debug for Status {
     }
 }
 
+/// Line error counters.
+///
+/// `LineErrors` contains the number of framing errors, parity errors, overruns and
+/// break conditions detected by the driver since the device was opened. The counters
+/// aren't reset when they're retrieved. Compare the values returned by two calls to
+/// [`Uart::line_errors`] to find out if any errors occurred in between.
+///
+/// The counters only indicate that errors occurred. To identify which incoming bytes
+/// were affected, set the parity check mode to [`ParityCheck::Mark`], which marks bytes
+/// with framing or parity errors, as well as break conditions, with a preceding `255`
+/// and `0` byte.
+///
+/// Support for line error counters is device-dependent.
+///
+/// [`Uart::line_errors`]: struct.Uart.html#method.line_errors
+/// [`ParityCheck::Mark`]: enum.ParityCheck.html#variant.Mark
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct LineErrors {
+    frame: u32,
+    parity: u32,
+    overrun: u32,
+    brk: u32,
+    buffer_overrun: u32,
+}
+
+impl LineErrors {
+    /// Returns the number of framing errors.
+    ///
+    /// A framing error occurs when a stop bit isn't detected at the expected
+    /// position, which usually indicates a baud rate mismatch or line noise.
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    /// Returns the number of parity errors.
+    pub fn parity(&self) -> u32 {
+        self.parity
+    }
+
+    /// Returns the number of hardware overruns.
+    ///
+    /// An overrun occurs when incoming data is received faster than the driver
+    /// can move it from the UART's receive FIFO to the input queue.
+    pub fn overrun(&self) -> u32 {
+        self.overrun
+    }
+
+    /// Returns the number of break conditions.
+    ///
+    /// A break condition occurs when the RX line is held low for longer than
+    /// the duration of a single character.
+    pub fn brk(&self) -> u32 {
+        self.brk
+    }
+
+    /// Returns the number of input queue overruns.
+    ///
+    /// An input queue overrun occurs when incoming data is discarded, because
+    /// the input queue is full.
+    pub fn buffer_overrun(&self) -> u32 {
+        self.buffer_overrun
+    }
+}
+
 #[derive(Debug)]
 struct UartInner {
     device: File,
@@ -392,6 +475,7 @@ struct UartInner {
     rtscts_pins: Option<(IoPin, IoPin)>,
     blocking_read: bool,
     blocking_write: bool,
+    read_timeout: Option<Duration>,
     baud_rate: u32,
     parity: Parity,
     parity_check: ParityCheck,
@@ -399,6 +483,14 @@ struct UartInner {
     stop_bits: u8,
     software_flow_control: bool,
     hardware_flow_control: bool,
+    rs485: Option<Rs485>,
+}
+
+#[derive(Debug)]
+struct Rs485 {
+    pin: OutputPin,
+    delay_before_send: Duration,
+    delay_after_send: Duration,
 }
 
 /// Provides access to the Raspberry Pi's UART peripherals and any USB to
@@ -408,9 +500,15 @@ struct UartInner {
 /// implementations for `Uart` can be enabled by specifying the optional `hal` feature in the
 /// dependency declaration for the `rppal` crate.
 ///
+/// `Uart` also implements `std::io::Read` and `std::io::Write`, which allows it to be
+/// wrapped in a `std::io::BufReader` to read newline-terminated lines through the
+/// standard library's `BufRead` methods. More information can be found in the
+/// documentation for the [`Read`] implementation.
+///
 /// [`serial::Read`]: ../../embedded_hal/serial/trait.Read.html
 /// [`serial::Write`]: ../../embedded_hal/serial/trait.Write.html
 /// [`blocking::serial::Write`]: ../../embedded_hal/blocking/serial/trait.Write.html
+/// [`Read`]: #impl-Read-for-Uart
 #[derive(Debug)]
 pub struct Uart {
     inner: UartInner,
@@ -488,6 +586,7 @@ impl Uart {
         termios::set_hardware_flow_control(fd, false)?;
 
         termios::set_line_speed(fd, baud_rate)?;
+        let baud_rate = termios::line_speed(fd)?;
         termios::set_parity(fd, parity)?;
         termios::set_data_bits(fd, data_bits)?;
         termios::set_stop_bits(fd, stop_bits)?;
@@ -506,6 +605,7 @@ impl Uart {
                 rtscts_pins: None,
                 blocking_read: false,
                 blocking_write: false,
+                read_timeout: None,
                 baud_rate,
                 parity,
                 parity_check: ParityCheck::None,
@@ -513,34 +613,75 @@ impl Uart {
                 stop_bits,
                 software_flow_control: false,
                 hardware_flow_control: false,
+                rs485: None,
             },
         })
     }
 
+    /// Constructs a new `Uart` connected to the serial character device specified by
+    /// `path`, configured for raw binary data transfers.
+    ///
+    /// `new_raw` calls [`with_path`] with no parity, 8 data bits and 1 stop bit (8N1),
+    /// which disables canonical mode, local echo, special input and output processing,
+    /// and flow control. Additionally, [`read`] is configured as a blocking read that waits
+    /// until at least one byte is available, which is equivalent to calling
+    /// [`set_read_mode(1, Duration::ZERO)`].
+    ///
+    /// This matches the configuration most binary sensors and modules expect. Any of
+    /// the settings can still be changed after the `Uart` is constructed.
+    ///
+    /// [`with_path`]: #method.with_path
+    /// [`read`]: #method.read
+    /// [`set_read_mode(1, Duration::ZERO)`]: #method.set_read_mode
+    pub fn new_raw<P: AsRef<Path>>(path: P, baud_rate: u32) -> Result<Uart> {
+        let mut uart = Self::with_path(path, baud_rate, Parity::None, 8, 1)?;
+        uart.set_read_mode(1, Duration::ZERO)?;
+
+        Ok(uart)
+    }
+
     /// Returns the line speed in baud (Bd).
+    ///
+    /// The returned value is the line speed reported by the UART driver, which
+    /// may differ from the value requested through [`new`] or [`set_baud_rate`].
+    ///
+    /// [`new`]: #method.new
+    /// [`set_baud_rate`]: #method.set_baud_rate
     pub fn baud_rate(&self) -> u32 {
         self.inner.baud_rate
     }
 
-    /// Sets the line speed in baud (Bd).
+    /// Sets the line speed in baud (Bd), and returns the line speed that was
+    /// actually configured.
     ///
     /// On the Raspberry Pi, baud rate is equivalent to bit rate in bits per
     /// second (bit/s).
     ///
-    /// Accepted values:
+    /// Standard values are configured using the legacy termios speed constants:
     /// `0`, `50`, `75`, `110`, `134`, `150`, `200`, `300`, `600`, `1_200`,
     /// `1_800`, `2_400`, `4_800`, `9_600`, `19_200`, `38_400`, `57_600`,
     /// `115_200`, `230_400`, `460_800`, `500_000`, `576_000`, `921_600`,
     /// `1_000_000`, `1_152_000`, `1_500_000`, `2_000_000`, `2_500_000`,
     /// `3_000_000`, `3_500_000`, `4_000_000`.
     ///
-    /// Support for some values may be device-dependent.
-    pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+    /// Any other value, such as `250_000`, is configured as a custom line speed
+    /// through `termios2`. Because the UART's clock divider can't reach every
+    /// value exactly, the driver may round the requested line speed to the
+    /// nearest supported value, or reject it with [`Error::InvalidValue`] if
+    /// it's out of range. The returned value is the line speed reported by the
+    /// driver after the change.
+    ///
+    /// The maximum line speed depends on the UART's reference clock. The mini
+    /// UART's clock is also tied to the VPU core clock, which affects the line
+    /// speed when the core clock frequency changes.
+    ///
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
+    pub fn set_baud_rate(&mut self, baud_rate: u32) -> Result<u32> {
         termios::set_line_speed(self.inner.fd, baud_rate)?;
 
-        self.inner.baud_rate = baud_rate;
+        self.inner.baud_rate = termios::line_speed(self.inner.fd)?;
 
-        Ok(())
+        Ok(self.inner.baud_rate)
     }
 
     /// Returns the parity bit mode.
@@ -626,6 +767,41 @@ impl Uart {
         Ok(Status { tiocm })
     }
 
+    /// Blocks until CTS, DSR, DCD or RI changes, and returns the new status of the
+    /// control signals.
+    ///
+    /// RI is only reported on the trailing edge, when the signal changes from active to
+    /// inactive. Changes that occur in between the last call to `wait_for_status_change`
+    /// and the next one aren't reported, so [`status`] should be checked first when
+    /// waiting for a specific state.
+    ///
+    /// Support for waiting on control signal changes is device-dependent. The Raspberry
+    /// Pi's UART peripherals only support CTS, while DSR, DCD and RI may be available on
+    /// some USB to serial adapters.
+    ///
+    /// [`status`]: #method.status
+    pub fn wait_for_status_change(&self) -> Result<Status> {
+        let tiocm = termios::wait_for_status_change(self.inner.fd)?;
+
+        Ok(Status { tiocm })
+    }
+
+    /// Returns the number of framing errors, parity errors, overruns and break
+    /// conditions detected since the device was opened.
+    ///
+    /// Support for line error counters is device-dependent.
+    pub fn line_errors(&self) -> Result<LineErrors> {
+        let counters = termios::line_errors(self.inner.fd)?;
+
+        Ok(LineErrors {
+            frame: counters.frame as u32,
+            parity: counters.parity as u32,
+            overrun: counters.overrun as u32,
+            brk: counters.brk as u32,
+            buffer_overrun: counters.buf_overrun as u32,
+        })
+    }
+
     /// Sets DTR to active (`true`) or inactive (`false`).
     ///
     /// DTR is not supported by the Raspberry Pi's UART peripherals,
@@ -701,12 +877,15 @@ impl Uart {
     ///
     /// By default, hardware flow control is disabled.
     ///
-    /// Support for RTS/CTS hardware flow control is device-dependent. You can
-    /// manually implement RTS/CTS using [`cts`], [`send_stop`] and
-    /// [`send_start`], or by disabling hardware flow control and configuring
-    /// an [`OutputPin`] for RTS and an [`InputPin`] for CTS.
+    /// Support for RTS/CTS hardware flow control is device-dependent. If the
+    /// driver doesn't apply the change, the RTS and CTS pins are reset, and
+    /// [`Error::InvalidValue`] is returned. You can manually implement RTS/CTS
+    /// using [`cts`], [`send_stop`] and [`send_start`], or by disabling
+    /// hardware flow control and configuring an [`OutputPin`] for RTS and an
+    /// [`InputPin`] for CTS.
     ///
     /// [here]: index.html#hardware-flow-control
+    /// [`Error::InvalidValue`]: enum.Error.html#variant.InvalidValue
     /// [`cts`]: struct.Status.html#method.cts
     /// [`send_start`]: #method.send_start
     /// [`send_stop`]: #method.send_stop
@@ -739,6 +918,15 @@ impl Uart {
 
         termios::set_hardware_flow_control(self.inner.fd, hardware_flow_control)?;
 
+        // Drivers that don't support RTS/CTS silently clear CRTSCTS, so make
+        // sure the change was actually applied.
+        if hardware_flow_control && !termios::hardware_flow_control(self.inner.fd)? {
+            self.inner.rtscts_pins = None;
+            self.inner.hardware_flow_control = false;
+
+            return Err(Error::InvalidValue);
+        }
+
         self.inner.hardware_flow_control = hardware_flow_control;
 
         Ok(())
@@ -782,6 +970,56 @@ impl Uart {
         Ok(())
     }
 
+    /// Returns `true` if RS-485 direction control is enabled.
+    pub fn is_rs485_enabled(&self) -> bool {
+        self.inner.rs485.is_some()
+    }
+
+    /// Enables RS-485 direction control using the specified pin.
+    ///
+    /// RS-485 transceivers require their driver enable (DE) input to be set high while
+    /// data is transmitted, and low to release the bus, so the external device can
+    /// respond. When RS-485 direction control is enabled, [`write`] sets `pin` high, waits
+    /// for `delay_before_send`, and copies the data to the output queue. It then blocks
+    /// until all data has been transmitted, waits for `delay_after_send`, and sets `pin`
+    /// low again.
+    ///
+    /// [`write`] relies on [`drain`] to determine when the last byte has been transmitted.
+    /// Depending on the driver, [`drain`] may return while the final byte is still being
+    /// shifted out. In that case, set `delay_after_send` to at least the duration of a
+    /// single character at the configured baud rate, which includes the start, data,
+    /// parity and stop bits. For example, a character with 8 data bits, no parity and 1
+    /// stop bit takes 10 bits, or roughly 87 µs at 115.2 kbit/s.
+    ///
+    /// Because [`write`] waits for the transmission to complete, it blocks even if
+    /// it's configured as non-blocking.
+    ///
+    /// `pin` is set low before `enable_rs485` returns. A transceiver's receiver enable
+    /// (RE) input is typically active-low, and can be connected to the same pin.
+    ///
+    /// [`write`]: #method.write
+    /// [`drain`]: #method.drain
+    pub fn enable_rs485(
+        &mut self,
+        mut pin: OutputPin,
+        delay_before_send: Duration,
+        delay_after_send: Duration,
+    ) {
+        pin.set_low();
+
+        self.inner.rs485 = Some(Rs485 {
+            pin,
+            delay_before_send,
+            delay_after_send,
+        });
+    }
+
+    /// Disables RS-485 direction control, and returns the pin that was used
+    /// to control the transceiver's driver.
+    pub fn disable_rs485(&mut self) -> Option<OutputPin> {
+        self.inner.rs485.take().map(|rs485| rs485.pin)
+    }
+
     /// Returns `true` if [`read`] is configured to block when needed.
     ///
     /// [`read`]: #method.write
@@ -850,6 +1088,46 @@ impl Uart {
         Ok(())
     }
 
+    /// Returns the inter-byte timeout configured by [`set_read_timeout`], or `None`
+    /// if [`read`] uses the settings configured by [`set_read_mode`].
+    ///
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    /// [`read`]: #method.read
+    /// [`set_read_mode`]: #method.set_read_mode
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.inner.read_timeout
+    }
+
+    /// Sets an inter-byte timeout with millisecond resolution for subsequent calls
+    /// to [`read`].
+    ///
+    /// The timeout configured by [`set_read_mode`] relies on the termios `VTIME`
+    /// setting, which uses a 0.1 second resolution. `set_read_timeout` waits for
+    /// incoming data using `poll()` instead, which allows for the shorter timeouts
+    /// needed by request/response protocols such as Modbus RTU.
+    ///
+    /// With a read timeout configured, [`read`] blocks until the provided buffer
+    /// is full, or the `timeout` duration elapses without receiving any additional
+    /// data. The timer is restarted after each received chunk of data. If no data
+    /// is received within `timeout`, [`read`] returns `Ok(0)`. If the device hangs
+    /// up, for instance because a USB to serial adapter is disconnected, [`read`]
+    /// returns an `io::ErrorKind::UnexpectedEof` error.
+    ///
+    /// Setting `timeout` to `Duration::ZERO` removes the read timeout, and
+    /// [`read`] returns to the mode configured by [`set_read_mode`].
+    ///
+    /// By default, no read timeout is configured.
+    ///
+    /// [`read`]: #method.read
+    /// [`set_read_mode`]: #method.set_read_mode
+    pub fn set_read_timeout(&mut self, timeout: Duration) {
+        self.inner.read_timeout = if timeout > Duration::ZERO {
+            Some(timeout)
+        } else {
+            None
+        };
+    }
+
     /// Sets the blocking mode for subsequent calls to [`write`].
     ///
     /// [`write`] operates in one of two modes, depending on the specified
@@ -903,10 +1181,41 @@ impl Uart {
     /// settings configured by [`set_read_mode`]. By default, `read` is configured
     /// as non-blocking.
     ///
+    /// If a read timeout has been configured with [`set_read_timeout`], `read` blocks
+    /// until `buffer` is full, or no additional data is received within the timeout
+    /// duration, regardless of the settings configured by [`set_read_mode`].
+    ///
     /// Returns how many bytes were read.
     ///
     /// [`set_read_mode`]: #method.set_read_mode
+    /// [`set_read_timeout`]: #method.set_read_timeout
     pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        match self.inner.read_timeout {
+            Some(timeout) => self.read_with_timeout(buffer, timeout),
+            None => self.read_device(buffer),
+        }
+    }
+
+    fn read_with_timeout(&mut self, buffer: &mut [u8], timeout: Duration) -> Result<usize> {
+        let mut len = 0;
+
+        while len < buffer.len() {
+            if !termios::poll_input(self.inner.fd, timeout)? {
+                break;
+            }
+
+            // Incoming data may be discarded before it can be read. Return what we
+            // have, rather than polling again with a fresh timeout.
+            match self.try_read(&mut buffer[len..])? {
+                0 => break,
+                bytes_read => len += bytes_read,
+            }
+        }
+
+        Ok(len)
+    }
+
+    fn read_device(&mut self, buffer: &mut [u8]) -> Result<usize> {
         self.inner.device.read(buffer).or_else(|e| {
             if e.kind() == io::ErrorKind::WouldBlock {
                 Ok(0)
@@ -916,16 +1225,147 @@ impl Uart {
         })
     }
 
+    /// Receives any incoming data that's already waiting in the input queue, and stores
+    /// it in `buffer`.
+    ///
+    /// Unlike [`read`], `try_read` never blocks, regardless of the settings configured by
+    /// [`set_read_mode`]. If the input queue is empty, `try_read` returns `Ok(0)` immediately.
+    /// [`input_len`] can be used to check how many bytes are waiting beforehand.
+    ///
+    /// Returns how many bytes were read.
+    ///
+    /// [`read`]: #method.read
+    /// [`set_read_mode`]: #method.set_read_mode
+    /// [`input_len`]: #method.input_len
+    pub fn try_read(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        // If read() is non-blocking, either with_path() or set_read_mode() will have
+        // already enabled O_NONBLOCK.
+        if !self.inner.blocking_read {
+            return self.read_device(buffer);
+        }
+
+        unsafe {
+            libc::fcntl(self.inner.fd, libc::F_SETFL, libc::O_NONBLOCK);
+        }
+
+        let result = self.read_device(buffer);
+
+        unsafe {
+            libc::fcntl(self.inner.fd, libc::F_SETFL, 0);
+        }
+
+        result
+    }
+
+    /// Receives incoming data until `delimiter` is found, and appends it to `buffer`.
+    ///
+    /// `read_until` reads one byte at a time, and stops after `delimiter` has been
+    /// appended to `buffer`, so any data following the delimiter stays in the input queue.
+    ///
+    /// `read_until` follows the settings configured by [`set_read_mode`] and
+    /// [`set_read_timeout`], the same way [`read`] does. If [`read`] is configured as
+    /// non-blocking (default), `read_until` only retrieves data that's already waiting
+    /// in the input queue, and returns immediately if the delimiter isn't found. If
+    /// [`read`] is configured to block, `read_until` waits for incoming data until
+    /// `timeout` elapses. When a read timeout is configured through [`set_read_timeout`],
+    /// `read_until` also stops waiting if no data is received within that period.
+    ///
+    /// Returns how many bytes were appended to `buffer`, including the delimiter. If
+    /// the delimiter isn't found before `read_until` stops waiting, it returns
+    /// `Err(`[`Error::TimedOut`]`)`, and any data received so far is kept in `buffer`.
+    /// If the device hangs up, for instance because a USB to serial adapter is
+    /// disconnected, an `io::ErrorKind::UnexpectedEof` error is returned.
+    ///
+    /// [`set_read_mode`]: #method.set_read_mode
+    /// [`set_read_timeout`]: #method.set_read_timeout
+    /// [`read`]: #method.read
+    /// [`Error::TimedOut`]: enum.Error.html#variant.TimedOut
+    pub fn read_until(
+        &mut self,
+        delimiter: u8,
+        buffer: &mut Vec<u8>,
+        timeout: Duration,
+    ) -> Result<usize> {
+        let start = std::time::Instant::now();
+        let mut len = 0;
+        let mut byte = [0u8; 1];
+
+        loop {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            let wait = if !self.inner.blocking_read {
+                Duration::ZERO
+            } else if let Some(read_timeout) = self.inner.read_timeout {
+                read_timeout.min(remaining)
+            } else {
+                remaining
+            };
+
+            if !termios::poll_input(self.inner.fd, wait)? {
+                return Err(Error::TimedOut);
+            }
+
+            // The device may report incoming data that's discarded before it can be
+            // read, for instance due to a parity error
+            if self.try_read(&mut byte)? == 0 {
+                if wait == Duration::ZERO {
+                    return Err(Error::TimedOut);
+                }
+
+                continue;
+            }
+
+            buffer.push(byte[0]);
+            len += 1;
+
+            if byte[0] == delimiter {
+                return Ok(len);
+            }
+        }
+    }
+
     /// Sends the contents of `buffer` to the external device.
     ///
     /// `write` operates in either blocking or non-blocking mode, depending on the
     /// settings configured by [`set_write_mode`]. By default, `write` is configured
     /// as non-blocking.
     ///
+    /// When RS-485 direction control is enabled, `write` also blocks until all
+    /// data has been transmitted. More information can be found in the
+    /// documentation for [`enable_rs485`].
+    ///
     /// Returns how many bytes were written.
     ///
     /// [`set_write_mode`]: #method.set_write_mode
+    /// [`enable_rs485`]: #method.enable_rs485
     pub fn write(&mut self, buffer: &[u8]) -> Result<usize> {
+        if let Some(rs485) = self.inner.rs485.as_mut() {
+            rs485.pin.set_high();
+
+            if rs485.delay_before_send > Duration::ZERO {
+                thread::sleep(rs485.delay_before_send);
+            }
+        }
+
+        let result = self.write_queue(buffer);
+
+        if let Some(rs485) = self.inner.rs485.as_mut() {
+            // Release the bus only after all data has left the transmitter,
+            // even if the write itself failed
+            let drained = termios::drain(self.inner.fd);
+
+            if rs485.delay_after_send > Duration::ZERO {
+                thread::sleep(rs485.delay_after_send);
+            }
+
+            rs485.pin.set_low();
+
+            drained?;
+        }
+
+        result
+    }
+
+    fn write_queue(&mut self, buffer: &[u8]) -> Result<usize> {
         // We only need to toggle O_NONBLOCK when read() is configured as
         // blocking. If read() is non-blocking, either with_path() or
         // set_read_mode() will have already enabled O_NONBLOCK.
@@ -953,12 +1393,78 @@ impl Uart {
     }
 
     /// Blocks until all data in the output queue has been transmitted.
+    ///
+    /// `drain` can be used to make sure the transmitter is idle before changing the
+    /// bus direction on a half-duplex bus, or before reconfiguring the UART. If `drain`
+    /// is interrupted by a signal, it automatically resumes waiting.
     pub fn drain(&self) -> Result<()> {
         termios::drain(self.inner.fd)
     }
 
     /// Discards all data in the input and/or output queue.
+    ///
+    /// [`Queue::Input`] discards any data that's been received but not read yet,
+    /// [`Queue::Output`] discards any data that's been written but not transmitted yet,
+    /// and [`Queue::Both`] discards the contents of both queues.
+    ///
+    /// [`Queue::Input`]: enum.Queue.html#variant.Input
+    /// [`Queue::Output`]: enum.Queue.html#variant.Output
+    /// [`Queue::Both`]: enum.Queue.html#variant.Both
     pub fn flush(&self, queue_type: Queue) -> Result<()> {
         termios::flush(self.inner.fd, queue_type)
     }
 }
+
+/// Receives incoming data through [`Uart::read`].
+///
+/// `read` follows the settings configured by [`set_read_mode`] or [`set_read_timeout`].
+/// When `read` returns `Ok(0)` because no data was received before the timeout
+/// elapsed, `std::io::BufRead` methods such as `read_line` and `lines` treat it as
+/// the end of the stream. `read_line` returns any partial line received so far,
+/// and `lines` stops iterating. Timeouts and errors other than I/O errors are
+/// returned as an `io::Error`.
+///
+/// Wrapping `Uart` in a `std::io::BufReader` buffers incoming data, so lines can be
+/// read without retrieving one byte at a time. Any data left in the `BufReader`'s
+/// buffer isn't visible to `Uart`.
+///
+/// [`Uart::read`]: struct.Uart.html#method.read
+/// [`set_read_mode`]: struct.Uart.html#method.set_read_mode
+/// [`set_read_timeout`]: struct.Uart.html#method.set_read_timeout
+impl Read for Uart {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Uart::read(self, buf).map_err(into_io_error)
+    }
+}
+
+/// Sends data through [`Uart::write`].
+///
+/// `flush` blocks until all data in the output queue has been transmitted, similar
+/// to [`Uart::drain`].
+///
+/// [`Uart::write`]: struct.Uart.html#method.write
+/// [`Uart::drain`]: struct.Uart.html#method.drain
+impl Write for Uart {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Uart::write(self, buf).map_err(into_io_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.drain().map_err(into_io_error)
+    }
+}
+
+fn into_io_error(err: Error) -> io::Error {
+    match err {
+        Error::Io(err) => err,
+        Error::TimedOut => io::Error::new(io::ErrorKind::TimedOut, err),
+        err => io::Error::new(io::ErrorKind::Other, err),
+    }
+}
+
+/// Returns the raw file descriptor of the underlying `tty` device.
+impl AsRawFd for Uart {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.fd
+    }
+}
@@ -14,13 +14,53 @@ use libc::{CS5, CS6, CS7, CS8, CSIZE, CSTOPB, PARENB, PARODD};
 use libc::{IGNPAR, INPCK, PARMRK};
 use libc::{IXANY, IXOFF, IXON, TCIFLUSH, TCIOFLUSH, TCOFLUSH, VMIN, VSTART, VSTOP, VTIME};
 use libc::{TCIOFF, TCION, TIOCMGET, TIOCM_CTS, TIOCM_DSR, TIOCM_DTR, TIOCM_RTS};
-use libc::{TIOCINQ, TIOCMBIC, TIOCMBIS, TIOCM_CAR, TIOCM_RNG, TIOCOUTQ};
+use libc::{TIOCINQ, TIOCMBIC, TIOCMBIS, TIOCMIWAIT, TIOCM_CAR, TIOCM_RNG, TIOCOUTQ};
 
 use crate::uart::{Error, Parity, ParityCheck, Queue, Result};
 
 const XON: u8 = 17;
 const XOFF: u8 = 19;
 
+// termios2 ioctl requests and flags, used for non-standard line speeds
+const TCGETS2: u32 = 0x802c_542a;
+const TCSETS2: u32 = 0x402c_542b;
+const CBAUD: u32 = 0o010_017;
+const BOTHER: u32 = 0o010_000;
+const IBSHIFT: u32 = 16;
+
+// Mirrors struct termios2. We define it ourselves, because it's not
+// available on every libc target.
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+struct Termios2 {
+    c_iflag: u32,
+    c_oflag: u32,
+    c_cflag: u32,
+    c_lflag: u32,
+    c_line: u8,
+    c_cc: [u8; 19],
+    c_ispeed: u32,
+    c_ospeed: u32,
+}
+
+// Interrupt counters returned by TIOCGICOUNT (serial_icounter_struct)
+#[derive(Debug, Default, Copy, Clone)]
+#[repr(C)]
+pub struct InterruptCounters {
+    pub cts: i32,
+    pub dsr: i32,
+    pub rng: i32,
+    pub dcd: i32,
+    pub rx: i32,
+    pub tx: i32,
+    pub frame: i32,
+    pub overrun: i32,
+    pub parity: i32,
+    pub brk: i32,
+    pub buf_overrun: i32,
+    pub reserved: [i32; 9],
+}
+
 #[cfg(target_env = "gnu")]
 pub fn attributes(fd: c_int) -> Result<termios> {
     let mut attr = termios {
@@ -69,42 +109,30 @@ pub fn set_attributes(fd: c_int, attr: &termios) -> Result<()> {
     Ok(())
 }
 
-pub fn line_speed(fd: c_int) -> Result<u32> {
-    // We can't use termios2 here, because it's not supported by musl
-    Ok(match unsafe { libc::cfgetospeed(&attributes(fd)?) } {
-        B0 => 0,
-        B50 => 50,
-        B75 => 75,
-        B110 => 110,
-        B134 => 134,
-        B150 => 150,
-        B200 => 200,
-        B300 => 300,
-        B600 => 600,
-        B1200 => 1_200,
-        B1800 => 1_800,
-        B2400 => 2_400,
-        B4800 => 4_800,
-        B9600 => 9_600,
-        B19200 => 19_200,
-        B38400 => 38_400,
-        B57600 => 57_600,
-        B115200 => 115_200,
-        B230400 => 230_400,
-        B460800 => 460_800,
-        B500000 => 500_000,
-        B576000 => 576_000,
-        B921600 => 921_600,
-        B1000000 => 1_000_000,
-        B1152000 => 1_152_000,
-        B1500000 => 1_500_000,
-        B2000000 => 2_000_000,
-        B2500000 => 2_500_000,
-        B3000000 => 3_000_000,
-        B3500000 => 3_500_000,
-        B4000000 => 4_000_000,
-        _ => return Err(Error::InvalidValue),
-    })
+fn attributes2(fd: i32) -> Result<Termios2> {
+    let mut attr = Termios2::default();
+
+    parse_retval!(unsafe { libc::ioctl(fd, TCGETS2 as _, &mut attr) })?;
+
+    Ok(attr)
+}
+
+fn set_attributes2(fd: i32, attr: &Termios2) -> Result<()> {
+    parse_retval!(unsafe { libc::ioctl(fd, TCSETS2 as _, attr) }).map_err(|e| {
+        if e.kind() == io::ErrorKind::InvalidInput {
+            Error::InvalidValue
+        } else {
+            Error::Io(e)
+        }
+    })?;
+
+    Ok(())
+}
+
+pub fn line_speed(fd: i32) -> Result<u32> {
+    // The kernel always stores the actual output speed in c_ospeed, even if
+    // it was configured through one of the legacy Bxxx constants
+    Ok(attributes2(fd)?.c_ospeed)
 }
 
 pub fn set_line_speed(fd: c_int, line_speed: u32) -> Result<()> {
@@ -140,10 +168,9 @@ pub fn set_line_speed(fd: c_int, line_speed: u32) -> Result<()> {
         3_000_000 => B3000000,
         3_500_000 => B3500000,
         4_000_000 => B4000000,
-        _ => return Err(Error::InvalidValue),
+        _ => return set_custom_line_speed(fd, line_speed),
     };
 
-    // We can't use termios2 here, because it's not supported by musl
     let mut attr = attributes(fd)?;
 
     parse_retval!(unsafe { libc::cfsetispeed(&mut attr, baud) })?;
@@ -152,6 +179,18 @@ pub fn set_line_speed(fd: c_int, line_speed: u32) -> Result<()> {
     set_attributes(fd, &attr)
 }
 
+// Sets a line speed that doesn't have a Bxxx constant (BOTHER)
+fn set_custom_line_speed(fd: i32, line_speed: u32) -> Result<()> {
+    let mut attr = attributes2(fd)?;
+
+    attr.c_cflag &= !(CBAUD | (CBAUD << IBSHIFT));
+    attr.c_cflag |= BOTHER | (BOTHER << IBSHIFT);
+    attr.c_ispeed = line_speed;
+    attr.c_ospeed = line_speed;
+
+    set_attributes2(fd, &attr)
+}
+
 pub fn parity(fd: c_int) -> Result<Parity> {
     let attr = attributes(fd)?;
 
@@ -380,6 +419,15 @@ pub fn status(fd: c_int) -> Result<c_int> {
     Ok(tiocm)
 }
 
+// Block until any of the modem status input lines change
+pub fn wait_for_status_change(fd: i32) -> Result<i32> {
+    let mask = TIOCM_CTS | TIOCM_DSR | TIOCM_CAR | TIOCM_RNG;
+
+    parse_retval!(unsafe { libc::ioctl(fd, TIOCMIWAIT, mask as usize) })?;
+
+    status(fd)
+}
+
 // Return CTS state
 pub fn cts(fd: c_int) -> Result<bool> {
     let mut tiocm: c_int = 0;
@@ -514,9 +562,14 @@ pub fn flush(fd: c_int, queue_type: Queue) -> Result<()> {
 
 // Wait until all outgoing data has been transmitted
 pub fn drain(fd: c_int) -> Result<()> {
-    parse_retval!(unsafe { libc::tcdrain(fd) })?;
-
-    Ok(())
+    loop {
+        match parse_retval!(unsafe { libc::tcdrain(fd) }) {
+            Ok(_) => return Ok(()),
+            // Keep waiting if tcdrain() was interrupted by a signal handler
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(Error::Io(e)),
+        }
+    }
 }
 
 // Returns the number of bytes waiting in the input queue.
@@ -536,3 +589,43 @@ pub fn output_len(fd: c_int) -> Result<usize> {
 
     Ok(len as usize)
 }
+
+// Waits until incoming data is available, or the timeout elapses. Returns false on timeout,
+// or an error if the device hung up or reported an error without any data left to read.
+pub fn poll_input(fd: i32, timeout: Duration) -> Result<bool> {
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    // Round up, so we don't return early
+    let timeout_ms = (timeout.as_nanos() + 999_999) / 1_000_000;
+
+    loop {
+        match parse_retval!(unsafe {
+            libc::poll(&mut pfd, 1, timeout_ms.min(i32::MAX as u128) as i32)
+        }) {
+            Ok(0) => return Ok(false),
+            Ok(_) if pfd.revents & libc::POLLIN != 0 => return Ok(true),
+            Ok(_) if pfd.revents & libc::POLLHUP != 0 => {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "device hung up",
+                )))
+            }
+            Ok(_) => return Err(Error::Io(io::Error::from_raw_os_error(libc::EIO))),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(Error::Io(e)),
+        }
+    }
+}
+
+// Returns the framing, parity, overrun and break counters
+pub fn line_errors(fd: i32) -> Result<InterruptCounters> {
+    let mut counters = InterruptCounters::default();
+
+    parse_retval!(unsafe { libc::ioctl(fd, libc::TIOCGICOUNT, &mut counters) })?;
+
+    Ok(counters)
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "mock")]
+
+use rppal::gpio::{Gpio, Level};
+use rppal::i2c::I2c;
+use rppal::spi::{Bus, Mode, Segment, SlaveSelect, Spi};
+
+fn enable_mock() {
+    std::env::set_var("RPPAL_MOCK", "1");
+}
+
+#[test]
+fn gpio_levels_and_transitions() {
+    enable_mock();
+
+    let gpio = Gpio::new().unwrap();
+    assert!(gpio.is_mock());
+
+    let input = gpio.get(17).unwrap().into_input_pullup();
+    assert_eq!(input.read(), Level::High);
+
+    gpio.set_mock_input_level(17, Some(Level::Low)).unwrap();
+    assert_eq!(input.read(), Level::Low);
+    gpio.set_mock_input_level(17, None).unwrap();
+    assert_eq!(input.read(), Level::High);
+
+    gpio.take_mock_transitions().unwrap();
+
+    let mut output = gpio.get(18).unwrap().into_output_low();
+    output.set_high();
+    output.set_high();
+    output.toggle();
+
+    assert_eq!(
+        gpio.take_mock_transitions().unwrap(),
+        vec![(18, Level::Low), (18, Level::High), (18, Level::Low)]
+    );
+    assert!(gpio.take_mock_transitions().unwrap().is_empty());
+
+    assert!(output.set_drive_strength(8).is_ok());
+    assert!(output.set_drive_strength(3).is_err());
+    assert!(output.set_drive_strength(18).is_err());
+}
+
+#[test]
+fn i2c_loopback() {
+    enable_mock();
+
+    let mut i2c = I2c::with_bus(1).unwrap();
+    i2c.set_slave_address(0x20).unwrap();
+
+    i2c.write(&[0x10, 0xAA, 0xBB]).unwrap();
+
+    let mut buffer = [0u8; 2];
+    i2c.write_read(&[0x10], &mut buffer).unwrap();
+    assert_eq!(buffer, [0xAA, 0xBB]);
+
+    i2c.smbus_write_word(0x40, 0x1234).unwrap();
+    assert_eq!(i2c.smbus_read_word(0x40).unwrap(), 0x1234);
+    assert_eq!(i2c.smbus_read_byte(0x41).unwrap(), 0x12);
+
+    i2c.smbus_block_write(0x80, &[1, 2, 3]).unwrap();
+    let mut block = [0u8; 8];
+    assert_eq!(i2c.smbus_block_read(0x80, &mut block).unwrap(), 3);
+    assert_eq!(block[..3], [1, 2, 3]);
+
+    // Every slave address has its own registers
+    i2c.set_slave_address(0x21).unwrap();
+    assert_eq!(i2c.smbus_read_byte(0x10).unwrap(), 0);
+}
+
+#[test]
+fn spi_loopback() {
+    enable_mock();
+
+    let mut spi = Spi::new(Bus::Spi0, SlaveSelect::Ss0, 1_000_000, Mode::Mode1).unwrap();
+    assert_eq!(spi.clock_speed().unwrap(), 1_000_000);
+    assert_eq!(spi.mode().unwrap(), Mode::Mode1);
+
+    let write_buffer = [0x01, 0x02, 0x03];
+    let mut read_buffer = [0u8; 3];
+    assert_eq!(spi.transfer(&mut read_buffer, &write_buffer).unwrap(), 3);
+    assert_eq!(read_buffer, write_buffer);
+    assert!(spi.loopback_test().unwrap());
+
+    let mut read_only = [0xFFu8; 2];
+    spi.transfer_segments(&[Segment::with_read(&mut read_only)])
+        .unwrap();
+    assert_eq!(read_only, [0, 0]);
+
+    assert_eq!(spi.write(&write_buffer).unwrap(), 3);
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "mock")]
+
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::io::FromRawFd;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rppal::gpio::{Gpio, Level};
+use rppal::uart::{Parity, Uart};
+
+// Opens a pseudoterminal, and returns the master side and the path to the slave device,
+// which stands in for the serial device
+fn open_pty() -> (File, String) {
+    unsafe {
+        let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+        assert!(master >= 0);
+        assert_eq!(libc::grantpt(master), 0);
+        assert_eq!(libc::unlockpt(master), 0);
+
+        let path = CStr::from_ptr(libc::ptsname(master))
+            .to_string_lossy()
+            .into_owned();
+
+        (File::from_raw_fd(master), path)
+    }
+}
+
+#[test]
+fn rs485_post_transmit_delay() {
+    std::env::set_var("RPPAL_MOCK", "1");
+
+    let delay_after_send = Duration::from_millis(200);
+
+    let (mut master, path) = open_pty();
+    let mut uart = Uart::with_path(path, 115_200, Parity::None, 8, 1).unwrap();
+
+    let gpio = Gpio::new().unwrap();
+    let pin = gpio.get(17).unwrap().into_output();
+    uart.enable_rs485(pin, Duration::ZERO, delay_after_send);
+    gpio.take_mock_transitions().unwrap();
+
+    // Halfway through the post-transmit delay, the driver should still be enabled
+    let observer = {
+        let gpio = gpio.clone();
+        thread::spawn(move || {
+            thread::sleep(delay_after_send / 2);
+            gpio.take_mock_transitions().unwrap()
+        })
+    };
+
+    let start = Instant::now();
+    assert_eq!(uart.write(b"test").unwrap(), 4);
+    let elapsed = start.elapsed();
+
+    assert_eq!(observer.join().unwrap(), vec![(17, Level::High)]);
+    assert_eq!(
+        gpio.take_mock_transitions().unwrap(),
+        vec![(17, Level::Low)]
+    );
+    assert!(elapsed >= delay_after_send);
+
+    let mut buffer = [0u8; 4];
+    master.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer, b"test");
+
+    let pin = uart.disable_rs485().unwrap();
+    assert!(pin.is_set_low());
+}